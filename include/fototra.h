@@ -0,0 +1,313 @@
+/*
+ * C API of fototra, mirroring src/ffi.rs and src/ffi/.
+ *
+ * Every call blocks until the operation completes and works on the global runtime.
+ * Requests and responses are NUL-terminated UTF-8 JSON strings. A string returned
+ * by the library belongs to the caller, who releases it with fototra_string_free.
+ * On failure, fototra_last_error gives the error of the calling thread as JSON:
+ * {"category": ..., "code": ..., "message": ..., "sources": [...], "details": {...}},
+ * the details, like the permission, principal_id and reason of a denial, being optional.
+ *
+ * actor_id is the id of the user performing the call, already authenticated by the host.
+ *
+ * A plugin exports fototra_plugin_abi_version, returning PLUGIN_ABI_VERSION, and
+ * fototra_plugin_create, filling a FototraPluginAdapter (see src/runtime/plugin.rs).
+ */
+
+#ifndef FOTOTRA_H
+#define FOTOTRA_H
+
+/* Generated by cbindgen from cbindgen.toml, do not edit */
+
+/**
+ * Version of the plugin ABI, the structures below, raised on each breaking change
+ */
+#define PLUGIN_ABI_VERSION 3
+
+/**
+ * Outcome of a C API call, following the category of the error
+ */
+typedef enum FototraStatus {
+  FOTOTRA_STATUS_OK = 0,
+  FOTOTRA_STATUS_AUTH = 1,
+  FOTOTRA_STATUS_VALIDATION = 2,
+  FOTOTRA_STATUS_NOT_FOUND = 3,
+  FOTOTRA_STATUS_CONFLICT = 4,
+  FOTOTRA_STATUS_INTERNAL = 5,
+} FototraStatus;
+
+/**
+ * Outcome of a plugin callback
+ */
+typedef enum FototraPluginStatus {
+  FOTOTRA_PLUGIN_STATUS_OK = 0,
+  FOTOTRA_PLUGIN_STATUS_FAILED = 1,
+} FototraPluginStatus;
+
+/**
+ * Health reported by a plugin adapter
+ */
+typedef enum FototraPluginHealth {
+  FOTOTRA_PLUGIN_HEALTH_HEALTHY = 0,
+  FOTOTRA_PLUGIN_HEALTH_DEGRADED = 1,
+  FOTOTRA_PLUGIN_HEALTH_UNHEALTHY = 2,
+} FototraPluginHealth;
+
+/**
+ * Handle on a runtime instance, cloning it shares the same registry and adapters
+ */
+typedef struct Runtime Runtime;
+
+/**
+ * Services of the host given to the `load` callback of a plugin, valid during the call.
+ *
+ * Strings returned by the host are released with its `string_free`.
+ */
+typedef struct FototraPluginHost {
+  const void *context;
+  /**
+   * Value at the dotted path of the configuration as JSON with the secrets redacted,
+   * the whole configuration for an empty path, null if it is not set
+   */
+  char *(*configuration)(const void *context, const char *path);
+  /**
+   * Text of the secret at the dotted path of the configuration, null if it is not set
+   */
+  char *(*secret)(const void *context, const char *path);
+  /**
+   * Register the string in the runtime under the name
+   */
+  enum FototraPluginStatus (*register_string)(const void *context,
+                                              const char *name,
+                                              const char *value);
+  void (*string_free)(char *string);
+} FototraPluginHost;
+
+/**
+ * Adapter of a plugin: callbacks over its opaque handle.
+ *
+ * The callbacks may be called from any thread, even at the same time, and block until
+ * done. The strings returned by `name`, `dependencies` and `configuration_schema` live
+ * as long as the handle; the host releases the messages written to `error` or `reason`
+ * with `string_free` of the adapter. `destroy` releases the handle once the runtime
+ * drops the adapter.
+ */
+typedef struct FototraPluginAdapter {
+  void *handle;
+  const char *(*name)(void *handle);
+  /**
+   * Names of the adapters to start before this one, their number written to `count`
+   */
+  const char *const *(*dependencies)(void *handle, uintptr_t *count);
+  /**
+   * Configuration expected by the adapter as a TOML schema, null without one
+   */
+  const char *(*configuration_schema)(void *handle);
+  enum FototraPluginStatus (*load)(void *handle, const struct FototraPluginHost *host, char **error);
+  enum FototraPluginHealth (*health)(void *handle, char **reason);
+  enum FototraPluginStatus (*shutdown)(void *handle, char **error);
+  void (*string_free)(char *string);
+  void (*destroy)(void *handle);
+} FototraPluginAdapter;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Initialize the global runtime
+ */
+enum FototraStatus fototra_init(void);
+
+/**
+ * Add the in-memory repository adapter
+ */
+enum FototraStatus fototra_add_in_memory_adapter(void);
+
+/**
+ * Add the adapter of the plugin at the given path
+ *
+ * # Safety
+ *
+ * `path` follows the pointer contract of the module
+ */
+enum FototraStatus fototra_add_plugin(const char *path);
+
+/**
+ * Start the added adapters
+ */
+enum FototraStatus fototra_start(void);
+
+/**
+ * Shut the started adapters down
+ */
+enum FototraStatus fototra_shutdown(void);
+
+/**
+ * Error of the last failed call of this thread as JSON, null if it succeeded
+ */
+char *fototra_last_error(void);
+
+/**
+ * Release a string returned by the library
+ *
+ * # Safety
+ *
+ * `string` is null or was returned by the library and not released yet
+ */
+void fototra_string_free(char *string);
+
+/**
+ * Create a user from a `UserAddRequest`, the response is the user
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_create(const char *actor_id, const char *request, char **response);
+
+/**
+ * Update the user of a `UserUpdateRequest`, the response is the user
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_update(const char *actor_id, const char *request, char **response);
+
+/**
+ * Find a user by id, the response is the user
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_find_one(const char *actor_id,
+                                         const char *user_id,
+                                         char **response);
+
+/**
+ * Find users from a `FindRequest`, the response is a page of users
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_find(const char *actor_id, const char *request, char **response);
+
+/**
+ * Delete a user with what depends on it, the response lists what was removed
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_delete(const char *actor_id, const char *user_id, char **response);
+
+/**
+ * Add an email to a user from a `UserInternetAddRequest`, the response is the email
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_internet_create(const char *actor_id,
+                                                const char *request,
+                                                char **response);
+
+/**
+ * Find emails from a `FindRequest`, the response is a page of emails
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_internet_find(const char *actor_id,
+                                              const char *request,
+                                              char **response);
+
+/**
+ * Remove an email of a user
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_internet_delete(const char *actor_id,
+                                                const char *user_id,
+                                                const char *email);
+
+/**
+ * Set the password of a user from a `UserPasswordAddRequest`
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_password_create(const char *actor_id, const char *request);
+
+/**
+ * Check the password of a user, `FOTOTRA_STATUS_OK` when it matches
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_password_match(const char *actor_id,
+                                               const char *user_id,
+                                               const char *password);
+
+/**
+ * Grant a permission from a `UserPermissionAddRequest`, the response is the grant
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_permission_create(const char *actor_id,
+                                                  const char *request,
+                                                  char **response);
+
+/**
+ * Find grants from a `FindRequest`, the response is a page of grants
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_permission_find(const char *actor_id,
+                                                const char *request,
+                                                char **response);
+
+/**
+ * Revoke a permission of a user
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_permission_delete(const char *actor_id,
+                                                  const char *user_id,
+                                                  const char *permission);
+
+/**
+ * List the permissions held by a user, the response gives the grants they come from
+ *
+ * # Safety
+ *
+ * The pointers follow the contract of the `ffi` module
+ */
+enum FototraStatus fototra_user_permission_find_effective(const char *actor_id,
+                                                          const char *user_id,
+                                                          char **response);
+
+/**
+ * Global runtime, for the hosts loading the library
+ */
+const struct Runtime *get_runtime(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FOTOTRA_H */
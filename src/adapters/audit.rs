@@ -0,0 +1,2 @@
+pub mod in_memory;
+pub mod json_lines;
@@ -0,0 +1,41 @@
+use std::{pin::Pin, sync::Arc};
+
+use tokio::sync::RwLock;
+
+use crate::{model::audit_event::AuditEvent, traits::audit_sink_trait::AuditSinkTrait};
+
+#[derive(Debug, Clone)]
+pub struct InMemoryAuditSink {
+    data: Arc<RwLock<Vec<AuditEvent>>>,
+}
+
+impl InMemoryAuditSink {
+    pub fn new() -> Self {
+        Self {
+            data: Arc::new(RwLock::new(Vec::new())),
+        }
+    }
+
+    /// Every recorded event, oldest first
+    pub async fn get_events(&self) -> Vec<AuditEvent> {
+        self.data.read().await.clone()
+    }
+}
+
+impl Default for InMemoryAuditSink {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuditSinkTrait for InMemoryAuditSink {
+    fn record<'a>(
+        &'a self,
+        event: &'a AuditEvent,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            self.data.write().await.push(event.clone());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,53 @@
+use std::{
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, sync::Mutex};
+
+use crate::{model::audit_event::AuditEvent, traits::audit_sink_trait::AuditSinkTrait};
+
+/// Append every event as one JSON document per line
+#[derive(Debug, Clone)]
+pub struct JsonLinesAuditSink {
+    path: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl JsonLinesAuditSink {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AuditSinkTrait for JsonLinesAuditSink {
+    fn record<'a>(
+        &'a self,
+        event: &'a AuditEvent,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            let mut line = serde_json::to_string(event)?;
+            line.push('\n');
+            let _guard = self.lock.lock().await;
+            let failed = |e| anyhow!("failed to append to {:?}: {}", self.path, e);
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await
+                .map_err(failed)?;
+            file.write_all(line.as_bytes()).await.map_err(failed)?;
+            // the write only completes once the file is flushed
+            file.flush().await.map_err(failed)
+        })
+    }
+}
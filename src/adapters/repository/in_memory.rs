@@ -1,19 +1,28 @@
+pub mod database;
+pub mod outbox_repository;
 pub mod permission_repository;
+pub mod transaction;
 pub mod user_internet_repository;
 pub mod user_password_policy_repository;
 pub mod user_password_repository;
 pub mod user_permission_repository;
 pub mod user_repository;
 
+use anyhow::anyhow;
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::adapters::repository::in_memory::database::InMemoryDatabase;
 use crate::adapters::repository::in_memory::permission_repository::InMemoryPermissionRepository;
+use crate::adapters::repository::in_memory::transaction::InMemoryUnitOfWork;
 use crate::adapters::repository::in_memory::user_internet_repository::InMemoryUserInternetRepository;
 use crate::adapters::repository::in_memory::user_password_policy_repository::InMemoryUserPasswordPolicyRepository;
 use crate::adapters::repository::in_memory::user_password_repository::InMemoryUserPasswordRepository;
 use crate::adapters::repository::in_memory::user_permission_repository::InMemoryUserPermissionRepository;
 use crate::adapters::repository::in_memory::user_repository::InMemoryUserRepository;
+use crate::repository::outbox_repository::OutboxRepository;
 use crate::repository::permission_repository::PermissionRepository;
+use crate::repository::unit_of_work::UnitOfWork;
 use crate::repository::user_internet_repository::UserInternetRepository;
 use crate::repository::user_password_policy_repository::UserPasswordPolicyRepository;
 use crate::repository::user_password_repository::UserPasswordRepository;
@@ -24,21 +33,55 @@ use crate::traits::adapter_loader_trait::AdapterLoaderTrait;
 use crate::runtime::Runtime;
 use crate::traits::initialize_trait::InitializeTrait;
 
+const OUTBOX_RELAY_PERIOD: Duration = Duration::from_millis(100);
+
+/// Adapter keeping every table in memory, each instance with its own database
 #[derive(Debug)]
-pub struct InMemoryRepository;
+pub struct InMemoryRepository {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryRepository {
+    pub fn new() -> Self {
+        Self {
+            database: InMemoryDatabase::new(),
+        }
+    }
+
+    pub fn get_database(&self) -> &InMemoryDatabase {
+        &self.database
+    }
+}
+
+impl Default for InMemoryRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl InitializeTrait for InMemoryRepository {
     fn initialize<'a>(
         &'a self,
     ) -> std::pin::Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
         Box::pin(async {
+            Runtime::get_instance()
+                .get::<PermissionRepository>()
+                .await
+                .ok_or_else(|| anyhow!("Cannot get permission repository"))?
+                .initialize()
+                .await?;
             Runtime::get_instance()
                 .get::<UserRepository>()
                 .await
-                .unwrap()
+                .ok_or_else(|| anyhow!("Cannot get user repository"))?
                 .initialize()
+                .await?;
+            Runtime::get_instance()
+                .get::<UserPermissionRepository>()
                 .await
-                .unwrap();
+                .ok_or_else(|| anyhow!("Cannot get user_permission repository"))?
+                .initialize()
+                .await?;
             Ok(())
         })
     }
@@ -53,35 +96,51 @@ impl AdapterLoaderTrait for InMemoryRepository {
         &'a self,
     ) -> std::pin::Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
         Box::pin(async {
+            Runtime::get_instance()
+                .register(OutboxRepository::new(Arc::new(
+                    self.database.get_outbox().clone(),
+                )))
+                .await?;
             Runtime::get_instance()
                 .register(PermissionRepository::new(Arc::new(
-                    InMemoryPermissionRepository::new(),
+                    InMemoryPermissionRepository::new(&self.database),
                 )))
-                .await;
+                .await?;
             Runtime::get_instance()
-                .register(UserRepository::new(Arc::new(InMemoryUserRepository::new())))
-                .await;
+                .register(UserRepository::new(Arc::new(InMemoryUserRepository::new(
+                    &self.database,
+                ))))
+                .await?;
             Runtime::get_instance()
                 .register(UserInternetRepository::new(Arc::new(
-                    InMemoryUserInternetRepository::new(),
+                    InMemoryUserInternetRepository::new(&self.database),
                 )))
-                .await;
+                .await?;
             Runtime::get_instance()
                 .register(UserPermissionRepository::new(Arc::new(
-                    InMemoryUserPermissionRepository::new(),
+                    InMemoryUserPermissionRepository::new(&self.database),
                 )))
-                .await;
+                .await?;
             Runtime::get_instance()
                 .register(UserPasswordPolicyRepository::new(Arc::new(
-                    InMemoryUserPasswordPolicyRepository::new(),
+                    InMemoryUserPasswordPolicyRepository::new(&self.database),
                 )))
-                .await;
+                .await?;
             Runtime::get_instance()
                 .register(UserPasswordRepository::new(Arc::new(
-                    InMemoryUserPasswordRepository::new(),
+                    InMemoryUserPasswordRepository::new(&self.database),
                 )))
+                .await?;
+            Runtime::get_instance()
+                .register(UnitOfWork::new(Arc::new(InMemoryUnitOfWork::new(
+                    &self.database,
+                ))))
+                .await?;
+            self.initialize().await?;
+            Runtime::get_instance()
+                .start_outbox_relay(OUTBOX_RELAY_PERIOD)
                 .await;
-            self.initialize().await
+            Ok(())
         })
     }
 }
@@ -0,0 +1,52 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use tokio::sync::RwLock;
+
+use crate::{
+    adapters::repository::in_memory::{
+        outbox_repository::InMemoryOutboxRepository, user_internet_repository::UserInternetTable,
+        user_password_repository::UserPasswordTable,
+        user_permission_repository::UserPermissionTable, user_repository::UserTable,
+    },
+    model::{password::password_level::PasswordLevel, permission::Permission},
+};
+
+/// Tables of one in-memory adapter, shared by the repositories it creates
+#[derive(Debug, Clone)]
+pub struct InMemoryDatabase {
+    pub(crate) users: Arc<RwLock<UserTable>>,
+    pub(crate) user_internets: Arc<RwLock<UserInternetTable>>,
+    pub(crate) user_passwords: Arc<RwLock<UserPasswordTable>>,
+    pub(crate) user_permissions: Arc<RwLock<UserPermissionTable>>,
+    pub(crate) permissions: Arc<RwLock<HashSet<Permission>>>,
+    pub(crate) password_level: Arc<RwLock<PasswordLevel>>,
+    pub(crate) outbox: InMemoryOutboxRepository,
+}
+
+impl InMemoryDatabase {
+    pub fn new() -> Self {
+        Self {
+            users: Arc::new(RwLock::new(HashMap::new())),
+            user_internets: Arc::new(RwLock::new(HashMap::new())),
+            user_passwords: Arc::new(RwLock::new(HashMap::new())),
+            user_permissions: Arc::new(RwLock::new(HashMap::new())),
+            permissions: Arc::new(RwLock::new(HashSet::new())),
+            password_level: Arc::new(RwLock::new(PasswordLevel::default())),
+            outbox: InMemoryOutboxRepository::new(),
+        }
+    }
+
+    /// Outbox of the database
+    pub fn get_outbox(&self) -> &InMemoryOutboxRepository {
+        &self.outbox
+    }
+}
+
+impl Default for InMemoryDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
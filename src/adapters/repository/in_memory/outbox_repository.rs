@@ -0,0 +1,147 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{pin::Pin, sync::Arc};
+
+use anyhow::anyhow;
+use tokio::sync::{RwLock, RwLockWriteGuard};
+use uuid::Uuid;
+
+use crate::{
+    model::domain_event::{DomainEventEnvelope, ParkedDomainEvent, error::DomainEventError},
+    traits::{
+        domain_event::outbox_repository_trait::OutboxRepositoryTrait,
+        initialize_trait::InitializeTrait,
+    },
+};
+
+/// Step of the relay that can be made to fail once, to exercise redelivery
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboxStep {
+    FindPending,
+    Acknowledge,
+}
+
+#[derive(Debug, Clone)]
+pub struct InMemoryOutboxRepository {
+    data: Arc<RwLock<Vec<DomainEventEnvelope>>>,
+    parked: Arc<RwLock<Vec<ParkedDomainEvent>>>,
+    fail_find_pending: Arc<AtomicBool>,
+    fail_acknowledge: Arc<AtomicBool>,
+}
+
+impl InMemoryOutboxRepository {
+    pub fn new() -> Self {
+        Self::with_data(Arc::new(RwLock::new(Vec::new())))
+    }
+
+    /// Outbox working on the events of a transaction
+    pub(crate) fn with_data(data: Arc<RwLock<Vec<DomainEventEnvelope>>>) -> Self {
+        Self {
+            data,
+            parked: Arc::new(RwLock::new(Vec::new())),
+            fail_find_pending: Arc::new(AtomicBool::new(false)),
+            fail_acknowledge: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Make the next call of the given step fail as if the process had crashed there
+    pub fn fail_next(&self, step: OutboxStep) {
+        match step {
+            OutboxStep::FindPending => self.fail_find_pending.store(true, Ordering::SeqCst),
+            OutboxStep::Acknowledge => self.fail_acknowledge.store(true, Ordering::SeqCst),
+        }
+    }
+
+    /// Lock the outbox so a repository can append events in its own write
+    pub(crate) async fn lock(&self) -> RwLockWriteGuard<'_, Vec<DomainEventEnvelope>> {
+        self.data.write().await
+    }
+}
+
+impl Default for InMemoryOutboxRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutboxRepositoryTrait for InMemoryOutboxRepository {
+    fn append<'a>(
+        &'a self,
+        envelopes: &'a [DomainEventEnvelope],
+    ) -> Pin<Box<dyn Future<Output = Result<(), DomainEventError>> + Send + 'a>> {
+        Box::pin(async move {
+            self.data.write().await.extend_from_slice(envelopes);
+            Ok(())
+        })
+    }
+
+    fn find_pending<'a>(
+        &'a self,
+        limit: u16,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<DomainEventEnvelope>, DomainEventError>> + Send + 'a>>
+    {
+        Box::pin(async move {
+            if self.fail_find_pending.swap(false, Ordering::SeqCst) {
+                return Err(anyhow!("Simulated failure while reading the outbox").into());
+            }
+            let data = self.data.read().await;
+            Ok(data.iter().take(limit as usize).cloned().collect())
+        })
+    }
+
+    fn acknowledge<'a>(
+        &'a self,
+        id: &'a Uuid,
+    ) -> Pin<Box<dyn Future<Output = Result<(), DomainEventError>> + Send + 'a>> {
+        Box::pin(async move {
+            if self.fail_acknowledge.swap(false, Ordering::SeqCst) {
+                return Err(anyhow!("Simulated failure while acknowledging {id}").into());
+            }
+            let mut data = self.data.write().await;
+            match data.iter().position(|e| e.get_id().eq(id)) {
+                Some(index) => {
+                    data.remove(index);
+                    Ok(())
+                }
+                None => Err(DomainEventError::EventNotPending { id: *id }),
+            }
+        })
+    }
+
+    fn park<'a>(
+        &'a self,
+        id: &'a Uuid,
+        reason: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), DomainEventError>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut data = self.data.write().await;
+            match data.iter().position(|e| e.get_id().eq(id)) {
+                Some(index) => {
+                    let envelope = data.remove(index);
+                    self.parked
+                        .write()
+                        .await
+                        .push(ParkedDomainEvent::new(&envelope, reason));
+                    Ok(())
+                }
+                None => Err(DomainEventError::EventNotPending { id: *id }),
+            }
+        })
+    }
+
+    fn find_parked<'a>(
+        &'a self,
+        limit: u16,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<ParkedDomainEvent>, DomainEventError>> + Send + 'a>>
+    {
+        Box::pin(async move {
+            let parked = self.parked.read().await;
+            Ok(parked.iter().take(limit as usize).cloned().collect())
+        })
+    }
+}
+
+impl InitializeTrait for InMemoryOutboxRepository {
+    fn initialize<'a>(&'a self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async { Ok(()) })
+    }
+}
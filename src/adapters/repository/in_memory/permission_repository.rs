@@ -1,14 +1,11 @@
-use std::{
-    collections::HashSet,
-    pin::Pin,
-    sync::{Arc, LazyLock},
-};
+use std::{collections::HashSet, pin::Pin, sync::Arc};
 
 use tokio::sync::RwLock;
 
 use crate::{
+    adapters::repository::in_memory::database::InMemoryDatabase,
     dtos::{find_request::FindRequest, find_response::FindResponse},
-    model::permission::{ALL_PERMISSIONS, Permission, error::PermissionError},
+    model::permission::{ALL_PERMISSIONS, Permission, error::PermissionError, permission_matches},
     traits::{
         find_option_trait::FindOptionTrait, initialize_trait::InitializeTrait,
         permission::permission_repository_trait::PermissionRepositoryTrait,
@@ -16,25 +13,25 @@ use crate::{
     },
 };
 
-static DB: LazyLock<Arc<RwLock<HashSet<Permission>>>> =
-    LazyLock::new(|| Arc::new(RwLock::new(HashSet::new())));
-
 #[derive(Debug, Clone)]
 pub struct InMemoryPermissionRepository {
     data: Arc<RwLock<HashSet<Permission>>>,
 }
 
 impl InMemoryPermissionRepository {
-    pub fn new() -> Self {
+    pub fn new(database: &InMemoryDatabase) -> Self {
         Self {
-            data: Arc::clone(&DB),
+            data: Arc::clone(&database.permissions),
         }
     }
-}
 
-impl Default for InMemoryPermissionRepository {
-    fn default() -> Self {
-        Self::new()
+    /// Check if the pattern, wildcard or not, covers at least one known permission
+    pub async fn covers(&self, pattern: &str) -> bool {
+        self.data
+            .read()
+            .await
+            .iter()
+            .any(|permission| permission_matches(pattern, permission))
     }
 }
 
@@ -114,9 +111,9 @@ impl RepositoryTrait for InMemoryPermissionRepository {
                 .iter()
                 .filter(|k| {
                     let mut found = true;
-
-                    found &= query.eq(*k);
-
+                    if !query.is_empty() {
+                        found &= query.eq(*k);
+                    }
                     found
                 })
                 .cloned()
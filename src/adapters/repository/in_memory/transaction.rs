@@ -0,0 +1,330 @@
+use std::{collections::HashMap, fmt::Debug, hash::Hash, pin::Pin, sync::Arc};
+
+use tokio::sync::{Mutex, RwLock};
+
+use crate::{
+    adapters::repository::in_memory::{
+        database::InMemoryDatabase,
+        outbox_repository::InMemoryOutboxRepository,
+        permission_repository::InMemoryPermissionRepository,
+        user_internet_repository::{InMemoryUserInternetRepository, UserInternetTable},
+        user_password_repository::{InMemoryUserPasswordRepository, UserPasswordTable},
+        user_permission_repository::{InMemoryUserPermissionRepository, UserPermissionTable},
+        user_repository::{InMemoryUserRepository, UserTable},
+    },
+    model::user::UserID,
+    repository::{
+        transaction::{Transaction, error::TransactionError},
+        user_internet_repository::UserInternetRepository,
+        user_password_repository::UserPasswordRepository,
+        user_permission_repository::UserPermissionRepository,
+        user_repository::UserRepository,
+    },
+    traits::{transaction_trait::TransactionTrait, unit_of_work_trait::UnitOfWorkTrait},
+};
+
+/// Tables as they were when the transaction began
+#[derive(Debug)]
+struct Snapshot {
+    users: UserTable,
+    user_internets: UserInternetTable,
+    user_passwords: UserPasswordTable,
+    user_permissions: UserPermissionTable,
+}
+
+/// Transaction working on copies of the tables taken when it began.
+///
+/// Commit applies the rows changed since the snapshot to the shared tables, so
+/// rows written meanwhile by others are kept. It fails without writing anything when
+/// another writer changed one of the rows the transaction changed, took one of the
+/// emails it adds, or deleted a user whose rows it writes.
+#[derive(Debug)]
+pub struct InMemoryTransaction {
+    database: InMemoryDatabase,
+    snapshot: Mutex<Option<Snapshot>>,
+    users: InMemoryUserRepository,
+    user_internets: InMemoryUserInternetRepository,
+    user_passwords: InMemoryUserPasswordRepository,
+    user_permissions: InMemoryUserPermissionRepository,
+    outbox: InMemoryOutboxRepository,
+}
+
+impl InMemoryTransaction {
+    async fn begin(database: &InMemoryDatabase) -> Self {
+        let users = InMemoryUserRepository::new(database);
+        let user_internets = InMemoryUserInternetRepository::new(database);
+        let user_passwords = InMemoryUserPasswordRepository::new(database);
+        let user_permissions = InMemoryUserPermissionRepository::new(database);
+        let snapshot = {
+            let users = users.lock().await;
+            let user_internets = user_internets.lock().await;
+            let user_passwords = user_passwords.lock().await;
+            let user_permissions = user_permissions.lock().await;
+            Snapshot {
+                users: users.clone(),
+                user_internets: user_internets.clone(),
+                user_passwords: user_passwords.clone(),
+                user_permissions: user_permissions.clone(),
+            }
+        };
+        let outbox = InMemoryOutboxRepository::with_data(Arc::new(RwLock::new(Vec::new())));
+        let users = InMemoryUserRepository::with_data(
+            Arc::new(RwLock::new(snapshot.users.clone())),
+            outbox.clone(),
+        );
+        Self {
+            user_internets: InMemoryUserInternetRepository::with_data(
+                Arc::new(RwLock::new(snapshot.user_internets.clone())),
+                users.clone(),
+                outbox.clone(),
+            ),
+            user_passwords: InMemoryUserPasswordRepository::with_data(
+                Arc::new(RwLock::new(snapshot.user_passwords.clone())),
+                users.clone(),
+                outbox.clone(),
+            ),
+            user_permissions: InMemoryUserPermissionRepository::with_data(
+                Arc::new(RwLock::new(snapshot.user_permissions.clone())),
+                InMemoryPermissionRepository::new(database),
+                users.clone(),
+                outbox.clone(),
+            ),
+            database: database.clone(),
+            snapshot: Mutex::new(Some(snapshot)),
+            users,
+            outbox,
+        }
+    }
+}
+
+/// Apply to the shared table the rows inserted, updated or removed since the snapshot
+fn apply<K: Eq + Hash + Clone, V: PartialEq + Clone>(
+    shared: &mut HashMap<K, V>,
+    before: &HashMap<K, V>,
+    after: &HashMap<K, V>,
+) {
+    for key in changed(before, after) {
+        match after.get(key) {
+            Some(value) => shared.insert(key.clone(), value.clone()),
+            None => shared.remove(key),
+        };
+    }
+}
+
+/// Keys of the rows inserted, updated or removed since the snapshot
+fn changed<'a, K: Eq + Hash, V: PartialEq>(
+    before: &'a HashMap<K, V>,
+    after: &'a HashMap<K, V>,
+) -> impl Iterator<Item = &'a K> {
+    before.keys().filter(|key| !after.contains_key(key)).chain(
+        after
+            .iter()
+            .filter(|(key, value)| before.get(key) != Some(value))
+            .map(|(key, _)| key),
+    )
+}
+
+/// Fail if a row changed by the transaction was changed in the shared table too
+fn check_conflicts<K: Eq + Hash + Debug, V: PartialEq>(
+    table: &str,
+    shared: &HashMap<K, V>,
+    before: &HashMap<K, V>,
+    after: &HashMap<K, V>,
+) -> Result<(), TransactionError> {
+    match changed(before, after).find(|key| shared.get(key) != before.get(key)) {
+        Some(key) => Err(TransactionError::Conflict {
+            reason: format!("{table} {key:?} changed since the transaction began"),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Fail if an email added by the transaction is now associated to another user
+fn check_unique_emails(
+    shared: &UserInternetTable,
+    before: &UserInternetTable,
+    after: &UserInternetTable,
+) -> Result<(), TransactionError> {
+    for (user_id, email) in after.keys().filter(|key| !before.contains_key(key)) {
+        if shared
+            .keys()
+            .any(|(other, taken)| taken == email && other != user_id)
+        {
+            return Err(TransactionError::Conflict {
+                reason: format!("email {email} associated to another user meanwhile"),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Fail if a row written by the transaction belongs to a user deleted meanwhile
+fn check_owners<K: Eq + Hash, V: PartialEq>(
+    table: &str,
+    users: (&UserTable, &UserTable, &UserTable),
+    before: &HashMap<K, V>,
+    after: &HashMap<K, V>,
+    owner: impl Fn(&K, &V) -> UserID,
+) -> Result<(), TransactionError> {
+    let (shared, snapshot, written) = users;
+    for (key, value) in after
+        .iter()
+        .filter(|(key, value)| before.get(key) != Some(value))
+    {
+        let user_id = owner(key, value);
+        let created = written.contains_key(&user_id) && !snapshot.contains_key(&user_id);
+        if !created && !shared.contains_key(&user_id) {
+            return Err(TransactionError::Conflict {
+                reason: format!("{table} written for user {user_id} deleted meanwhile"),
+            });
+        }
+    }
+    Ok(())
+}
+
+impl TransactionTrait for InMemoryTransaction {
+    fn get_user_repository(&self) -> UserRepository {
+        UserRepository::new(Arc::new(self.users.clone()))
+    }
+
+    fn get_user_internet_repository(&self) -> UserInternetRepository {
+        UserInternetRepository::new(Arc::new(self.user_internets.clone()))
+    }
+
+    fn get_user_password_repository(&self) -> UserPasswordRepository {
+        UserPasswordRepository::new(Arc::new(self.user_passwords.clone()))
+    }
+
+    fn get_user_permission_repository(&self) -> UserPermissionRepository {
+        UserPermissionRepository::new(Arc::new(self.user_permissions.clone()))
+    }
+
+    fn commit<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), TransactionError>> + Send + 'a>> {
+        Box::pin(async {
+            let snapshot = self
+                .snapshot
+                .lock()
+                .await
+                .take()
+                .ok_or(TransactionError::AlreadyFinished)?;
+            let shared_users = InMemoryUserRepository::new(&self.database);
+            let shared_user_internets = InMemoryUserInternetRepository::new(&self.database);
+            let shared_user_passwords = InMemoryUserPasswordRepository::new(&self.database);
+            let shared_user_permissions = InMemoryUserPermissionRepository::new(&self.database);
+            let shared_outbox = self.database.get_outbox();
+            let mut users = shared_users.lock().await;
+            let mut user_internets = shared_user_internets.lock().await;
+            let mut user_passwords = shared_user_passwords.lock().await;
+            let mut user_permissions = shared_user_permissions.lock().await;
+            let mut outbox = shared_outbox.lock().await;
+            let written_users = self.users.lock().await;
+            let written_user_internets = self.user_internets.lock().await;
+            let written_user_passwords = self.user_passwords.lock().await;
+            let written_user_permissions = self.user_permissions.lock().await;
+            check_conflicts("user", &users, &snapshot.users, &written_users)?;
+            check_conflicts(
+                "user_internet",
+                &user_internets,
+                &snapshot.user_internets,
+                &written_user_internets,
+            )?;
+            check_unique_emails(
+                &user_internets,
+                &snapshot.user_internets,
+                &written_user_internets,
+            )?;
+            check_conflicts(
+                "user_password",
+                &user_passwords,
+                &snapshot.user_passwords,
+                &written_user_passwords,
+            )?;
+            check_conflicts(
+                "user_permission",
+                &user_permissions,
+                &snapshot.user_permissions,
+                &written_user_permissions,
+            )?;
+            let owners = (&*users, &snapshot.users, &*written_users);
+            check_owners(
+                "user_internet",
+                owners,
+                &snapshot.user_internets,
+                &written_user_internets,
+                |(user_id, _), _| *user_id,
+            )?;
+            check_owners(
+                "user_password",
+                owners,
+                &snapshot.user_passwords,
+                &written_user_passwords,
+                |user_id, _| *user_id,
+            )?;
+            check_owners(
+                "user_permission",
+                owners,
+                &snapshot.user_permissions,
+                &written_user_permissions,
+                |_, grant| *grant.get_user_id(),
+            )?;
+            apply(&mut users, &snapshot.users, &written_users);
+            apply(
+                &mut user_internets,
+                &snapshot.user_internets,
+                &written_user_internets,
+            );
+            apply(
+                &mut user_passwords,
+                &snapshot.user_passwords,
+                &written_user_passwords,
+            );
+            apply(
+                &mut user_permissions,
+                &snapshot.user_permissions,
+                &written_user_permissions,
+            );
+            outbox.append(&mut *self.outbox.lock().await);
+            Ok(())
+        })
+    }
+
+    fn rollback<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), TransactionError>> + Send + 'a>> {
+        Box::pin(async {
+            self.snapshot
+                .lock()
+                .await
+                .take()
+                .ok_or(TransactionError::AlreadyFinished)?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct InMemoryUnitOfWork {
+    database: InMemoryDatabase,
+}
+
+impl InMemoryUnitOfWork {
+    pub fn new(database: &InMemoryDatabase) -> Self {
+        Self {
+            database: database.clone(),
+        }
+    }
+}
+
+impl UnitOfWorkTrait for InMemoryUnitOfWork {
+    fn begin<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<Transaction, TransactionError>> + Send + 'a>> {
+        Box::pin(async {
+            Ok(Transaction::new(Arc::new(
+                InMemoryTransaction::begin(&self.database).await,
+            )))
+        })
+    }
+}
@@ -1,19 +1,18 @@
-use std::{
-    collections::HashMap,
-    fmt::Debug,
-    pin::Pin,
-    sync::{Arc, LazyLock},
-};
+use std::{collections::HashMap, fmt::Debug, pin::Pin, sync::Arc};
 
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, RwLockWriteGuard};
 
 use crate::{
-    adapters::repository::in_memory::user_repository::InMemoryUserRepository,
+    adapters::repository::in_memory::{
+        database::InMemoryDatabase, outbox_repository::InMemoryOutboxRepository,
+        user_repository::InMemoryUserRepository,
+    },
     dtos::{
         find_request::FindRequest, find_response::FindResponse,
         user_internet::user_internet_find_request_filter::UserInternetFindRequestFilter,
     },
     model::{
+        domain_event::{DomainEvent, DomainEventEnvelope},
         email_address::EmailAddress,
         user::{UserID, error::UserError},
         user_internet::{UserInternet, error::UserInternetError},
@@ -25,28 +24,40 @@ use crate::{
     },
 };
 
-static DB: LazyLock<Arc<RwLock<HashMap<(UserID, EmailAddress), UserInternet>>>> =
-    LazyLock::new(|| Arc::new(RwLock::new(HashMap::new())));
+pub(crate) type UserInternetTable = HashMap<(UserID, EmailAddress), UserInternet>;
 
 #[derive(Debug, Clone)]
 pub struct InMemoryUserInternetRepository {
-    data: Arc<RwLock<HashMap<(UserID, EmailAddress), UserInternet>>>,
+    data: Arc<RwLock<UserInternetTable>>,
     user_repository: InMemoryUserRepository,
+    outbox: InMemoryOutboxRepository,
 }
 
-impl Default for InMemoryUserInternetRepository {
-    fn default() -> Self {
-        Self::new()
+impl InMemoryUserInternetRepository {
+    pub fn new(database: &InMemoryDatabase) -> Self {
+        Self {
+            data: Arc::clone(&database.user_internets),
+            user_repository: InMemoryUserRepository::new(database),
+            outbox: database.outbox.clone(),
+        }
     }
-}
 
-impl InMemoryUserInternetRepository {
-    pub fn new() -> Self {
+    /// Repository working on the tables of a transaction
+    pub(crate) fn with_data(
+        data: Arc<RwLock<UserInternetTable>>,
+        user_repository: InMemoryUserRepository,
+        outbox: InMemoryOutboxRepository,
+    ) -> Self {
         Self {
-            data: Arc::clone(&DB),
-            user_repository: InMemoryUserRepository::new(),
+            data,
+            user_repository,
+            outbox,
         }
     }
+
+    pub(crate) async fn lock(&self) -> RwLockWriteGuard<'_, UserInternetTable> {
+        self.data.write().await
+    }
 }
 
 impl RepositoryTrait for InMemoryUserInternetRepository {
@@ -72,10 +83,14 @@ impl RepositoryTrait for InMemoryUserInternetRepository {
                 })?;
             let user_internet = UserInternet::new(entity.get_user_id(), entity.get_email());
             let mut data = self.data.write().await;
+            let mut outbox = self.outbox.lock().await;
             data.insert(
                 (*entity.get_user_id(), entity.get_email().clone()),
                 user_internet.clone(),
             );
+            outbox.push(DomainEventEnvelope::new(&DomainEvent::EmailAdded {
+                user_internet: user_internet.clone(),
+            }));
             Ok(user_internet)
         })
     }
@@ -96,8 +111,11 @@ impl RepositoryTrait for InMemoryUserInternetRepository {
     ) -> Pin<Box<dyn std::future::Future<Output = Result<(), UserInternetError>> + Send + 'a>> {
         Box::pin(async move {
             let mut data = self.data.write().await;
-            if data.contains_key(entity_id) {
-                data.remove(entity_id);
+            if let Some(user_internet) = data.remove(entity_id) {
+                let mut outbox = self.outbox.lock().await;
+                outbox.push(DomainEventEnvelope::new(&DomainEvent::EmailRemoved {
+                    user_internet,
+                }));
                 Ok(())
             } else {
                 Err(UserInternetError::EmailNotAssociatedToUser {
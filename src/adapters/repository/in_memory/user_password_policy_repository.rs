@@ -1,11 +1,9 @@
-use std::{
-    pin::Pin,
-    sync::{Arc, LazyLock},
-};
+use std::{pin::Pin, sync::Arc};
 
 use tokio::sync::RwLock;
 
 use crate::{
+    adapters::repository::in_memory::database::InMemoryDatabase,
     dtos::{find_request::FindRequest, find_response::FindResponse},
     model::{
         password::password_level::PasswordLevel,
@@ -17,23 +15,16 @@ use crate::{
     },
 };
 
-static DB: LazyLock<Arc<RwLock<PasswordLevel>>> =
-    LazyLock::new(|| Arc::new(RwLock::new(PasswordLevel::default())));
-
 #[derive(Debug, Clone)]
 pub struct InMemoryUserPasswordPolicyRepository {
     data: Arc<RwLock<PasswordLevel>>,
 }
 
-impl Default for InMemoryUserPasswordPolicyRepository {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl InMemoryUserPasswordPolicyRepository {
-    pub fn new() -> Self {
-        Self { data: DB.clone() }
+    pub fn new(database: &InMemoryDatabase) -> Self {
+        Self {
+            data: database.password_level.clone(),
+        }
     }
 }
 
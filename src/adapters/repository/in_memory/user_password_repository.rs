@@ -1,15 +1,15 @@
-use std::{
-    collections::HashMap,
-    pin::Pin,
-    sync::{Arc, LazyLock},
-};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
 
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, RwLockWriteGuard};
 
 use crate::{
-    adapters::repository::in_memory::user_repository::InMemoryUserRepository,
+    adapters::repository::in_memory::{
+        database::InMemoryDatabase, outbox_repository::InMemoryOutboxRepository,
+        user_repository::InMemoryUserRepository,
+    },
     dtos::{find_request::FindRequest, find_response::FindResponse},
     model::{
+        domain_event::{DomainEvent, DomainEventEnvelope},
         password::Password,
         user::{UserID, error::UserError},
         user_password::{UserPassword, error::UserPasswordError},
@@ -20,28 +20,40 @@ use crate::{
     },
 };
 
-static DB: LazyLock<Arc<RwLock<HashMap<UserID, String>>>> =
-    LazyLock::new(|| Arc::new(RwLock::new(HashMap::new())));
+pub(crate) type UserPasswordTable = HashMap<UserID, String>;
 
 #[derive(Debug, Clone)]
 pub struct InMemoryUserPasswordRepository {
-    data: Arc<RwLock<HashMap<UserID, String>>>,
+    data: Arc<RwLock<UserPasswordTable>>,
     user_repository: InMemoryUserRepository,
+    outbox: InMemoryOutboxRepository,
 }
 
-impl Default for InMemoryUserPasswordRepository {
-    fn default() -> Self {
-        Self::new()
+impl InMemoryUserPasswordRepository {
+    pub fn new(database: &InMemoryDatabase) -> Self {
+        Self {
+            data: database.user_passwords.clone(),
+            user_repository: InMemoryUserRepository::new(database),
+            outbox: database.outbox.clone(),
+        }
     }
-}
 
-impl InMemoryUserPasswordRepository {
-    pub fn new() -> Self {
+    /// Repository working on the tables of a transaction
+    pub(crate) fn with_data(
+        data: Arc<RwLock<UserPasswordTable>>,
+        user_repository: InMemoryUserRepository,
+        outbox: InMemoryOutboxRepository,
+    ) -> Self {
         Self {
-            data: DB.clone(),
-            user_repository: InMemoryUserRepository::new(),
+            data,
+            user_repository,
+            outbox,
         }
     }
+
+    pub(crate) async fn lock(&self) -> RwLockWriteGuard<'_, UserPasswordTable> {
+        self.data.write().await
+    }
 }
 
 impl RepositoryTrait for InMemoryUserPasswordRepository {
@@ -65,18 +77,16 @@ impl RepositoryTrait for InMemoryUserPasswordRepository {
                     ref e => UserPasswordError::Unknown(anyhow::anyhow!(e.to_string())),
                 })?;
             let user_password = UserPassword::new(entity.get_user_id(), entity.get_password());
-            let mut data = self.data.write().await;
-            let _ = user_password
+            let hashed_password = user_password
                 .get_password()
                 .hash()
-                .map_err(UserPasswordError::PasswordError);
-            data.insert(
-                *entity.get_user_id(),
-                user_password
-                    .get_password()
-                    .hash()
-                    .map_err(UserPasswordError::PasswordError)?,
-            );
+                .map_err(UserPasswordError::PasswordError)?;
+            let mut data = self.data.write().await;
+            let mut outbox = self.outbox.lock().await;
+            data.insert(*entity.get_user_id(), hashed_password);
+            outbox.push(DomainEventEnvelope::new(&DomainEvent::PasswordChanged {
+                user_id: *entity.get_user_id(),
+            }));
             Ok(user_password)
         })
     }
@@ -128,6 +138,13 @@ impl RepositoryTrait for InMemoryUserPasswordRepository {
 }
 
 impl UserPasswordRepositoryTrait for InMemoryUserPasswordRepository {
+    fn has_password<'a>(
+        &'a self,
+        user_id: &'a Self::Id,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, Self::Error>> + Send + 'a>> {
+        Box::pin(async { Ok(self.data.read().await.contains_key(user_id)) })
+    }
+
     fn verify_password<'a>(
         &'a self,
         user_id: &'a Self::Id,
@@ -1,13 +1,11 @@
-use std::{
-    collections::HashMap,
-    pin::Pin,
-    sync::{Arc, LazyLock},
-};
+use std::{collections::HashMap, pin::Pin, sync::Arc};
 
-use tokio::sync::RwLock;
+use chrono::{DateTime, Utc};
+use tokio::sync::{RwLock, RwLockWriteGuard};
 
 use crate::{
     adapters::repository::in_memory::{
+        database::InMemoryDatabase, outbox_repository::InMemoryOutboxRepository,
         permission_repository::InMemoryPermissionRepository,
         user_repository::InMemoryUserRepository,
     },
@@ -16,41 +14,59 @@ use crate::{
         user_permission::user_permission_find_request_filter::UserPermissionFindRequestFilter,
     },
     model::{
-        permission::{ALL_PERMISSIONS, Permission},
+        domain_event::{DomainEvent, DomainEventEnvelope},
+        permission::{ALL_PERMISSIONS, Permission, error::PermissionError, is_wildcard},
         user::{DEFAULT_ADMIN_USER, UserID, error::UserError},
         user_permission::{UserPermission, error::UserPermissionError},
     },
     traits::{
-        find_option_trait::FindOptionTrait, initialize_trait::InitializeTrait,
+        find_option_trait::FindOptionTrait,
+        initialize_trait::InitializeTrait,
         repository_trait::RepositoryTrait,
-        user_permission::user_permission_repository::UserPermissionRepositoryTrait,
+        user_permission::user_permission_repository::{
+            PurgedGrants, UserPermissionRepositoryTrait,
+        },
     },
 };
 
-static DB: LazyLock<Arc<RwLock<HashMap<(UserID, Permission), UserPermission>>>> =
-    LazyLock::new(|| Arc::new(RwLock::new(HashMap::new())));
+pub(crate) type UserPermissionTable = HashMap<(UserID, Permission), UserPermission>;
 
 #[derive(Debug, Clone)]
 pub struct InMemoryUserPermissionRepository {
-    data: Arc<RwLock<HashMap<(UserID, Permission), UserPermission>>>,
+    data: Arc<RwLock<UserPermissionTable>>,
     permission_repository: InMemoryPermissionRepository,
     user_repository: InMemoryUserRepository,
+    outbox: InMemoryOutboxRepository,
 }
 
-impl Default for InMemoryUserPermissionRepository {
-    fn default() -> Self {
-        Self::new()
+impl InMemoryUserPermissionRepository {
+    pub fn new(database: &InMemoryDatabase) -> Self {
+        Self {
+            data: database.user_permissions.clone(),
+            permission_repository: InMemoryPermissionRepository::new(database),
+            user_repository: InMemoryUserRepository::new(database),
+            outbox: database.outbox.clone(),
+        }
     }
-}
 
-impl InMemoryUserPermissionRepository {
-    pub fn new() -> Self {
+    /// Repository working on the tables of a transaction
+    pub(crate) fn with_data(
+        data: Arc<RwLock<UserPermissionTable>>,
+        permission_repository: InMemoryPermissionRepository,
+        user_repository: InMemoryUserRepository,
+        outbox: InMemoryOutboxRepository,
+    ) -> Self {
         Self {
-            data: DB.clone(),
-            permission_repository: InMemoryPermissionRepository::new(),
-            user_repository: InMemoryUserRepository::new(),
+            data,
+            permission_repository,
+            user_repository,
+            outbox,
         }
     }
+
+    pub(crate) async fn lock(&self) -> RwLockWriteGuard<'_, UserPermissionTable> {
+        self.data.write().await
+    }
 }
 
 impl RepositoryTrait for InMemoryUserPermissionRepository {
@@ -78,17 +94,39 @@ impl RepositoryTrait for InMemoryUserPermissionRepository {
                     UserError::UserNotExists { id } => UserPermissionError::UserNotExists { id },
                     ref e => UserPermissionError::Unknown(anyhow::anyhow!(e.to_string())),
                 })?;
-            self.permission_repository
-                .find_by_id(&entity.get_permission().to_string())
-                .await
-                .map_err(|e| UserPermissionError::Unknown(anyhow::anyhow!(e.to_string())))?;
-            let user_permission =
-                UserPermission::new(entity.get_user_id(), entity.get_permission());
+            if is_wildcard(entity.get_permission()) {
+                if !self
+                    .permission_repository
+                    .covers(entity.get_permission())
+                    .await
+                {
+                    return Err(UserPermissionError::PermissionNotExists {
+                        permission: entity.get_permission().to_string(),
+                    });
+                }
+            } else {
+                self.permission_repository
+                    .find_by_id(&entity.get_permission().to_string())
+                    .await
+                    .map_err(|e| match e {
+                        PermissionError::PermissionNotExists { name } => {
+                            UserPermissionError::PermissionNotExists { permission: name }
+                        }
+                        ref e => UserPermissionError::Unknown(anyhow::anyhow!(e.to_string())),
+                    })?;
+            }
+            let user_permission = entity.clone();
             let mut data = self.data.write().await;
-            data.insert(
-                (*entity.get_user_id(), entity.get_permission().to_string()),
-                user_permission.clone(),
-            );
+            // a grant, even expired, is never replaced: it must be revoked or purged first
+            let key = (*entity.get_user_id(), entity.get_permission().to_string());
+            if data.contains_key(&key) {
+                return Err(UserPermissionError::PermissionAlreadyAssigned { permission: key.1 });
+            }
+            let mut outbox = self.outbox.lock().await;
+            data.insert(key, user_permission.clone());
+            outbox.push(DomainEventEnvelope::new(&DomainEvent::PermissionGranted {
+                user_permission: user_permission.clone(),
+            }));
             Ok(user_permission)
         })
     }
@@ -115,7 +153,12 @@ impl RepositoryTrait for InMemoryUserPermissionRepository {
         Box::pin(async move {
             let mut data = self.data.write().await;
             if data.contains_key(entity_id) {
+                let mut outbox = self.outbox.lock().await;
                 data.remove(entity_id);
+                outbox.push(DomainEventEnvelope::new(&DomainEvent::PermissionRevoked {
+                    user_id: entity_id.0,
+                    permission: entity_id.1.clone(),
+                }));
                 Ok(())
             } else {
                 Err(UserPermissionError::PermissionAlreadyNotAssigned {
@@ -141,16 +184,20 @@ impl RepositoryTrait for InMemoryUserPermissionRepository {
             let limit = options.get_limit();
             let order_by = options.get_order_by();
             let offset = options.get_offset();
+            let now = Utc::now();
             let data = self.data.read().await;
             let mut filtered: Vec<UserPermission> = data
                 .iter()
-                .filter(|(k, _v)| {
+                .filter(|(k, v)| {
                     let mut found = true;
-                    if query.user_id.is_some() {
-                        found &= query.user_id.unwrap().eq(&k.0);
+                    if let Some(user_id) = query.user_id {
+                        found &= user_id.eq(&k.0);
+                    }
+                    if let Some(permission) = query.permission.as_ref() {
+                        found &= permission.eq(&k.1);
                     }
-                    if query.permission.is_some() {
-                        found &= query.permission.as_ref().unwrap().eq(&k.1);
+                    if !query.include_expired {
+                        found &= !v.is_expired_at(&now);
                     }
                     found
                 })
@@ -193,7 +240,28 @@ impl RepositoryTrait for InMemoryUserPermissionRepository {
     }
 }
 
-impl UserPermissionRepositoryTrait for InMemoryUserPermissionRepository {}
+impl UserPermissionRepositoryTrait for InMemoryUserPermissionRepository {
+    fn purge_expired<'a>(&'a self, instant: &'a DateTime<Utc>) -> PurgedGrants<'a> {
+        Box::pin(async move {
+            let mut data = self.data.write().await;
+            let mut purged: Vec<UserPermission> = data
+                .values()
+                .filter(|user_permission| user_permission.is_expired_at(instant))
+                .cloned()
+                .collect();
+            data.retain(|_, user_permission| !user_permission.is_expired_at(instant));
+            purged.sort();
+            let mut outbox = self.outbox.lock().await;
+            outbox.extend(purged.iter().map(|user_permission| {
+                DomainEventEnvelope::new(&DomainEvent::PermissionRevoked {
+                    user_id: *user_permission.get_user_id(),
+                    permission: user_permission.get_permission().to_string(),
+                })
+            }));
+            Ok(purged)
+        })
+    }
+}
 
 impl InitializeTrait for InMemoryUserPermissionRepository {
     fn initialize<'a>(
@@ -202,10 +270,12 @@ impl InitializeTrait for InMemoryUserPermissionRepository {
         Box::pin(async {
             let default_admin = &DEFAULT_ADMIN_USER;
             let all_permissions = ALL_PERMISSIONS;
+            let mut data = self.data.write().await;
             for perm in all_permissions {
-                self.save(&UserPermission::new(default_admin.get_id(), perm))
-                    .await
-                    .unwrap();
+                data.insert(
+                    (*default_admin.get_id(), perm.to_string()),
+                    UserPermission::new(default_admin.get_id(), perm),
+                );
             }
             Ok(())
         })
@@ -1,10 +1,13 @@
-use std::sync::LazyLock;
 use std::{collections::HashMap, pin::Pin, sync::Arc};
 
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, RwLockWriteGuard};
 use uuid::Uuid;
 
+use crate::adapters::repository::in_memory::database::InMemoryDatabase;
+use crate::adapters::repository::in_memory::outbox_repository::InMemoryOutboxRepository;
+use crate::model::domain_event::{DomainEvent, DomainEventEnvelope};
 use crate::model::user::UserID;
+use crate::model::user::user_status::UserStatus;
 use crate::traits::find_option_trait::FindOptionTrait;
 use crate::{
     dtos::{
@@ -21,25 +24,32 @@ use crate::{
     },
 };
 
-static DB: LazyLock<Arc<RwLock<HashMap<UserID, User>>>> =
-    LazyLock::new(|| Arc::new(RwLock::new(HashMap::new())));
+pub(crate) type UserTable = HashMap<UserID, User>;
 
 #[derive(Debug, Clone)]
 pub struct InMemoryUserRepository {
-    data: Arc<RwLock<HashMap<UserID, User>>>,
+    data: Arc<RwLock<UserTable>>,
+    outbox: InMemoryOutboxRepository,
 }
 
 impl InMemoryUserRepository {
-    pub fn new() -> Self {
+    pub fn new(database: &InMemoryDatabase) -> Self {
         Self {
-            data: Arc::clone(&DB),
+            data: Arc::clone(&database.users),
+            outbox: database.outbox.clone(),
         }
     }
-}
 
-impl Default for InMemoryUserRepository {
-    fn default() -> Self {
-        Self::new()
+    /// Repository working on the tables of a transaction
+    pub(crate) fn with_data(
+        data: Arc<RwLock<UserTable>>,
+        outbox: InMemoryOutboxRepository,
+    ) -> Self {
+        Self { data, outbox }
+    }
+
+    pub(crate) async fn lock(&self) -> RwLockWriteGuard<'_, UserTable> {
+        self.data.write().await
     }
 }
 
@@ -62,7 +72,11 @@ impl RepositoryTrait for InMemoryUserRepository {
             };
             let user = User::new(&user_id, entity.get_firstname(), entity.get_lastname());
             let mut data = self.data.write().await;
+            let mut outbox = self.outbox.lock().await;
             data.insert(user_id, user.clone());
+            outbox.push(DomainEventEnvelope::new(&DomainEvent::UserCreated {
+                user: user.clone(),
+            }));
             Ok(user)
         })
     }
@@ -80,7 +94,11 @@ impl RepositoryTrait for InMemoryUserRepository {
                     id2: *entity.get_id(),
                 })
             } else if data.contains_key(entity_id) {
+                let mut outbox = self.outbox.lock().await;
                 data.insert(*entity_id, entity.clone());
+                outbox.push(DomainEventEnvelope::new(&DomainEvent::UserUpdated {
+                    user: entity.clone(),
+                }));
                 Ok(entity.clone())
             } else {
                 Err(UserError::UserNotExists { id: *entity_id })
@@ -95,7 +113,11 @@ impl RepositoryTrait for InMemoryUserRepository {
         Box::pin(async move {
             let mut data = self.data.write().await;
             if data.contains_key(entity_id) {
+                let mut outbox = self.outbox.lock().await;
                 data.remove(entity_id);
+                outbox.push(DomainEventEnvelope::new(&DomainEvent::UserDeleted {
+                    user_id: *entity_id,
+                }));
                 Ok(())
             } else {
                 Err(UserError::UserNotExists { id: *entity_id })
@@ -134,6 +156,9 @@ impl RepositoryTrait for InMemoryUserRepository {
                             .unwrap()
                             .contains(&v.get_lastname().map_or("", |n| n).to_string());
                     }
+                    if !query.include_deleted {
+                        found &= v.get_status() != UserStatus::Deleted;
+                    }
                     found
                 })
                 .map(|u| u.1.clone())
@@ -171,7 +196,7 @@ impl InitializeTrait for InMemoryUserRepository {
     fn initialize<'a>(&'a self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
         Box::pin(async {
             let user = DEFAULT_ADMIN_USER.clone();
-            self.save(&user).await?;
+            self.data.write().await.insert(*user.get_id(), user);
             Ok(())
         })
     }
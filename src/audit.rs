@@ -0,0 +1,2 @@
+pub mod audit_sink;
+pub mod audit_trail;
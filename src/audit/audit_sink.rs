@@ -0,0 +1,22 @@
+use std::{ops::Deref, sync::Arc};
+
+use crate::traits::audit_sink_trait::AuditSinkTrait;
+
+pub struct AuditSink {
+    inner: Arc<dyn AuditSinkTrait>,
+}
+
+impl AuditSink {
+    pub fn new(audit_sink: Arc<dyn AuditSinkTrait>) -> Self {
+        Self {
+            inner: audit_sink.clone(),
+        }
+    }
+}
+
+impl Deref for AuditSink {
+    type Target = dyn AuditSinkTrait;
+    fn deref(&self) -> &Self::Target {
+        self.inner.deref()
+    }
+}
@@ -0,0 +1,96 @@
+use serde::Serialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{
+    audit::audit_sink::AuditSink,
+    model::audit_event::{AuditEvent, AuditOutcome, AuditTarget},
+    runtime::Runtime,
+    security::error::SecurityError,
+    service::error::{ErrorCategory, ServiceError},
+};
+
+/// Collect what a service call did and record it into the registered audit sink
+#[derive(Debug, Clone)]
+pub struct AuditTrail {
+    action: String,
+    actor: Option<Uuid>,
+    target: Option<AuditTarget>,
+    before: Option<Value>,
+    after: Option<Value>,
+}
+
+impl AuditTrail {
+    pub fn new(action: &str) -> Self {
+        Self {
+            action: action.to_string(),
+            actor: None,
+            target: None,
+            before: None,
+            after: None,
+        }
+    }
+
+    pub fn set_actor(&mut self, actor: Option<&Uuid>) {
+        self.actor = actor.copied();
+    }
+
+    pub fn set_target(&mut self, entity: &str, id: &str) {
+        self.target = Some(AuditTarget::new(entity, id));
+    }
+
+    pub fn set_before<T: Serialize>(&mut self, before: &T) {
+        self.before = serde_json::to_value(before).ok();
+    }
+
+    pub fn set_after<T: Serialize>(&mut self, after: &T) {
+        self.after = serde_json::to_value(after).ok();
+    }
+
+    /// Record the outcome of the call, a sink failure never fails the call itself and
+    /// is reported by `Runtime::health` instead
+    pub async fn record<T>(self, result: &Result<T, ServiceError>) {
+        let runtime = Runtime::get_instance();
+        let Some(audit_sink) = runtime.get::<AuditSink>().await else {
+            return;
+        };
+        let mut actor = self.actor;
+        let outcome = match result {
+            Ok(_) => AuditOutcome::Success,
+            Err(e) if e.get_category() == ErrorCategory::Auth => {
+                if actor.is_none() {
+                    actor = e
+                        .get::<SecurityError>()
+                        .and_then(|e| e.get_principal_id().copied());
+                }
+                AuditOutcome::Denied {
+                    code: e.get_code().to_string(),
+                    message: e.to_string(),
+                }
+            }
+            Err(e) => AuditOutcome::Failed {
+                code: e.get_code().to_string(),
+                message: e.to_string(),
+            },
+        };
+        let event = AuditEvent::new(
+            actor.as_ref(),
+            &self.action,
+            self.target.as_ref(),
+            self.before.as_ref(),
+            self.after.as_ref(),
+            &outcome,
+        );
+        match audit_sink.record(&event).await {
+            Ok(()) => runtime.clear_failure("audit sink").await,
+            Err(e) => {
+                runtime
+                    .report_failure(
+                        "audit sink",
+                        &format!("cannot record audit event {}: {e:#}", event.get_id()),
+                    )
+                    .await
+            }
+        }
+    }
+}
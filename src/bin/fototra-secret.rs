@@ -0,0 +1,169 @@
+//! Encrypt, decrypt and edit the secret files of the configuration.
+//!
+//! The key is read from `FOTOTRA_SECRET_KEY` (base64) or from the file at
+//! `FOTOTRA_SECRET_KEY_FILE`, like the runtime does when loading the configuration.
+
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, ExitCode},
+};
+
+use anyhow::{Context, anyhow, bail};
+use fototra::configuration::encryption::{SecretKey, is_encrypted};
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+const USAGE: &str = "Usage: fototra-secret <command>
+
+Commands:
+  generate-key      print a new base64 key
+  encrypt <file>    encrypt the TOML file in place
+  decrypt <file>    print the decrypted content of the file
+  edit <file>       edit the decrypted content with $EDITOR, then encrypt it again
+
+The key comes from FOTOTRA_SECRET_KEY, or from the file at FOTOTRA_SECRET_KEY_FILE.";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["generate-key"] => generate_key(),
+        ["encrypt", file] => encrypt(Path::new(file)),
+        ["decrypt", file] => decrypt(Path::new(file)),
+        ["edit", file] => edit(Path::new(file)),
+        ["help" | "--help" | "-h"] => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("fototra-secret: {e:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn generate_key() -> anyhow::Result<()> {
+    println!("{}", SecretKey::generate().to_base64().as_str());
+    Ok(())
+}
+
+fn encrypt(file: &Path) -> anyhow::Result<()> {
+    let content = read(file)?;
+    if is_encrypted(&content) {
+        bail!("{} is already encrypted", file.display());
+    }
+    check_toml(file, &content)?;
+    write_atomically(file, &key()?.encrypt(&content)?)
+}
+
+fn decrypt(file: &Path) -> anyhow::Result<()> {
+    let content = read(file)?;
+    if !is_encrypted(&content) {
+        bail!("{} is not encrypted", file.display());
+    }
+    print!("{}", key()?.decrypt(file, &content)?.as_str());
+    Ok(())
+}
+
+/// Edit a decrypted copy kept in a file only the user can read, a new file starting empty
+fn edit(file: &Path) -> anyhow::Result<()> {
+    let key = key()?;
+    let content = if file.exists() {
+        let content = read(file)?;
+        if !is_encrypted(&content) {
+            bail!("{} is not encrypted, run encrypt first", file.display());
+        }
+        key.decrypt(file, &content)?
+    } else {
+        Zeroizing::new(String::new())
+    };
+    let copy = env::temp_dir().join(format!("fototra-secret-{}.toml", Uuid::new_v4()));
+    let result = create_private(&copy)
+        .and_then(|mut handle| Ok(handle.write_all(content.as_bytes())?))
+        .and_then(|()| run_editor(&copy))
+        .and_then(|()| read(&copy));
+    if copy.exists() {
+        // overwrite the plain copy before removing it
+        let length = fs::metadata(&copy)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        let _ = fs::write(&copy, vec![0_u8; length as usize]);
+        let _ = fs::remove_file(&copy);
+    }
+    let edited = result?;
+    if edited == content {
+        eprintln!("{} unchanged", file.display());
+        return Ok(());
+    }
+    check_toml(file, &edited)?;
+    write_atomically(file, &key.encrypt(&edited)?)
+}
+
+fn key() -> anyhow::Result<SecretKey> {
+    SecretKey::from_vars("FOTOTRA", &env::vars().collect())?
+        .ok_or_else(|| anyhow!("set FOTOTRA_SECRET_KEY or FOTOTRA_SECRET_KEY_FILE"))
+}
+
+fn read(file: &Path) -> anyhow::Result<Zeroizing<String>> {
+    Ok(Zeroizing::new(fs::read_to_string(file).with_context(
+        || format!("cannot read {}", file.display()),
+    )?))
+}
+
+fn check_toml(file: &Path, content: &str) -> anyhow::Result<()> {
+    toml::from_str::<toml::Table>(content)
+        .map(|_| ())
+        .with_context(|| format!("{} is not valid TOML, nothing written", file.display()))
+}
+
+fn create_private(path: &Path) -> anyhow::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options
+        .open(path)
+        .with_context(|| format!("cannot create {}", path.display()))
+}
+
+fn run_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().ok_or_else(|| anyhow!("the editor is empty"))?;
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .with_context(|| format!("cannot run {editor}"))?;
+    if !status.success() {
+        bail!("{editor} exited with {status}, nothing written");
+    }
+    Ok(())
+}
+
+/// Replace the file only once the new content is fully written
+fn write_atomically(file: &Path, content: &str) -> anyhow::Result<()> {
+    let mut temporary = PathBuf::from(file);
+    temporary.set_file_name(format!(
+        ".{}.{}",
+        file.file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default(),
+        Uuid::new_v4()
+    ));
+    fs::write(&temporary, content)
+        .with_context(|| format!("cannot write {}", temporary.display()))?;
+    fs::rename(&temporary, file).with_context(|| format!("cannot replace {}", file.display()))
+}
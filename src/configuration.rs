@@ -1,41 +1,50 @@
-use anyhow::anyhow;
-use chrono::{DateTime, Utc};
-use std::{collections::HashMap, env, fs::File, io::Read, path::Path};
-use toml::Value;
+mod de;
+pub mod diff;
+pub mod encryption;
+pub mod error;
+mod export;
+pub mod layer;
+pub mod loader;
+pub mod merge;
+pub mod schema;
+pub mod secret;
+pub mod watch;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use toml::{Value, value::Datetime};
+
+use crate::configuration::{
+    de::ConfigurationDeserializer, error::ConfigurationError, secret::Secret,
+};
 
 //use crate::traits::Repository;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Configuration {
     Map(HashMap<String, Configuration>),
     Array(Vec<Configuration>),
     String(String),
+    /// Redacted when formatted, from a secret file or declared secret by a schema
+    Secret(Secret),
     Int(i64),
     Float(f64),
     Bool(bool),
     DateTime(DateTime<Utc>),
+    /// Date and time without offset, as written in the file
+    LocalDateTime(NaiveDateTime),
+    LocalDate(NaiveDate),
+    LocalTime(NaiveTime),
 }
 
 impl Configuration {
+    /// Merge the upper value into this one, tables being merged key by key and any
+    /// other value replaced
     pub fn merge(&self, setting_value: &Configuration) -> Configuration {
-        match (self, setting_value) {
-            (Configuration::Map(old_map), Configuration::Map(new_map)) => {
-                let mut hashmap = HashMap::new();
-                for (k, v) in old_map {
-                    if new_map.contains_key(k) {
-                        hashmap.insert(k.clone(), v.merge(new_map.get(k).unwrap()));
-                    } else {
-                        hashmap.insert(k.clone(), v.clone());
-                    }
-                }
-                for (k, v) in new_map {
-                    if !old_map.contains_key(k) {
-                        hashmap.insert(k.clone(), v.clone());
-                    }
-                }
-                Configuration::Map(hashmap)
-            }
-            _ => setting_value.clone(),
+        match self.merge_with(setting_value, &HashMap::new()) {
+            Ok(merged) => merged,
+            Err(_) => unreachable!("only a merge strategy can refuse a value"),
         }
     }
 
@@ -45,86 +54,223 @@ impl Configuration {
             _ => None,
         }
     }
-}
 
-impl From<Value> for Configuration {
-    fn from(value: Value) -> Self {
-        if value.is_table() {
-            let mut hashmap = HashMap::new();
-            for (k, v) in value.as_table().unwrap() {
-                hashmap.insert(k.clone(), Self::from(v.clone()));
+    /// Value at the dotted path (`conf1.array1.foo`), array items being addressed by index
+    pub fn get_path(&self, path: &str) -> Option<&Configuration> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        path.split('.').try_fold(self, |value, key| match value {
+            Configuration::Map(hashmap) => hashmap.get(key),
+            Configuration::Array(array) => key.parse::<usize>().ok().and_then(|i| array.get(i)),
+            _ => None,
+        })
+    }
+
+    pub fn get_str(&self, path: &str) -> Result<&str, ConfigurationError> {
+        self.get_typed(path, "string", |value| match value {
+            Configuration::String(value) => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn get_secret(&self, path: &str) -> Result<&Secret, ConfigurationError> {
+        self.get_typed(path, "secret", |value| match value {
+            Configuration::Secret(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    pub fn get_int(&self, path: &str) -> Result<i64, ConfigurationError> {
+        self.get_typed(path, "integer", |value| match value {
+            Configuration::Int(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    /// Float at the path, an integer being converted
+    pub fn get_float(&self, path: &str) -> Result<f64, ConfigurationError> {
+        self.get_typed(path, "float", |value| match value {
+            Configuration::Float(value) => Some(*value),
+            Configuration::Int(value) => Some(*value as f64),
+            _ => None,
+        })
+    }
+
+    pub fn get_bool(&self, path: &str) -> Result<bool, ConfigurationError> {
+        self.get_typed(path, "boolean", |value| match value {
+            Configuration::Bool(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_datetime(&self, path: &str) -> Result<DateTime<Utc>, ConfigurationError> {
+        self.get_typed(path, "datetime", |value| match value {
+            Configuration::DateTime(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_local_datetime(&self, path: &str) -> Result<NaiveDateTime, ConfigurationError> {
+        self.get_typed(path, "local datetime", |value| match value {
+            Configuration::LocalDateTime(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_local_date(&self, path: &str) -> Result<NaiveDate, ConfigurationError> {
+        self.get_typed(path, "local date", |value| match value {
+            Configuration::LocalDate(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    pub fn get_local_time(&self, path: &str) -> Result<NaiveTime, ConfigurationError> {
+        self.get_typed(path, "local time", |value| match value {
+            Configuration::LocalTime(value) => Some(*value),
+            _ => None,
+        })
+    }
+
+    /// Deserialize the subtree at the dotted path, errors naming the path of the
+    /// offending value
+    pub fn get_as<T: DeserializeOwned>(&self, path: &str) -> Result<T, ConfigurationError> {
+        let value = self
+            .get_path(path)
+            .ok_or_else(|| ConfigurationError::MissingKey {
+                key: path.to_string(),
+            })?;
+        T::deserialize(ConfigurationDeserializer::new(value, path)).map_err(|e| {
+            ConfigurationError::Deserialize {
+                key: e.path.unwrap_or_else(|| path.to_string()),
+                message: e.message,
             }
-            Self::Map(hashmap)
-        } else if value.is_array() {
-            Self::Array(
-                value
-                    .as_array()
-                    .unwrap()
-                    .iter()
-                    .map(|v| Self::from(v.clone()))
-                    .collect(),
-            )
-        } else if value.is_bool() {
-            Self::Bool(value.as_bool().unwrap())
-        } else if value.is_str() {
-            Self::String(value.as_str().unwrap().to_string())
-        } else if value.is_integer() {
-            Self::Int(value.as_integer().unwrap())
-        } else if value.is_float() {
-            Self::Float(value.as_float().unwrap())
-        } else if value.is_datetime() {
-            let dt_str = value.as_datetime().unwrap().to_string();
-            let fixed_dt = DateTime::parse_from_rfc3339(&dt_str).unwrap();
-            Self::DateTime(fixed_dt.to_utc())
-        } else {
-            panic!("Unhandle toml type")
+        })
+    }
+
+    /// Name of the type of the value, for the error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Configuration::Map(_) => "table",
+            Configuration::Array(_) => "array",
+            Configuration::String(_) => "string",
+            Configuration::Secret(_) => "secret",
+            Configuration::Int(_) => "integer",
+            Configuration::Float(_) => "float",
+            Configuration::Bool(_) => "boolean",
+            Configuration::DateTime(_) => "datetime",
+            Configuration::LocalDateTime(_) => "local datetime",
+            Configuration::LocalDate(_) => "local date",
+            Configuration::LocalTime(_) => "local time",
         }
     }
+
+    fn get_typed<'a, T>(
+        &'a self,
+        path: &str,
+        expected: &'static str,
+        convert: impl Fn(&'a Configuration) -> Option<T>,
+    ) -> Result<T, ConfigurationError> {
+        let value = self
+            .get_path(path)
+            .ok_or_else(|| ConfigurationError::MissingKey {
+                key: path.to_string(),
+            })?;
+        convert(value).ok_or_else(|| ConfigurationError::InvalidType {
+            key: path.to_string(),
+            expected,
+            found: value.type_name(),
+        })
+    }
 }
 
-pub(crate) fn load_configuration() -> Configuration {
-    let current_exe_dir = env::current_exe().unwrap();
-    let parent_dir = current_exe_dir.parent().unwrap();
-    let mut config_path = parent_dir.join("config.toml");
-    let mut secret_path = parent_dir.join("secret.toml");
-    let pwd = env::var("PWD").unwrap();
-    let current_dir = Path::new(&pwd);
-
-    eprintln!("config path: {:?}", config_path);
-    eprintln!("secret path: {:?}", secret_path);
-    if !config_path.exists() {
-        config_path = current_dir.join("config.toml");
-    }
-    if !secret_path.exists() {
-        secret_path = current_dir.join("secret.toml");
-    }
-    eprintln!("config path: {:?}", config_path);
-    eprintln!("secret path: {:?}", secret_path);
-    let mut config = File::open(&config_path)
-        .map_err(|e| anyhow!("failed to load config.toml: {:?}", e))
-        .unwrap();
-    let mut secret = File::open(&secret_path)
-        .map_err(|e| anyhow!("failed to load secret.toml: {:?}", e))
-        .unwrap();
-
-    let config_content = &mut String::new();
-    let secret_content = &mut String::new();
-
-    config.read_to_string(config_content).unwrap();
-    secret.read_to_string(secret_content).unwrap();
-
-    let parsed_config: Value = toml::from_str(config_content).unwrap();
-    let parsed_secret: Value = toml::from_str(secret_content).unwrap();
-
-    Configuration::from(parsed_config.clone()).merge(&Configuration::from(parsed_secret.clone()))
+impl TryFrom<Value> for Configuration {
+    type Error = ConfigurationError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        Self::from_value(value, "")
+    }
 }
 
-// impl Repository<String> for Configuration {
-//     fn delete(&self, repository_connexion: &String) {
+impl Configuration {
+    fn from_value(value: Value, key: &str) -> Result<Self, ConfigurationError> {
+        let child = |name: &str| child_path(key, name);
+        Ok(match value {
+            Value::Table(table) => Self::Map(
+                table
+                    .into_iter()
+                    .map(|(k, v)| Ok((k.clone(), Self::from_value(v, &child(&k))?)))
+                    .collect::<Result<_, ConfigurationError>>()?,
+            ),
+            Value::Array(array) => Self::Array(
+                array
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, v)| Self::from_value(v, &child(&i.to_string())))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Boolean(value) => Self::Bool(value),
+            Value::String(value) => Self::String(value),
+            Value::Integer(value) => Self::Int(value),
+            Value::Float(value) => Self::Float(value),
+            Value::Datetime(value) => {
+                Self::from_datetime(&value).ok_or_else(|| ConfigurationError::UnsupportedValue {
+                    path: None,
+                    key: key.to_string(),
+                    value: value.to_string(),
+                })?
+            }
+        })
+    }
 
-//     }
+    /// Offset datetime, local datetime, local date or local time, depending on the
+    /// parts written
+    fn from_datetime(value: &Datetime) -> Option<Self> {
+        let date = value.date.map(|date| {
+            NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())
+        });
+        let time = value.time.map(|time| {
+            // chrono holds a leap second as a second 59 longer than a second
+            let (second, nanosecond) = match time.second {
+                60 => (59, time.nanosecond + 1_000_000_000),
+                second => (second, time.nanosecond),
+            };
+            NaiveTime::from_hms_nano_opt(
+                time.hour.into(),
+                time.minute.into(),
+                second.into(),
+                nanosecond,
+            )
+        });
+        Some(match (date, time, value.offset) {
+            (Some(_), Some(_), Some(_)) => Self::DateTime(
+                DateTime::parse_from_rfc3339(&value.to_string())
+                    .ok()?
+                    .to_utc(),
+            ),
+            (Some(date), Some(time), None) => Self::LocalDateTime(date?.and_time(time?)),
+            (Some(date), None, None) => Self::LocalDate(date?),
+            (None, Some(time), None) => Self::LocalTime(time?),
+            _ => return None,
+        })
+    }
+}
+
+/// Format of a local datetime, the one of TOML and of the serde implementation of chrono
+pub(crate) const LOCAL_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.f";
 
-//     fn save(&self, repository_connexion: &String) {
+/// Dotted path of a key under the parent path, the root path being empty
+pub(crate) fn child_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{parent}.{key}")
+    }
+}
 
-//     }
-// }
+/// Value nested under the keys, the first key being the outermost
+pub(crate) fn nest<S: AsRef<str>>(keys: &[S], value: Configuration) -> Configuration {
+    keys.iter().rev().fold(value, |value, key| {
+        Configuration::Map(HashMap::from([(key.as_ref().to_string(), value)]))
+    })
+}
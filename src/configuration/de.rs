@@ -0,0 +1,290 @@
+use std::{
+    collections::hash_map,
+    fmt::{self, Display, Formatter},
+    iter::Enumerate,
+    slice,
+};
+
+use serde::de::{
+    self, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor,
+    value::BorrowedStrDeserializer,
+};
+
+use crate::configuration::{Configuration, LOCAL_DATETIME_FORMAT, child_path};
+
+/// Failure of a deserialization, with the dotted path of the value it failed on
+#[derive(Debug)]
+pub(crate) struct DeserializeError {
+    pub(crate) path: Option<String>,
+    pub(crate) message: String,
+}
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl de::Error for DeserializeError {
+    fn custom<T: Display>(message: T) -> Self {
+        Self {
+            path: None,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Scalars read from a secret, which holds the text of the value of a secret file
+macro_rules! deserialize_secret_scalar {
+    ($($method:ident => $visit:ident($kind:ty)),* $(,)?) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            match self.value {
+                Configuration::Secret(value) => {
+                    let result = value
+                        .expose()
+                        .parse::<$kind>()
+                        .map_err(de::Error::custom)
+                        .and_then(|value| visitor.$visit(value));
+                    Self::locate(&self.path, result)
+                }
+                _ => self.deserialize_any(visitor),
+            }
+        }
+    )*};
+}
+
+/// Deserializer of a configuration value found at the given path
+pub(crate) struct ConfigurationDeserializer<'de> {
+    value: &'de Configuration,
+    path: String,
+}
+
+impl<'de> ConfigurationDeserializer<'de> {
+    pub(crate) fn new(value: &'de Configuration, path: &str) -> Self {
+        Self {
+            value,
+            path: path.to_string(),
+        }
+    }
+
+    /// Blame this value for an error not raised by one of its children
+    fn locate<T>(path: &str, result: Result<T, DeserializeError>) -> Result<T, DeserializeError> {
+        result.map_err(|mut e| {
+            e.path.get_or_insert_with(|| path.to_string());
+            e
+        })
+    }
+}
+
+impl<'de> Deserializer<'de> for ConfigurationDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let result = match self.value {
+            Configuration::Map(map) => visitor.visit_map(MapDeserializer {
+                iter: map.iter(),
+                value: None,
+                path: &self.path,
+            }),
+            Configuration::Array(array) => visitor.visit_seq(SeqDeserializer {
+                iter: array.iter().enumerate(),
+                path: &self.path,
+            }),
+            Configuration::String(value) => visitor.visit_borrowed_str(value),
+            Configuration::Secret(value) => visitor.visit_borrowed_str(value.expose()),
+            Configuration::Int(value) => visitor.visit_i64(*value),
+            Configuration::Float(value) => visitor.visit_f64(*value),
+            Configuration::Bool(value) => visitor.visit_bool(*value),
+            Configuration::DateTime(value) => visitor.visit_string(value.to_rfc3339()),
+            Configuration::LocalDateTime(value) => {
+                visitor.visit_string(value.format(LOCAL_DATETIME_FORMAT).to_string())
+            }
+            Configuration::LocalDate(value) => visitor.visit_string(value.to_string()),
+            Configuration::LocalTime(value) => visitor.visit_string(value.to_string()),
+        };
+        Self::locate(&self.path, result)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// A unit variant is a string, any other variant a table with the variant as only key
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let result = match self.value {
+            Configuration::String(variant) => {
+                visitor.visit_enum(BorrowedStrDeserializer::<DeserializeError>::new(variant))
+            }
+            Configuration::Map(map) if map.len() == 1 => {
+                let (variant, value) = map.iter().next().unwrap();
+                visitor.visit_enum(EnumDeserializer {
+                    variant,
+                    value,
+                    path: &self.path,
+                })
+            }
+            _ => Err(de::Error::custom(
+                "expected a string or a table with a single key for an enum",
+            )),
+        };
+        Self::locate(&self.path, result)
+    }
+
+    deserialize_secret_scalar! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i64(i64),
+        deserialize_i16 => visit_i64(i64),
+        deserialize_i32 => visit_i64(i64),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u64(u64),
+        deserialize_u16 => visit_u64(u64),
+        deserialize_u32 => visit_u64(u64),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_f32 => visit_f64(f64),
+        deserialize_f64 => visit_f64(f64),
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct MapDeserializer<'de, 'a> {
+    iter: hash_map::Iter<'de, String, Configuration>,
+    value: Option<(&'de String, &'de Configuration)>,
+    path: &'a str,
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer<'de, '_> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some((key, value));
+                seed.deserialize(BorrowedStrDeserializer::new(key))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (key, value) = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before its key"))?;
+        seed.deserialize(ConfigurationDeserializer::new(
+            value,
+            &child_path(self.path, key),
+        ))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct SeqDeserializer<'de, 'a> {
+    iter: Enumerate<slice::Iter<'de, Configuration>>,
+    path: &'a str,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer<'de, '_> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        self.iter
+            .next()
+            .map(|(index, value)| {
+                seed.deserialize(ConfigurationDeserializer::new(
+                    value,
+                    &child_path(self.path, &index.to_string()),
+                ))
+            })
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct EnumDeserializer<'de, 'a> {
+    variant: &'de String,
+    value: &'de Configuration,
+    path: &'a str,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer<'de, '_> {
+    type Error = DeserializeError;
+    type Variant = ConfigurationDeserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let variant = seed.deserialize(BorrowedStrDeserializer::new(self.variant))?;
+        Ok((
+            variant,
+            ConfigurationDeserializer::new(self.value, &child_path(self.path, self.variant)),
+        ))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ConfigurationDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_any(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_any(visitor)
+    }
+}
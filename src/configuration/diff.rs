@@ -0,0 +1,123 @@
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display, Formatter},
+};
+
+use serde::Serialize;
+
+use crate::configuration::{Configuration, child_path};
+
+/// Difference at a dotted path between two configurations
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "difference", rename_all = "snake_case")]
+pub enum ConfigurationDifference {
+    Added {
+        path: String,
+        value: Configuration,
+    },
+    Removed {
+        path: String,
+        value: Configuration,
+    },
+    Changed {
+        path: String,
+        old: Configuration,
+        new: Configuration,
+    },
+}
+
+impl ConfigurationDifference {
+    pub fn get_path(&self) -> &str {
+        match self {
+            ConfigurationDifference::Added { path, .. }
+            | ConfigurationDifference::Removed { path, .. }
+            | ConfigurationDifference::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// One line per difference, the secrets redacted
+impl Display for ConfigurationDifference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let json = |value: &Configuration| serde_json::to_string(value).map_err(|_| fmt::Error);
+        match self {
+            ConfigurationDifference::Added { path, value } => {
+                write!(f, "+ {path} = {}", json(value)?)
+            }
+            ConfigurationDifference::Removed { path, value } => {
+                write!(f, "- {path} = {}", json(value)?)
+            }
+            ConfigurationDifference::Changed { path, old, new } => {
+                write!(f, "~ {path} = {} -> {}", json(old)?, json(new)?)
+            }
+        }
+    }
+}
+
+impl Configuration {
+    /// Differences from this configuration to the other, by path, tables and arrays
+    /// being compared item by item
+    pub fn diff(&self, other: &Configuration) -> Vec<ConfigurationDifference> {
+        let mut differences = Vec::new();
+        diff_at("", self, other, &mut differences);
+        differences
+    }
+
+    /// Whether both configurations hold the same values, a float being equal to itself
+    /// even when it is NaN, unlike `==`
+    pub fn same_as(&self, other: &Configuration) -> bool {
+        self.diff(other).is_empty()
+    }
+}
+
+fn diff_at(
+    path: &str,
+    old: &Configuration,
+    new: &Configuration,
+    differences: &mut Vec<ConfigurationDifference>,
+) {
+    match (old, new) {
+        (Configuration::Map(old), Configuration::Map(new)) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let child = child_path(path, key);
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_at(&child, old, new, differences),
+                    (Some(old), None) => differences.push(ConfigurationDifference::Removed {
+                        path: child,
+                        value: old.clone(),
+                    }),
+                    (None, Some(new)) => differences.push(ConfigurationDifference::Added {
+                        path: child,
+                        value: new.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Configuration::Array(old), Configuration::Array(new)) => {
+            for index in 0..old.len().max(new.len()) {
+                let child = child_path(path, &index.to_string());
+                match (old.get(index), new.get(index)) {
+                    (Some(old), Some(new)) => diff_at(&child, old, new, differences),
+                    (Some(old), None) => differences.push(ConfigurationDifference::Removed {
+                        path: child,
+                        value: old.clone(),
+                    }),
+                    (None, Some(new)) => differences.push(ConfigurationDifference::Added {
+                        path: child,
+                        value: new.clone(),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Configuration::Float(old), Configuration::Float(new)) if old.total_cmp(new).is_eq() => {}
+        (old, new) if old != new => differences.push(ConfigurationDifference::Changed {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
@@ -0,0 +1,132 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chacha20poly1305::{
+    ChaCha20Poly1305, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng},
+};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::configuration::error::ConfigurationError;
+
+/// First line of an encrypted file, followed by the base64 of the nonce and the ciphertext
+pub const ENCRYPTED_HEADER: &str = "# fototra-encrypted:v1";
+
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 12;
+
+/// ChaCha20-Poly1305 key of the encrypted configuration files, wiped when dropped
+#[derive(Clone)]
+pub struct SecretKey(Zeroizing<[u8; KEY_LENGTH]>);
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey([REDACTED])")
+    }
+}
+
+impl SecretKey {
+    pub fn generate() -> Self {
+        let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+        Self(Zeroizing::new(key.into()))
+    }
+
+    /// Key written in base64, surrounding whitespace ignored
+    pub fn from_base64(encoded: &str) -> Result<Self, ConfigurationError> {
+        let invalid = |reason: &str| ConfigurationError::InvalidSecretKey {
+            reason: reason.to_string(),
+        };
+        let bytes = Zeroizing::new(
+            STANDARD
+                .decode(encoded.trim())
+                .map_err(|_| invalid("not base64"))?,
+        );
+        let key: [u8; KEY_LENGTH] = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| invalid("expected 32 bytes"))?;
+        Ok(Self(Zeroizing::new(key)))
+    }
+
+    /// Key given in base64 by `<PREFIX>_SECRET_KEY`, or read from the file at
+    /// `<PREFIX>_SECRET_KEY_FILE`, none if neither is set
+    pub fn from_vars(
+        prefix: &str,
+        vars: &HashMap<String, String>,
+    ) -> Result<Option<Self>, ConfigurationError> {
+        if let Some(key) = vars.get(&format!("{prefix}_SECRET_KEY")) {
+            return Self::from_base64(key).map(Some);
+        }
+        let Some(path) = vars.get(&format!("{prefix}_SECRET_KEY_FILE")) else {
+            return Ok(None);
+        };
+        let key = Zeroizing::new(fs::read_to_string(path).map_err(|source| {
+            ConfigurationError::Read {
+                path: PathBuf::from(path),
+                source,
+            }
+        })?);
+        Self::from_base64(&key).map(Some)
+    }
+
+    pub fn to_base64(&self) -> Zeroizing<String> {
+        Zeroizing::new(STANDARD.encode(*self.0))
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new_from_slice(&*self.0).expect("the key has the length of the cipher")
+    }
+
+    /// Encrypted file holding the content, with a fresh nonce
+    pub fn encrypt(&self, content: &str) -> Result<String, ConfigurationError> {
+        let cipher = self.cipher();
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher.encrypt(&nonce, content.as_bytes()).map_err(|_| {
+            ConfigurationError::InvalidSecretKey {
+                reason: "encryption failed".to_string(),
+            }
+        })?;
+        let mut payload = nonce.to_vec();
+        payload.extend(ciphertext);
+        Ok(format!(
+            "{ENCRYPTED_HEADER}\n{}\n",
+            STANDARD.encode(payload)
+        ))
+    }
+
+    /// Content of the encrypted file at the path, failing if it was not encrypted with
+    /// this key or was modified
+    pub fn decrypt(
+        &self,
+        path: &Path,
+        encrypted: &str,
+    ) -> Result<Zeroizing<String>, ConfigurationError> {
+        let failed = || ConfigurationError::Decrypt {
+            path: path.to_path_buf(),
+        };
+        let payload = encrypted
+            .strip_prefix(ENCRYPTED_HEADER)
+            .and_then(|payload| STANDARD.decode(payload.trim()).ok())
+            .filter(|payload| payload.len() > NONCE_LENGTH)
+            .ok_or_else(failed)?;
+        let (nonce, ciphertext) = payload.split_at(NONCE_LENGTH);
+        let nonce: [u8; NONCE_LENGTH] = nonce.try_into().map_err(|_| failed())?;
+        let cipher = self.cipher();
+        let content = cipher
+            .decrypt(&Nonce::from(nonce), ciphertext)
+            .map_err(|_| failed())?;
+        // converted in place, the bytes wiped if they are not text
+        String::from_utf8(content).map(Zeroizing::new).map_err(|e| {
+            e.into_bytes().zeroize();
+            failed()
+        })
+    }
+}
+
+pub fn is_encrypted(content: &str) -> bool {
+    content.starts_with(ENCRYPTED_HEADER)
+}
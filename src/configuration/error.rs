@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::configuration::{merge::MergeStrategy, schema::ConfigurationViolation};
+
+#[derive(Debug, Error)]
+pub enum ConfigurationError {
+    #[error("Cannot find the current directory: {source}")]
+    CurrentDirectory {
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Required configuration file {} not found, looked for {}", path.display(), considered.iter().map(|path| path.display().to_string()).collect::<Vec<_>>().join(", "))]
+    MissingFile {
+        path: PathBuf,
+        considered: Vec<PathBuf>,
+    },
+    #[error("Cannot read {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Invalid TOML in {}:{line}:{column}: {message}", path.display())]
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    #[error("Unsupported value {value} at {key}{}", path.as_ref().map(|path| format!(" in {}", path.display())).unwrap_or_default())]
+    UnsupportedValue {
+        path: Option<PathBuf>,
+        key: String,
+        value: String,
+    },
+    #[error("No configuration value at {key}")]
+    MissingKey { key: String },
+    #[error("Expected type {expected} at {key}, found {found}")]
+    InvalidType {
+        key: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    #[error("Invalid configuration at {key}: {message}")]
+    Deserialize { key: String, message: String },
+    #[error("Invalid configuration: {}", violations.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    Invalid {
+        violations: Vec<ConfigurationViolation>,
+    },
+    #[error("Secret {key} cannot be read from {}: {source}", path.display())]
+    SecretFile {
+        key: String,
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Secret {key} refers to the environment variable {variable} which is not set")]
+    SecretVariable { key: String, variable: String },
+    #[error("{} is encrypted but no secret key is given", path.display())]
+    MissingSecretKey { path: PathBuf },
+    #[error("Invalid secret key: {reason}")]
+    InvalidSecretKey { reason: String },
+    #[error("{} cannot be decrypted, the key is wrong or the file was modified", path.display())]
+    Decrypt { path: PathBuf },
+    #[error("Merge strategy {strategy:?} at {key} does not fit a {lower} overridden by a {upper}")]
+    MergeStrategy {
+        key: String,
+        strategy: MergeStrategy,
+        lower: &'static str,
+        upper: &'static str,
+    },
+    #[error("The configuration cannot be written as {format}: {reason}")]
+    Export {
+        format: &'static str,
+        reason: String,
+    },
+}
@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Serialize, Serializer};
+use toml::Value;
+
+use crate::configuration::{
+    Configuration, LOCAL_DATETIME_FORMAT, error::ConfigurationError, secret::Secret,
+};
+
+/// Secrets are written redacted, tables with sorted keys
+impl Serialize for Configuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Configuration::Map(map) => map.iter().collect::<BTreeMap<_, _>>().serialize(serializer),
+            Configuration::Array(array) => array.serialize(serializer),
+            Configuration::String(value) => value.serialize(serializer),
+            Configuration::Secret(value) => value.serialize(serializer),
+            Configuration::Int(value) => value.serialize(serializer),
+            Configuration::Float(value) => value.serialize(serializer),
+            Configuration::Bool(value) => value.serialize(serializer),
+            Configuration::DateTime(value) => rfc3339(value).serialize(serializer),
+            Configuration::LocalDateTime(value) => value
+                .format(LOCAL_DATETIME_FORMAT)
+                .to_string()
+                .serialize(serializer),
+            Configuration::LocalDate(value) => value.to_string().serialize(serializer),
+            Configuration::LocalTime(value) => value.to_string().serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for Secret {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+impl Configuration {
+    /// TOML value with the secrets redacted, the datetimes staying datetimes
+    pub fn to_toml_value(&self) -> Value {
+        match self {
+            Configuration::Map(map) => Value::Table(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), value.to_toml_value()))
+                    .collect(),
+            ),
+            Configuration::Array(array) => {
+                Value::Array(array.iter().map(Configuration::to_toml_value).collect())
+            }
+            Configuration::String(value) => Value::String(value.clone()),
+            Configuration::Secret(value) => Value::String(value.to_string()),
+            Configuration::Int(value) => Value::Integer(*value),
+            Configuration::Float(value) => Value::Float(*value),
+            Configuration::Bool(value) => Value::Boolean(*value),
+            Configuration::DateTime(value) => datetime(rfc3339(value)),
+            Configuration::LocalDateTime(value) => {
+                datetime(value.format(LOCAL_DATETIME_FORMAT).to_string())
+            }
+            Configuration::LocalDate(value) => datetime(value.to_string()),
+            Configuration::LocalTime(value) => datetime(value.to_string()),
+        }
+    }
+
+    /// TOML document of the configuration with the secrets redacted, which must be a table
+    pub fn to_toml(&self) -> Result<String, ConfigurationError> {
+        let failed = |reason: String| ConfigurationError::Export {
+            format: "toml",
+            reason,
+        };
+        match self.to_toml_value() {
+            Value::Table(table) => {
+                toml::to_string_pretty(&table).map_err(|e| failed(e.to_string()))
+            }
+            _ => Err(failed(format!(
+                "expected a table, found a {}",
+                self.type_name()
+            ))),
+        }
+    }
+
+    /// Pretty JSON of the configuration with the secrets redacted
+    pub fn to_json(&self) -> Result<String, ConfigurationError> {
+        serde_json::to_string_pretty(self).map_err(|e| ConfigurationError::Export {
+            format: "json",
+            reason: e.to_string(),
+        })
+    }
+}
+
+fn rfc3339(value: &DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// TOML datetime written as the string, the string itself if TOML does not read it
+fn datetime(value: String) -> Value {
+    value
+        .parse()
+        .map(Value::Datetime)
+        .unwrap_or(Value::String(value))
+}
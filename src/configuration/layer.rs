@@ -0,0 +1,102 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::configuration::{Configuration, child_path};
+
+/// Source of configuration values, the later ones overriding the earlier ones
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "layer", rename_all = "snake_case")]
+pub enum ConfigurationLayer {
+    Defaults,
+    File { path: PathBuf },
+    Profile { profile: String, path: PathBuf },
+    Secret { path: PathBuf },
+    Environment { variable: String },
+}
+
+/// File looked for while loading the configuration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConfigurationFile {
+    path: PathBuf,
+    required: bool,
+    found: bool,
+}
+
+impl ConfigurationFile {
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    pub fn is_found(&self) -> bool {
+        self.found
+    }
+}
+
+/// Layer each key of the merged configuration comes from, by dotted path, and the
+/// files considered to build it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConfigurationOrigins {
+    origins: HashMap<String, ConfigurationLayer>,
+    files: Vec<ConfigurationFile>,
+}
+
+impl ConfigurationOrigins {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Files in the order they were looked for
+    pub fn get_files(&self) -> &[ConfigurationFile] {
+        &self.files
+    }
+
+    pub(crate) fn consider(&mut self, path: &Path, required: bool, found: bool) {
+        self.files.push(ConfigurationFile {
+            path: path.to_path_buf(),
+            required,
+            found,
+        });
+    }
+
+    /// Layer of the value at the dotted path, or of the closest parent set as a whole
+    pub fn get(&self, path: &str) -> Option<&ConfigurationLayer> {
+        let mut path = path;
+        loop {
+            if let Some(layer) = self.origins.get(path) {
+                return Some(layer);
+            }
+            path = &path[..path.rfind('.')?];
+        }
+    }
+
+    /// Record the values of a layer merged over the previous ones
+    pub(crate) fn record(&mut self, configuration: &Configuration, layer: &ConfigurationLayer) {
+        self.record_at("", configuration, layer);
+    }
+
+    fn record_at(&mut self, path: &str, configuration: &Configuration, layer: &ConfigurationLayer) {
+        match configuration {
+            Configuration::Map(map) if !map.is_empty() => {
+                // a table replaces a plain value set by a previous layer
+                self.origins.remove(path);
+                for (key, value) in map {
+                    self.record_at(&child_path(path, key), value, layer);
+                }
+            }
+            Configuration::Map(_) => {}
+            _ => {
+                let prefix = format!("{path}.");
+                self.origins.retain(|key, _| !key.starts_with(&prefix));
+                self.origins.insert(path.to_string(), layer.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use toml::Value;
+use zeroize::Zeroizing;
+
+use crate::configuration::{
+    Configuration,
+    encryption::{SecretKey, is_encrypted},
+    error::ConfigurationError,
+    layer::{ConfigurationLayer, ConfigurationOrigins},
+    merge::MergeStrategy,
+    nest,
+    schema::{ConfigurationSchema, conceal_declared, validate},
+    secret::conceal,
+};
+
+const DEFAULT_ENV_PREFIX: &str = "FOTOTRA";
+
+/// Load the configuration from its layers, in increasing precedence: the defaults,
+/// `config.toml`, `config.<profile>.toml`, `secret.toml`, `secret.<profile>.toml` and
+/// the environment variables `<PREFIX>__SECTION__KEY`.
+///
+/// The files are searched next to the executable, then in the current directory,
+/// unless a path is given with `with_path` or `<PREFIX>_CONFIG`. The profile comes
+/// from `with_profile` or `<PREFIX>_PROFILE`.
+#[derive(Debug, Clone)]
+pub struct ConfigurationLoader {
+    defaults: Option<Configuration>,
+    path: Option<PathBuf>,
+    profile: Option<String>,
+    env_prefix: String,
+    env_vars: Option<Vec<(String, String)>>,
+    required: bool,
+    schemas: Vec<ConfigurationSchema>,
+    secret_key: Option<SecretKey>,
+    merge_strategies: HashMap<String, MergeStrategy>,
+}
+
+impl Default for ConfigurationLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConfigurationLoader {
+    pub fn new() -> Self {
+        Self {
+            defaults: None,
+            path: None,
+            profile: None,
+            env_prefix: DEFAULT_ENV_PREFIX.to_string(),
+            env_vars: None,
+            required: true,
+            schemas: Vec::new(),
+            secret_key: None,
+            merge_strategies: HashMap::new(),
+        }
+    }
+
+    pub fn with_defaults(mut self, defaults: Configuration) -> Self {
+        self.defaults = Some(defaults);
+        self
+    }
+
+    /// Base file to load instead of the `config.toml` found by the search
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.path = Some(path.to_path_buf());
+        self
+    }
+
+    /// Whether a missing base file is an error, the other files being optional
+    pub fn with_required(mut self, required: bool) -> Self {
+        self.required = required;
+        self
+    }
+
+    pub fn with_profile(mut self, profile: &str) -> Self {
+        self.profile = Some(profile.to_string());
+        self
+    }
+
+    pub fn with_env_prefix(mut self, prefix: &str) -> Self {
+        self.env_prefix = prefix.to_string();
+        self
+    }
+
+    /// Schema the configuration is validated against, its defaults being merged under
+    /// the ones of `with_defaults`
+    pub fn with_schema(mut self, schema: ConfigurationSchema) -> Self {
+        self.schemas.push(schema);
+        self
+    }
+
+    /// Variables to read instead of the environment of the process
+    pub fn with_env_vars(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env_vars = Some(vars.into_iter().collect());
+        self
+    }
+
+    /// Key decrypting the encrypted files instead of `<PREFIX>_SECRET_KEY` or the file
+    /// at `<PREFIX>_SECRET_KEY_FILE`
+    pub fn with_secret_key(mut self, key: SecretKey) -> Self {
+        self.secret_key = Some(key);
+        self
+    }
+
+    /// How each layer combines the value at the dotted path with the layers below,
+    /// see `Configuration::merge_with`
+    pub fn with_merge_strategy(mut self, path: &str, strategy: MergeStrategy) -> Self {
+        self.merge_strategies.insert(path.to_string(), strategy);
+        self
+    }
+
+    fn secret_key(
+        &self,
+        vars: &HashMap<String, String>,
+    ) -> Result<Option<SecretKey>, ConfigurationError> {
+        match &self.secret_key {
+            Some(key) => Ok(Some(key.clone())),
+            None => SecretKey::from_vars(&self.env_prefix, vars),
+        }
+    }
+
+    /// `load` on the blocking pool of tokio, which async code uses instead as the files
+    /// are read with blocking calls
+    pub async fn load_async(
+        &self,
+    ) -> Result<(Configuration, ConfigurationOrigins), ConfigurationError> {
+        let loader = self.clone();
+        tokio::task::spawn_blocking(move || loader.load())
+            .await
+            .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))
+    }
+
+    /// Merge every layer and record where each key comes from, with the files considered
+    pub fn load(&self) -> Result<(Configuration, ConfigurationOrigins), ConfigurationError> {
+        let vars: HashMap<String, String> = match &self.env_vars {
+            Some(vars) => vars.iter().cloned().collect(),
+            None => env::vars().collect(),
+        };
+        let mut origins = ConfigurationOrigins::new();
+        let path = match self.path.clone().or_else(|| {
+            vars.get(&format!("{}_CONFIG", self.env_prefix))
+                .map(PathBuf::from)
+        }) {
+            Some(path) => path,
+            None => {
+                let candidates = search_candidates("config.toml")?;
+                let found = candidates.iter().position(|path| path.exists());
+                for (i, candidate) in candidates.iter().enumerate() {
+                    // the last candidate is the base file when none exists
+                    if found.map_or(i + 1 < candidates.len(), |found| i < found) {
+                        origins.consider(candidate, self.required, false);
+                    }
+                }
+                match found {
+                    Some(found) => candidates[found].clone(),
+                    None if self.required => {
+                        return Err(ConfigurationError::MissingFile {
+                            path: candidates[candidates.len() - 1].clone(),
+                            considered: candidates,
+                        });
+                    }
+                    None => candidates[candidates.len() - 1].clone(),
+                }
+            }
+        };
+        let directory = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let profile = self
+            .profile
+            .clone()
+            .or_else(|| vars.get(&format!("{}_PROFILE", self.env_prefix)).cloned());
+
+        let secret_key = || self.secret_key(&vars);
+
+        let mut layers = Vec::new();
+        let mut defaults: Option<Configuration> = None;
+        for layer in self
+            .schemas
+            .iter()
+            .map(ConfigurationSchema::defaults)
+            .chain(self.defaults.clone())
+        {
+            defaults = Some(match defaults {
+                Some(defaults) => defaults.merge_with(&layer, &self.merge_strategies)?,
+                None => layer,
+            });
+        }
+        if let Some(defaults) = defaults {
+            layers.push((defaults, ConfigurationLayer::Defaults));
+        }
+        let mut files = vec![(
+            path.clone(),
+            self.required,
+            ConfigurationLayer::File { path: path.clone() },
+        )];
+        if let Some(profile) = &profile {
+            let path = directory.join(format!("config.{profile}.toml"));
+            files.push((
+                path.clone(),
+                false,
+                ConfigurationLayer::Profile {
+                    profile: profile.clone(),
+                    path,
+                },
+            ));
+        }
+        let mut secrets = vec![directory.join("secret.toml")];
+        if let Some(profile) = &profile {
+            secrets.push(directory.join(format!("secret.{profile}.toml")));
+        }
+        for path in secrets {
+            files.push((path.clone(), false, ConfigurationLayer::Secret { path }));
+        }
+        for (path, required, layer) in files {
+            let found = path.exists();
+            origins.consider(&path, required, found);
+            if found {
+                let mut configuration = read_file(&path, &secret_key)?;
+                if let ConfigurationLayer::Secret { .. } = layer {
+                    let mut referenced = Vec::new();
+                    configuration = conceal(configuration, "", &directory, &vars, &mut referenced)?;
+                    for path in referenced {
+                        origins.consider(&path, true, true);
+                    }
+                }
+                layers.push((configuration, layer));
+            } else if required {
+                return Err(ConfigurationError::MissingFile {
+                    considered: vec![path.clone()],
+                    path,
+                });
+            }
+        }
+        let env_prefix = format!("{}__", self.env_prefix);
+        let mut variables: Vec<_> = vars
+            .iter()
+            .filter(|(variable, _)| variable.starts_with(&env_prefix))
+            .collect();
+        variables.sort();
+        for (variable, value) in variables {
+            let keys: Vec<String> = variable[env_prefix.len()..]
+                .split("__")
+                .map(str::to_lowercase)
+                .collect();
+            if keys.iter().any(String::is_empty) {
+                continue;
+            }
+            layers.push((
+                nest(&keys, parse_env_value(value)),
+                ConfigurationLayer::Environment {
+                    variable: variable.clone(),
+                },
+            ));
+        }
+
+        let mut configuration = Configuration::Map(HashMap::new());
+        for (layer, source) in &layers {
+            origins.record(layer, source);
+            configuration = configuration.merge_with(layer, &self.merge_strategies)?;
+        }
+        let configuration = conceal_declared(&self.schemas, configuration);
+        let violations = validate(&self.schemas, &configuration, &layers);
+        if !violations.is_empty() {
+            return Err(ConfigurationError::Invalid { violations });
+        }
+        Ok((configuration, origins))
+    }
+}
+
+/// Places where the file is looked for: next to the executable, then in the current directory
+fn search_candidates(name: &str) -> Result<Vec<PathBuf>, ConfigurationError> {
+    let mut candidates = Vec::new();
+    if let Some(directory) = env::current_exe().ok().as_deref().and_then(Path::parent) {
+        candidates.push(directory.join(name));
+    }
+    let current_directory = match env::var_os("PWD") {
+        Some(pwd) => PathBuf::from(pwd),
+        None => {
+            env::current_dir().map_err(|source| ConfigurationError::CurrentDirectory { source })?
+        }
+    };
+    candidates.push(current_directory.join(name));
+    Ok(candidates)
+}
+
+/// Parse the file, decrypting it with the key when it is encrypted
+fn read_file(
+    path: &Path,
+    secret_key: &dyn Fn() -> Result<Option<SecretKey>, ConfigurationError>,
+) -> Result<Configuration, ConfigurationError> {
+    // wiped once parsed, the file may hold secrets
+    let mut content =
+        Zeroizing::new(
+            fs::read_to_string(path).map_err(|source| ConfigurationError::Read {
+                path: path.to_path_buf(),
+                source,
+            })?,
+        );
+    if is_encrypted(&content) {
+        let key = secret_key()?.ok_or_else(|| ConfigurationError::MissingSecretKey {
+            path: path.to_path_buf(),
+        })?;
+        content = key.decrypt(path, &content)?;
+    }
+    let value = toml::from_str::<Value>(&content).map_err(|e| {
+        let (line, column) = e
+            .span()
+            .map(|span| line_column(&content, span.start))
+            .unwrap_or((0, 0));
+        ConfigurationError::Parse {
+            path: path.to_path_buf(),
+            line,
+            column,
+            message: e.message().to_string(),
+        }
+    })?;
+    Configuration::try_from(value).map_err(|e| match e {
+        ConfigurationError::UnsupportedValue { key, value, .. } => {
+            ConfigurationError::UnsupportedValue {
+                path: Some(path.to_path_buf()),
+                key,
+                value,
+            }
+        }
+        e => e,
+    })
+}
+
+/// One-based line and column of the byte offset
+fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before
+        .rsplit('\n')
+        .next()
+        .map_or(0, |line| line.chars().count())
+        + 1;
+    (line, column)
+}
+
+/// Boolean and numbers keep their type, anything else is a string
+fn parse_env_value(raw: &str) -> Configuration {
+    match toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+    {
+        Some(value @ (Value::Boolean(_) | Value::Integer(_) | Value::Float(_))) => {
+            Configuration::try_from(value)
+                .unwrap_or_else(|_| Configuration::String(raw.to_string()))
+        }
+        _ => Configuration::String(raw.to_string()),
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::configuration::{
+    Configuration, child_path, error::ConfigurationError, secret::conceal_scalar,
+};
+
+/// How the value of a layer is combined with the value of the layers below at a path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// The upper value replaces the lower one, tables included
+    Replace,
+    /// The items of the upper array follow the items of the lower one
+    Append,
+    /// The tables of both arrays holding the same value for the key are merged, the
+    /// other items of the upper array being appended
+    MergeByKey(String),
+}
+
+impl Configuration {
+    /// Merge the upper value into this one with the strategies by dotted path, arrays
+    /// being replaced and tables merged where no strategy is given.
+    ///
+    /// The path of the keys of the items of an array does not hold their index, so
+    /// `servers.tags` applies to the `tags` of every item of `servers`. A strategy
+    /// combining arrays fails on any other value, rather than replacing it.
+    pub fn merge_with(
+        &self,
+        setting_value: &Configuration,
+        strategies: &HashMap<String, MergeStrategy>,
+    ) -> Result<Configuration, ConfigurationError> {
+        merge_at("", self, setting_value, strategies)
+    }
+}
+
+fn merge_at(
+    path: &str,
+    old: &Configuration,
+    new: &Configuration,
+    strategies: &HashMap<String, MergeStrategy>,
+) -> Result<Configuration, ConfigurationError> {
+    Ok(match (strategies.get(path), old, new) {
+        (Some(MergeStrategy::Append), Configuration::Array(old), Configuration::Array(new)) => {
+            Configuration::Array(old.iter().chain(new).cloned().collect())
+        }
+        (
+            Some(MergeStrategy::MergeByKey(key)),
+            Configuration::Array(old),
+            Configuration::Array(new),
+        ) => {
+            let mut merged = old.clone();
+            for item in new {
+                let existing = item.get(key).and_then(|id| {
+                    merged
+                        .iter()
+                        .position(|candidate| candidate.get(key) == Some(id))
+                });
+                match existing {
+                    // the items are tables merged under the path of the array itself
+                    Some(i) => {
+                        merged[i] = match (&merged[i], item) {
+                            (Configuration::Map(old_item), Configuration::Map(new_item)) => {
+                                merge_maps(path, old_item, new_item, strategies)?
+                            }
+                            _ => item.clone(),
+                        }
+                    }
+                    None => merged.push(item.clone()),
+                }
+            }
+            Configuration::Array(merged)
+        }
+        (None, Configuration::Map(old), Configuration::Map(new)) => {
+            merge_maps(path, old, new, strategies)?
+        }
+        (Some(strategy @ (MergeStrategy::Append | MergeStrategy::MergeByKey(_))), old, new) => {
+            return Err(ConfigurationError::MergeStrategy {
+                key: path.to_string(),
+                strategy: strategy.clone(),
+                lower: old.type_name(),
+                upper: new.type_name(),
+            });
+        }
+        // a secret overridden by a plain value stays a secret
+        (_, Configuration::Secret(_), new) => conceal_scalar(new.clone()),
+        _ => new.clone(),
+    })
+}
+
+fn merge_maps(
+    path: &str,
+    old_map: &HashMap<String, Configuration>,
+    new_map: &HashMap<String, Configuration>,
+    strategies: &HashMap<String, MergeStrategy>,
+) -> Result<Configuration, ConfigurationError> {
+    let mut hashmap = HashMap::new();
+    for (k, v) in old_map {
+        match new_map.get(k) {
+            Some(new) => {
+                hashmap.insert(
+                    k.clone(),
+                    merge_at(&child_path(path, k), v, new, strategies)?,
+                );
+            }
+            None => {
+                hashmap.insert(k.clone(), v.clone());
+            }
+        }
+    }
+    for (k, v) in new_map {
+        if !old_map.contains_key(k) {
+            hashmap.insert(k.clone(), v.clone());
+        }
+    }
+    Ok(Configuration::Map(hashmap))
+}
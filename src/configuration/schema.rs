@@ -0,0 +1,468 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use toml::Value;
+
+use crate::configuration::{
+    Configuration, LOCAL_DATETIME_FORMAT, child_path,
+    error::ConfigurationError,
+    layer::ConfigurationLayer,
+    nest,
+    secret::{Secret, conceal_scalar},
+};
+
+/// Type expected for a configuration value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigurationType {
+    /// A string, a secret being accepted
+    String,
+    Int,
+    /// A float, an integer being accepted
+    Float,
+    Bool,
+    DateTime,
+    LocalDateTime,
+    LocalDate,
+    LocalTime,
+    Array,
+    /// A table whose keys are not checked
+    Table,
+}
+
+impl ConfigurationType {
+    /// Whether the value is of this type, a secret matching when its text reads as one
+    pub fn matches(&self, value: &Configuration) -> bool {
+        if let Configuration::Secret(secret) = value {
+            if *self != ConfigurationType::String {
+                return self
+                    .reveal(secret)
+                    .is_some_and(|value| self.matches(&value));
+            }
+        }
+        matches!(
+            (self, value),
+            (
+                ConfigurationType::String,
+                Configuration::String(_) | Configuration::Secret(_)
+            ) | (ConfigurationType::Int, Configuration::Int(_))
+                | (
+                    ConfigurationType::Float,
+                    Configuration::Float(_) | Configuration::Int(_)
+                )
+                | (ConfigurationType::Bool, Configuration::Bool(_))
+                | (ConfigurationType::DateTime, Configuration::DateTime(_))
+                | (
+                    ConfigurationType::LocalDateTime,
+                    Configuration::LocalDateTime(_)
+                )
+                | (ConfigurationType::LocalDate, Configuration::LocalDate(_))
+                | (ConfigurationType::LocalTime, Configuration::LocalTime(_))
+                | (ConfigurationType::Array, Configuration::Array(_))
+                | (ConfigurationType::Table, Configuration::Map(_))
+        )
+    }
+
+    /// Value whose text the secret holds, read as this type
+    fn reveal(&self, secret: &Secret) -> Option<Configuration> {
+        let text = secret.expose();
+        match self {
+            ConfigurationType::Int => text.parse().ok().map(Configuration::Int),
+            ConfigurationType::Float => text.parse().ok().map(Configuration::Float),
+            ConfigurationType::Bool => text.parse().ok().map(Configuration::Bool),
+            ConfigurationType::DateTime => DateTime::parse_from_rfc3339(text)
+                .ok()
+                .map(|value| Configuration::DateTime(value.to_utc())),
+            ConfigurationType::LocalDateTime => {
+                NaiveDateTime::parse_from_str(text, LOCAL_DATETIME_FORMAT)
+                    .ok()
+                    .map(Configuration::LocalDateTime)
+            }
+            ConfigurationType::LocalDate => {
+                text.parse::<NaiveDate>().ok().map(Configuration::LocalDate)
+            }
+            ConfigurationType::LocalTime => {
+                text.parse::<NaiveTime>().ok().map(Configuration::LocalTime)
+            }
+            ConfigurationType::String | ConfigurationType::Array | ConfigurationType::Table => None,
+        }
+    }
+}
+
+impl Display for ConfigurationType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigurationType::String => "string",
+            ConfigurationType::Int => "integer",
+            ConfigurationType::Float => "float",
+            ConfigurationType::Bool => "boolean",
+            ConfigurationType::DateTime => "datetime",
+            ConfigurationType::LocalDateTime => "local datetime",
+            ConfigurationType::LocalDate => "local date",
+            ConfigurationType::LocalTime => "local time",
+            ConfigurationType::Array => "array",
+            ConfigurationType::Table => "table",
+        })
+    }
+}
+
+/// Key expected in a section of the configuration
+#[derive(Debug, Clone)]
+pub struct ConfigurationKey {
+    path: String,
+    kind: ConfigurationType,
+    required: bool,
+    secret: bool,
+    default: Option<Configuration>,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl ConfigurationKey {
+    /// Key at the dotted path, relative to the section of its schema
+    pub fn new(path: &str, kind: ConfigurationType) -> Self {
+        Self {
+            path: path.to_string(),
+            kind,
+            required: false,
+            secret: false,
+            default: None,
+            min: None,
+            max: None,
+        }
+    }
+
+    /// The key must be set, by a layer or by its default
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// The key must come from a secret file or the environment, never from a config file
+    pub fn secret(mut self) -> Self {
+        self.secret = true;
+        self
+    }
+
+    /// Value used when no layer sets the key
+    pub fn with_default(mut self, default: Configuration) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// Lowest number allowed, inclusive
+    pub fn with_min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Highest number allowed, inclusive
+    pub fn with_max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn get_kind(&self) -> ConfigurationType {
+        self.kind
+    }
+}
+
+/// Keys a component expects under its section, any other key of the section being unknown
+#[derive(Debug, Clone)]
+pub struct ConfigurationSchema {
+    section: String,
+    keys: Vec<ConfigurationKey>,
+}
+
+impl ConfigurationSchema {
+    /// Schema of the section at the dotted path, the empty path owning the whole configuration
+    pub fn new(section: &str) -> Self {
+        Self {
+            section: section.to_string(),
+            keys: Vec::new(),
+        }
+    }
+
+    pub fn with_key(mut self, key: ConfigurationKey) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    pub fn get_section(&self) -> &str {
+        &self.section
+    }
+
+    pub fn get_keys(&self) -> &[ConfigurationKey] {
+        &self.keys
+    }
+
+    /// Default values of the keys, as a configuration to merge under the others
+    pub fn defaults(&self) -> Configuration {
+        self.keys
+            .iter()
+            .filter_map(|key| {
+                key.default
+                    .as_ref()
+                    .map(|default| (child_path(&self.section, &key.path), default))
+            })
+            .fold(
+                Configuration::Map(HashMap::new()),
+                |defaults, (path, default)| {
+                    let keys: Vec<&str> = path.split('.').collect();
+                    defaults.merge(&nest(&keys, default.clone()))
+                },
+            )
+    }
+}
+
+/// Schema written as TOML, to cross the boundary of a plugin
+#[derive(Serialize, Deserialize)]
+struct SchemaDocument {
+    section: String,
+    #[serde(default)]
+    keys: Vec<KeyDocument>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyDocument {
+    path: String,
+    #[serde(rename = "type")]
+    kind: ConfigurationType,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    required: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    secret: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    default: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max: Option<f64>,
+}
+
+impl ConfigurationSchema {
+    /// TOML document of the schema, a secret default being redacted
+    pub fn to_toml(&self) -> Result<String, ConfigurationError> {
+        let document = SchemaDocument {
+            section: self.section.clone(),
+            keys: self
+                .keys
+                .iter()
+                .map(|key| KeyDocument {
+                    path: key.path.clone(),
+                    kind: key.kind,
+                    required: key.required,
+                    secret: key.secret,
+                    default: key.default.as_ref().map(Configuration::to_toml_value),
+                    min: key.min,
+                    max: key.max,
+                })
+                .collect(),
+        };
+        toml::to_string(&document).map_err(|e| ConfigurationError::Export {
+            format: "toml",
+            reason: e.to_string(),
+        })
+    }
+
+    /// Schema read from the TOML document written by `to_toml`
+    pub fn from_toml(document: &str) -> Result<Self, ConfigurationError> {
+        let document: SchemaDocument =
+            toml::from_str(document).map_err(|e| ConfigurationError::Deserialize {
+                key: "schema".to_string(),
+                message: e.message().to_string(),
+            })?;
+        let keys = document
+            .keys
+            .into_iter()
+            .map(|key| {
+                Ok(ConfigurationKey {
+                    default: key.default.map(Configuration::try_from).transpose()?,
+                    path: key.path,
+                    kind: key.kind,
+                    required: key.required,
+                    secret: key.secret,
+                    min: key.min,
+                    max: key.max,
+                })
+            })
+            .collect::<Result<_, ConfigurationError>>()?;
+        Ok(Self {
+            section: document.section,
+            keys,
+        })
+    }
+}
+
+/// Configuration with the scalars of the keys declared secret turned into secrets holding
+/// their text, whatever layer they come from
+pub(crate) fn conceal_declared(
+    schemas: &[ConfigurationSchema],
+    configuration: Configuration,
+) -> Configuration {
+    let secrets: Vec<(String, Configuration)> = schemas
+        .iter()
+        .flat_map(|schema| {
+            schema
+                .keys
+                .iter()
+                .filter(|key| key.secret)
+                .map(|key| child_path(&schema.section, &key.path))
+        })
+        .filter_map(|path| match configuration.get_path(&path) {
+            Some(Configuration::Map(_) | Configuration::Array(_) | Configuration::Secret(_))
+            | None => None,
+            Some(value) => Some((path, conceal_scalar(value.clone()))),
+        })
+        .collect();
+    secrets
+        .into_iter()
+        .fold(configuration, |configuration, (path, secret)| {
+            let keys: Vec<&str> = path.split('.').collect();
+            configuration.merge(&nest(&keys, secret))
+        })
+}
+
+/// Mismatch between the configuration and a schema
+#[derive(Debug, Clone, PartialEq, Error, Serialize)]
+#[serde(tag = "violation", rename_all = "snake_case")]
+pub enum ConfigurationViolation {
+    #[error("{key} is required")]
+    Missing { key: String },
+    #[error("{key} must be of type {expected}, found {found}")]
+    InvalidType {
+        key: String,
+        expected: ConfigurationType,
+        found: &'static str,
+    },
+    #[error("{key} must be between {} and {}", min.map_or("-inf".to_string(), |min| min.to_string()), max.map_or("inf".to_string(), |max| max.to_string()))]
+    OutOfRange {
+        key: String,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    #[error("{key} is not a known key")]
+    UnknownKey { key: String },
+    #[error("{key} is a secret but is set in {}", path.display())]
+    SecretInPlainFile { key: String, path: PathBuf },
+}
+
+/// Every violation of the schemas by the merged configuration, a secret being checked
+/// in each layer as one set in a config file leaks even when another layer overrides it
+pub fn validate(
+    schemas: &[ConfigurationSchema],
+    configuration: &Configuration,
+    layers: &[(Configuration, ConfigurationLayer)],
+) -> Vec<ConfigurationViolation> {
+    let mut violations = Vec::new();
+    let mut declared = Vec::new();
+    for schema in schemas {
+        for key in &schema.keys {
+            let path = child_path(&schema.section, &key.path);
+            check_key(key, &path, configuration, &mut violations);
+            if key.secret {
+                for (layer, source) in layers {
+                    if let ConfigurationLayer::File { path: file }
+                    | ConfigurationLayer::Profile { path: file, .. } = source
+                    {
+                        if layer.get_path(&path).is_some() {
+                            violations.push(ConfigurationViolation::SecretInPlainFile {
+                                key: path.clone(),
+                                path: file.clone(),
+                            });
+                        }
+                    }
+                }
+            }
+            declared.push(path);
+        }
+    }
+    let mut unknown = Vec::new();
+    for schema in schemas {
+        if let Some(section) = configuration.get_path(&schema.section) {
+            find_unknown(&schema.section, section, &declared, &mut unknown);
+        }
+    }
+    unknown.sort();
+    unknown.dedup();
+    violations.extend(
+        unknown
+            .into_iter()
+            .map(|key| ConfigurationViolation::UnknownKey { key }),
+    );
+    violations
+}
+
+fn check_key(
+    key: &ConfigurationKey,
+    path: &str,
+    configuration: &Configuration,
+    violations: &mut Vec<ConfigurationViolation>,
+) {
+    let Some(value) = configuration.get_path(path) else {
+        if key.required {
+            violations.push(ConfigurationViolation::Missing {
+                key: path.to_string(),
+            });
+        }
+        return;
+    };
+    if !key.kind.matches(value) {
+        violations.push(ConfigurationViolation::InvalidType {
+            key: path.to_string(),
+            expected: key.kind,
+            found: value.type_name(),
+        });
+        return;
+    }
+    let revealed = match value {
+        Configuration::Secret(secret) => key.kind.reveal(secret),
+        _ => None,
+    };
+    let number = match revealed.as_ref().unwrap_or(value) {
+        Configuration::Int(value) => Some(*value as f64),
+        Configuration::Float(value) => Some(*value),
+        _ => None,
+    };
+    if let Some(number) = number {
+        if key.min.is_some_and(|min| number < min) || key.max.is_some_and(|max| number > max) {
+            violations.push(ConfigurationViolation::OutOfRange {
+                key: path.to_string(),
+                min: key.min,
+                max: key.max,
+            });
+        }
+    }
+}
+
+/// Keys of the table neither declared nor leading to a declared key
+fn find_unknown(path: &str, value: &Configuration, declared: &[String], unknown: &mut Vec<String>) {
+    let Configuration::Map(map) = value else {
+        return;
+    };
+    for (key, value) in map {
+        let child = child_path(path, key);
+        if declared.contains(&child) {
+            continue;
+        }
+        let prefix = format!("{child}.");
+        if declared
+            .iter()
+            .any(|declared| declared.starts_with(&prefix))
+        {
+            find_unknown(&child, value, declared, unknown);
+        } else {
+            unknown.push(child);
+        }
+    }
+}
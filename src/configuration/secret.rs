@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Deserializer};
+use zeroize::Zeroizing;
+
+use crate::configuration::{
+    Configuration, LOCAL_DATETIME_FORMAT, child_path, error::ConfigurationError,
+};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Sensitive value, redacted when formatted and wiped from memory when dropped
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(Zeroizing<String>);
+
+impl Secret {
+    pub fn new(value: &str) -> Self {
+        Self(Zeroizing::new(value.to_string()))
+    }
+
+    /// The value itself, to hand to whatever needs it without keeping a copy
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(Zeroizing::new(value))
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret({REDACTED})")
+    }
+}
+
+impl Display for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Zeroizing::<String>::deserialize(deserializer).map(Self)
+    }
+}
+
+/// Where the value of a secret is read from at load time
+enum Indirection<'a> {
+    File(&'a str),
+    Env(&'a str),
+}
+
+impl<'a> Indirection<'a> {
+    /// `{ file = "..." }` or `{ env = "..." }`, a table with any other key being plain
+    fn of(value: &'a Configuration) -> Option<Self> {
+        let Configuration::Map(map) = value else {
+            return None;
+        };
+        if map.len() != 1 {
+            return None;
+        }
+        match map.iter().next()? {
+            (key, Configuration::String(path)) if key == "file" => Some(Self::File(path)),
+            (key, Configuration::String(variable)) if key == "env" => Some(Self::Env(variable)),
+            _ => None,
+        }
+    }
+}
+
+/// Turn every value of a secret layer into a secret, resolving the indirections.
+///
+/// Numbers, booleans and dates become secrets holding their text.
+///
+/// A relative file is read from the directory of the layer; the files read are added
+/// to `files`.
+pub(crate) fn conceal(
+    value: Configuration,
+    key: &str,
+    directory: &Path,
+    vars: &HashMap<String, String>,
+    files: &mut Vec<PathBuf>,
+) -> Result<Configuration, ConfigurationError> {
+    match Indirection::of(&value) {
+        Some(Indirection::File(path)) => {
+            let path = directory.join(path);
+            let content = Zeroizing::new(fs::read_to_string(&path).map_err(|source| {
+                ConfigurationError::SecretFile {
+                    key: key.to_string(),
+                    path: path.clone(),
+                    source,
+                }
+            })?);
+            files.push(path);
+            // secret files usually end with a newline that is not part of the value
+            return Ok(Configuration::Secret(Secret::new(
+                content.trim_end_matches(['\r', '\n']),
+            )));
+        }
+        Some(Indirection::Env(variable)) => {
+            return vars
+                .get(variable)
+                .map(|value| Configuration::Secret(Secret::new(value)))
+                .ok_or_else(|| ConfigurationError::SecretVariable {
+                    key: key.to_string(),
+                    variable: variable.to_string(),
+                });
+        }
+        None => {}
+    }
+    Ok(match value {
+        Configuration::Map(map) => Configuration::Map(
+            map.into_iter()
+                .map(|(k, v)| {
+                    let value = conceal(v, &child_path(key, &k), directory, vars, files)?;
+                    Ok((k, value))
+                })
+                .collect::<Result<_, ConfigurationError>>()?,
+        ),
+        Configuration::Array(array) => Configuration::Array(
+            array
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| conceal(v, &child_path(key, &i.to_string()), directory, vars, files))
+                .collect::<Result<_, _>>()?,
+        ),
+        value => conceal_scalar(value),
+    })
+}
+
+/// Secret holding the text of a scalar, as read by `get_as`, tables and arrays being kept
+pub(crate) fn conceal_scalar(value: Configuration) -> Configuration {
+    match value {
+        Configuration::String(value) => Configuration::Secret(Secret::from(value)),
+        Configuration::Int(value) => Configuration::Secret(Secret::from(value.to_string())),
+        Configuration::Float(value) => Configuration::Secret(Secret::from(value.to_string())),
+        Configuration::Bool(value) => Configuration::Secret(Secret::from(value.to_string())),
+        Configuration::DateTime(value) => Configuration::Secret(Secret::from(value.to_rfc3339())),
+        Configuration::LocalDateTime(value) => Configuration::Secret(Secret::from(
+            value.format(LOCAL_DATETIME_FORMAT).to_string(),
+        )),
+        Configuration::LocalDate(value) => Configuration::Secret(Secret::from(value.to_string())),
+        Configuration::LocalTime(value) => Configuration::Secret(Secret::from(value.to_string())),
+        value => value,
+    }
+}
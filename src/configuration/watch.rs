@@ -0,0 +1,75 @@
+use std::{path::PathBuf, time::SystemTime};
+
+use crate::configuration::{Configuration, layer::ConfigurationOrigins};
+
+/// Value at a subscribed path before and after a reload, none when it is not set
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigurationChange {
+    path: String,
+    old: Option<Configuration>,
+    new: Option<Configuration>,
+}
+
+impl ConfigurationChange {
+    /// Change at the path between both configurations, none if the value is the same
+    pub fn between(path: &str, old: &Configuration, new: &Configuration) -> Option<Self> {
+        let (old, new) = (old.get_path(path), new.get_path(path));
+        let same = match (old, new) {
+            (Some(old), Some(new)) => old.same_as(new),
+            (old, new) => old.is_none() && new.is_none(),
+        };
+        (!same).then(|| Self {
+            path: path.to_string(),
+            old: old.cloned(),
+            new: new.cloned(),
+        })
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn get_old(&self) -> Option<&Configuration> {
+        self.old.as_ref()
+    }
+
+    pub fn get_new(&self) -> Option<&Configuration> {
+        self.new.as_ref()
+    }
+}
+
+/// Modification time and length of each file considered by the load, none if missing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FileStamps(Vec<(PathBuf, Option<(SystemTime, u64)>)>);
+
+impl FileStamps {
+    pub(crate) async fn of(origins: &ConfigurationOrigins) -> Self {
+        let mut stamps = Vec::new();
+        for file in origins.get_files() {
+            let stamp = tokio::fs::metadata(file.get_path())
+                .await
+                .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+                .ok();
+            stamps.push((file.get_path().to_path_buf(), stamp));
+        }
+        Self(stamps)
+    }
+
+    /// Stamps of the files, keeping the earlier stamp of those already known so that a
+    /// change made since then is still seen
+    pub(crate) fn keeping(self, earlier: &FileStamps) -> Self {
+        Self(
+            self.0
+                .into_iter()
+                .map(|(path, stamp)| {
+                    let stamp = earlier
+                        .0
+                        .iter()
+                        .find(|(known, _)| *known == path)
+                        .map_or(stamp, |(_, earlier)| *earlier);
+                    (path, stamp)
+                })
+                .collect(),
+        )
+    }
+}
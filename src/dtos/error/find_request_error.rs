@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::{service::error::ErrorCategory, traits::service_error_trait::ServiceErrorTrait};
+
 #[derive(Debug, Error)]
 pub enum FindRequestError {
     #[error("offset value {offset} cannot be less than 1, please choose higher value")]
@@ -9,3 +11,17 @@ pub enum FindRequestError {
     #[error("per_page value {per_page} is too high, please choose lower value")]
     PerPageValueTooHigh { per_page: u16 },
 }
+
+impl ServiceErrorTrait for FindRequestError {
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::Validation
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            FindRequestError::PerPageOffsetTooLow { .. } => "find_request.offset_too_low",
+            FindRequestError::PerPageValueTooLow { .. } => "find_request.per_page_too_low",
+            FindRequestError::PerPageValueTooHigh { .. } => "find_request.per_page_too_high",
+        }
+    }
+}
@@ -7,8 +7,12 @@ use crate::{
     traits::{find_option_trait::FindOptionTrait, find_request_trait::FindRequestTrait},
 };
 
+/// Deserialized through `FindRequest::new`, so that a request read from JSON is validated too
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
-#[serde(default)]
+#[serde(
+    try_from = "FindRequestFields<T>",
+    bound = "T: Default + for<'d> Deserialize<'d> + Debug + Clone + Send + Sync + 'static"
+)]
 pub struct FindRequest<T>
 where
     T: Default + 'static,
@@ -19,6 +23,43 @@ where
     offset: u64,
 }
 
+/// Fields of a `FindRequest` as read, before their validation
+#[derive(Deserialize)]
+#[serde(default)]
+struct FindRequestFields<T> {
+    filters: T,
+    order_by: String,
+    per_page: u16,
+    offset: u64,
+}
+
+impl<T: Default> Default for FindRequestFields<T> {
+    fn default() -> Self {
+        Self {
+            filters: T::default(),
+            order_by: String::new(),
+            per_page: 25,
+            offset: 1,
+        }
+    }
+}
+
+impl<T> TryFrom<FindRequestFields<T>> for FindRequest<T>
+where
+    T: Default + for<'de> Deserialize<'de> + Debug + Clone + Send + Sync + 'static,
+{
+    type Error = FindRequestError;
+
+    fn try_from(fields: FindRequestFields<T>) -> Result<Self, Self::Error> {
+        Self::new(
+            &fields.filters,
+            &fields.order_by,
+            &fields.per_page,
+            &fields.offset,
+        )
+    }
+}
+
 impl<T> FindRequest<T>
 where
     T: Default + for<'de> Deserialize<'de> + Debug + Clone + Send + Sync + 'static,
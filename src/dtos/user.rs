@@ -1,4 +1,8 @@
 pub mod user_add_request;
 pub mod user_delete_request;
+pub mod user_delete_response;
 pub mod user_find_request_filter;
+pub mod user_onboard_request;
+pub mod user_onboard_response;
+pub mod user_status_change_request;
 pub mod user_update_request;
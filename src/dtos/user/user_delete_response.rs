@@ -0,0 +1,45 @@
+use serde::Serialize;
+
+use crate::model::{user::UserID, user_internet::UserInternet, user_permission::UserPermission};
+
+/// What was removed with the user
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserDeleteResponse {
+    user_id: UserID,
+    emails: Vec<UserInternet>,
+    permissions: Vec<UserPermission>,
+    password: bool,
+}
+
+impl UserDeleteResponse {
+    pub fn new(
+        user_id: &UserID,
+        emails: &[UserInternet],
+        permissions: &[UserPermission],
+        password: bool,
+    ) -> Self {
+        Self {
+            user_id: *user_id,
+            emails: emails.to_vec(),
+            permissions: permissions.to_vec(),
+            password,
+        }
+    }
+
+    pub fn get_user_id(&self) -> &UserID {
+        &self.user_id
+    }
+
+    pub fn get_emails(&self) -> &[UserInternet] {
+        &self.emails
+    }
+
+    pub fn get_permissions(&self) -> &[UserPermission] {
+        &self.permissions
+    }
+
+    /// Whether a password hash was removed
+    pub fn has_password(&self) -> bool {
+        self.password
+    }
+}
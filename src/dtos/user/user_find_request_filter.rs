@@ -5,4 +5,7 @@ pub struct UserFindRequestFilter {
     pub id: Option<uuid::Uuid>,
     pub firstname: Option<String>,
     pub lastname: Option<String>,
+    /// Soft-deleted users are left out unless set
+    #[serde(default)]
+    pub include_deleted: bool,
 }
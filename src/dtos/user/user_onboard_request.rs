@@ -0,0 +1,65 @@
+use std::fmt::Debug;
+
+use serde::Deserialize;
+
+/// Everything needed to create a user at once, kept raw so every problem can be reported
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+pub struct UserOnboardRequest {
+    firstname: String,
+    lastname: Option<String>,
+    emails: Vec<String>,
+    password: String,
+    /// Permissions or wildcard patterns like `user:*` acting as roles
+    #[serde(default)]
+    permissions: Vec<String>,
+}
+
+impl UserOnboardRequest {
+    pub fn new(
+        firstname: &str,
+        lastname: Option<&str>,
+        emails: &[&str],
+        password: &str,
+        permissions: &[&str],
+    ) -> Self {
+        Self {
+            firstname: firstname.to_string(),
+            lastname: lastname.map(str::to_string),
+            emails: emails.iter().map(|e| e.to_string()).collect(),
+            password: password.to_string(),
+            permissions: permissions.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    pub fn get_firstname(&self) -> &str {
+        &self.firstname
+    }
+
+    pub fn get_lastname(&self) -> Option<&str> {
+        self.lastname.as_deref()
+    }
+
+    pub fn get_emails(&self) -> &[String] {
+        &self.emails
+    }
+
+    pub fn get_password(&self) -> &str {
+        &self.password
+    }
+
+    pub fn get_permissions(&self) -> &[String] {
+        &self.permissions
+    }
+}
+
+impl Debug for UserOnboardRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UserOnboardRequest")
+            .field("firstname", &self.firstname)
+            .field("lastname", &self.lastname)
+            .field("emails", &self.emails)
+            .field("password", &"[REDACTED]")
+            .field("permissions", &self.permissions)
+            .finish()
+    }
+}
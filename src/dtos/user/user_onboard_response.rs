@@ -0,0 +1,33 @@
+use serde::Serialize;
+
+use crate::model::{user::User, user_internet::UserInternet, user_permission::UserPermission};
+
+/// The user created by an onboarding with its emails and grants
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserOnboardResponse {
+    user: User,
+    emails: Vec<UserInternet>,
+    permissions: Vec<UserPermission>,
+}
+
+impl UserOnboardResponse {
+    pub fn new(user: &User, emails: &[UserInternet], permissions: &[UserPermission]) -> Self {
+        Self {
+            user: user.clone(),
+            emails: emails.to_vec(),
+            permissions: permissions.to_vec(),
+        }
+    }
+
+    pub fn get_user(&self) -> &User {
+        &self.user
+    }
+
+    pub fn get_emails(&self) -> &[UserInternet] {
+        &self.emails
+    }
+
+    pub fn get_permissions(&self) -> &[UserPermission] {
+        &self.permissions
+    }
+}
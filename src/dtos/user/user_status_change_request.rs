@@ -0,0 +1,27 @@
+use serde::Deserialize;
+
+use crate::model::user::UserID;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct UserStatusChangeRequest {
+    user_id: UserID,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+impl UserStatusChangeRequest {
+    pub fn new(user_id: &UserID, reason: Option<&str>) -> Self {
+        Self {
+            user_id: *user_id,
+            reason: reason.map(str::to_string),
+        }
+    }
+
+    pub fn get_user_id(&self) -> &UserID {
+        &self.user_id
+    }
+
+    pub fn get_reason(&self) -> Option<&str> {
+        self.reason.as_deref()
+    }
+}
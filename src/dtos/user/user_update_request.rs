@@ -17,6 +17,10 @@ impl UserUpdateRequest {
             lastname: lastname.cloned(),
         }
     }
+
+    pub fn get_id(&self) -> &uuid::Uuid {
+        &self.id
+    }
 }
 
 impl From<&UserUpdateRequest> for User {
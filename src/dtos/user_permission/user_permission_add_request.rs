@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
 
 use crate::model::{permission::Permission, user::UserID, user_permission::UserPermission};
@@ -6,19 +7,55 @@ use crate::model::{permission::Permission, user::UserID, user_permission::UserPe
 pub struct UserPermissionAddRequest {
     user_id: UserID,
     permission: Permission,
+    #[serde(default)]
+    valid_from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    valid_until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    deny: bool,
 }
 
 impl From<&UserPermissionAddRequest> for UserPermission {
     fn from(val: &UserPermissionAddRequest) -> Self {
-        UserPermission::new(&val.user_id, &val.permission)
+        let new = if val.deny {
+            UserPermission::new_deny
+        } else {
+            UserPermission::new_time_bound
+        };
+        new(
+            &val.user_id,
+            &val.permission,
+            val.valid_from.as_ref(),
+            val.valid_until.as_ref(),
+        )
     }
 }
 
 impl UserPermissionAddRequest {
     pub fn new(user_id: &UserID, permission: &str) -> Self {
+        Self::new_time_bound(user_id, permission, None, None)
+    }
+
+    pub fn new_time_bound(
+        user_id: &UserID,
+        permission: &str,
+        valid_from: Option<&DateTime<Utc>>,
+        valid_until: Option<&DateTime<Utc>>,
+    ) -> Self {
         Self {
             user_id: *user_id,
             permission: permission.to_string(),
+            valid_from: valid_from.cloned(),
+            valid_until: valid_until.cloned(),
+            deny: false,
+        }
+    }
+
+    /// Request of a grant refusing the permission
+    pub fn new_deny(user_id: &UserID, permission: &str) -> Self {
+        Self {
+            deny: true,
+            ..Self::new(user_id, permission)
         }
     }
 
@@ -29,4 +66,16 @@ impl UserPermissionAddRequest {
     pub fn get_permission(&self) -> &str {
         &self.permission
     }
+
+    pub fn get_valid_from(&self) -> Option<&DateTime<Utc>> {
+        self.valid_from.as_ref()
+    }
+
+    pub fn get_valid_until(&self) -> Option<&DateTime<Utc>> {
+        self.valid_until.as_ref()
+    }
+
+    pub fn is_deny(&self) -> bool {
+        self.deny
+    }
 }
@@ -6,4 +6,7 @@ use crate::model::{permission::Permission, user::UserID};
 pub struct UserPermissionFindRequestFilter {
     pub user_id: Option<UserID>,
     pub permission: Option<Permission>,
+    /// Expired grants are left out unless this is set
+    #[serde(default)]
+    pub include_expired: bool,
 }
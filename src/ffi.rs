@@ -0,0 +1,284 @@
+//! C API of the library, declared in `include/fototra.h`.
+//!
+//! Every call blocks on an internal tokio runtime and works on the global `Runtime`.
+//! Requests and responses are NUL-terminated UTF-8 JSON strings: a string returned
+//! through a `char **` argument belongs to the caller, who releases it with
+//! `fototra_string_free`. Pointer arguments must be null or valid for the call.
+//!
+//! Each call returns a `FototraStatus`; on failure `fototra_last_error` gives the
+//! error of the calling thread as JSON (category, code, message, sources and, for a
+//! denied permission, its details).
+
+pub mod error;
+pub mod user;
+pub mod user_internet;
+pub mod user_password;
+pub mod user_permission;
+
+use std::{
+    cell::RefCell,
+    ffi::{CStr, CString, c_char},
+    panic::{AssertUnwindSafe, catch_unwind},
+    path::Path,
+    pin::Pin,
+    ptr,
+    sync::{Arc, LazyLock},
+};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    adapters::repository::in_memory::InMemoryRepository,
+    ffi::error::FfiError,
+    runtime::{Runtime, error::RuntimeError},
+    security::{error::SecurityError, user_authorizer::UserAuthorizer},
+    service::error::{ErrorCategory, ServiceError},
+    traits::{authentication_trait::AuthenticationTrait, authorization_trait::AuthorizationTrait},
+};
+
+static FFI_RUNTIME: LazyLock<tokio::runtime::Runtime> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("cannot build the tokio runtime of the C API")
+});
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Outcome of a C API call, following the category of the error
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FototraStatus {
+    Ok = 0,
+    Auth = 1,
+    Validation = 2,
+    NotFound = 3,
+    Conflict = 4,
+    Internal = 5,
+}
+
+impl From<ErrorCategory> for FototraStatus {
+    fn from(category: ErrorCategory) -> Self {
+        match category {
+            ErrorCategory::Auth => Self::Auth,
+            ErrorCategory::Validation => Self::Validation,
+            ErrorCategory::NotFound => Self::NotFound,
+            ErrorCategory::Conflict => Self::Conflict,
+            ErrorCategory::Internal => Self::Internal,
+        }
+    }
+}
+
+/// User acting through the C API, already authenticated by the host
+struct Actor {
+    user_id: Uuid,
+}
+
+impl AuthenticationTrait for Actor {
+    fn authenticate<'a>(
+        &'a self,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Arc<dyn AuthorizationTrait + 'static>, SecurityError>>
+                + Send
+                + 'a,
+        >,
+    > {
+        Box::pin(async {
+            Ok(Arc::new(UserAuthorizer::new(&self.user_id)) as Arc<dyn AuthorizationTrait>)
+        })
+    }
+}
+
+/// Initialize the global runtime
+#[unsafe(no_mangle)]
+pub extern "C" fn fototra_init() -> FototraStatus {
+    unsafe {
+        respond(ptr::null_mut(), || {
+            runtime_call(Runtime::get_instance().init())
+        })
+    }
+}
+
+/// Add the in-memory repository adapter
+#[unsafe(no_mangle)]
+pub extern "C" fn fototra_add_in_memory_adapter() -> FototraStatus {
+    unsafe {
+        respond(ptr::null_mut(), || {
+            runtime_call(Runtime::get_instance().add_adapter(Arc::new(InMemoryRepository::new())))
+        })
+    }
+}
+
+/// Add the adapter of the plugin at the given path
+///
+/// # Safety
+///
+/// `path` follows the pointer contract of the module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_add_plugin(path: *const c_char) -> FototraStatus {
+    unsafe {
+        respond(ptr::null_mut(), || {
+            let path = read_str("path", path)?;
+            runtime_call(Runtime::get_instance().add_plugin(Path::new(path)))
+        })
+    }
+}
+
+/// Start the added adapters
+#[unsafe(no_mangle)]
+pub extern "C" fn fototra_start() -> FototraStatus {
+    unsafe {
+        respond(ptr::null_mut(), || {
+            runtime_call(Runtime::get_instance().start())
+        })
+    }
+}
+
+/// Shut the started adapters down
+#[unsafe(no_mangle)]
+pub extern "C" fn fototra_shutdown() -> FototraStatus {
+    unsafe {
+        respond(ptr::null_mut(), || {
+            runtime_call(Runtime::get_instance().shutdown())
+        })
+    }
+}
+
+/// Error of the last failed call of this thread as JSON, null if it succeeded
+#[unsafe(no_mangle)]
+pub extern "C" fn fototra_last_error() -> *mut c_char {
+    LAST_ERROR.with_borrow(|error| {
+        error
+            .as_ref()
+            .map_or(ptr::null_mut(), |error| error.clone().into_raw())
+    })
+}
+
+/// Release a string returned by the library
+///
+/// # Safety
+///
+/// `string` is null or was returned by the library and not released yet
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+/// Run the future on the runtime of the C API
+fn block_on<F: Future>(future: F) -> F::Output {
+    FFI_RUNTIME.block_on(future)
+}
+
+fn runtime_call(
+    future: impl Future<Output = Result<(), RuntimeError>>,
+) -> Result<(), ServiceError> {
+    block_on(future).map_err(|e| ServiceError::new(FfiError::Runtime(e)))
+}
+
+/// Run the call, hand its JSON result to the host and record its error
+///
+/// # Safety
+///
+/// `response` is null or writable
+unsafe fn respond<T: Serialize>(
+    response: *mut *mut c_char,
+    call: impl FnOnce() -> Result<T, ServiceError>,
+) -> FototraStatus {
+    let result = catch_unwind(AssertUnwindSafe(call))
+        .unwrap_or_else(|panic| {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(ServiceError::new(FfiError::Panic { message }))
+        })
+        .and_then(|value| {
+            serde_json::to_string(&value)
+                .ok()
+                .and_then(|json| CString::new(json).ok())
+                .ok_or(ServiceError::new(FfiError::Unknown(anyhow::anyhow!(
+                    "cannot serialize the response"
+                ))))
+        });
+    match result {
+        Ok(json) => {
+            LAST_ERROR.set(None);
+            if !response.is_null() {
+                unsafe { *response = json.into_raw() };
+            }
+            FototraStatus::Ok
+        }
+        Err(error) => {
+            LAST_ERROR.set(
+                serde_json::to_string(&error)
+                    .ok()
+                    .and_then(|json| CString::new(json).ok()),
+            );
+            if !response.is_null() {
+                unsafe { *response = ptr::null_mut() };
+            }
+            error.get_category().into()
+        }
+    }
+}
+
+/// # Safety
+///
+/// `value` follows the pointer contract of the module
+unsafe fn read_str<'a>(argument: &str, value: *const c_char) -> Result<&'a str, ServiceError> {
+    let invalid = |reason: &str| {
+        ServiceError::new(FfiError::InvalidArgument {
+            argument: argument.to_string(),
+            reason: reason.to_string(),
+        })
+    };
+    if value.is_null() {
+        return Err(invalid("null pointer"));
+    }
+    unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .map_err(|_| invalid("not UTF-8"))
+}
+
+/// # Safety
+///
+/// `value` follows the pointer contract of the module
+unsafe fn read_json<T: for<'de> Deserialize<'de>>(
+    argument: &str,
+    value: *const c_char,
+) -> Result<T, ServiceError> {
+    serde_json::from_str(unsafe { read_str(argument, value) }?).map_err(|e| {
+        ServiceError::new(FfiError::InvalidArgument {
+            argument: argument.to_string(),
+            reason: e.to_string(),
+        })
+    })
+}
+
+/// # Safety
+///
+/// `value` follows the pointer contract of the module
+unsafe fn read_uuid(argument: &str, value: *const c_char) -> Result<Uuid, ServiceError> {
+    Uuid::parse_str(unsafe { read_str(argument, value) }?).map_err(|e| {
+        ServiceError::new(FfiError::InvalidArgument {
+            argument: argument.to_string(),
+            reason: e.to_string(),
+        })
+    })
+}
+
+/// # Safety
+///
+/// `actor_id` follows the pointer contract of the module
+unsafe fn read_actor(actor_id: *const c_char) -> Result<Actor, ServiceError> {
+    Ok(Actor {
+        user_id: unsafe { read_uuid("actor_id", actor_id) }?,
+    })
+}
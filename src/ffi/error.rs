@@ -0,0 +1,41 @@
+use thiserror::Error;
+
+use crate::{
+    runtime::error::RuntimeError, service::error::ErrorCategory,
+    traits::service_error_trait::ServiceErrorTrait,
+};
+
+#[derive(Debug, Error)]
+pub enum FfiError {
+    #[error("Invalid argument {argument}: {reason}")]
+    InvalidArgument { argument: String, reason: String },
+    #[error(transparent)]
+    Runtime(#[from] RuntimeError),
+    #[error("The call panicked: {message}")]
+    Panic { message: String },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+impl ServiceErrorTrait for FfiError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            FfiError::InvalidArgument { .. } => ErrorCategory::Validation,
+            FfiError::Runtime(RuntimeError::DuplicateAdapter { .. })
+            | FfiError::Runtime(RuntimeError::AlreadyRegistered { .. })
+            | FfiError::Runtime(RuntimeError::AlreadyInitialized) => ErrorCategory::Conflict,
+            FfiError::Runtime(_) | FfiError::Panic { .. } | FfiError::Unknown(_) => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            FfiError::InvalidArgument { .. } => "ffi.invalid_argument",
+            FfiError::Runtime(_) => "ffi.runtime",
+            FfiError::Panic { .. } => "ffi.panic",
+            FfiError::Unknown(_) => "ffi.unknown",
+        }
+    }
+}
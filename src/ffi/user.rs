@@ -0,0 +1,117 @@
+use std::ffi::c_char;
+
+use crate::{
+    dtos::{
+        find_request::FindRequest,
+        user::{
+            user_add_request::UserAddRequest, user_delete_request::UserDeleteRequest,
+            user_find_request_filter::UserFindRequestFilter,
+            user_update_request::UserUpdateRequest,
+        },
+    },
+    ffi::{FototraStatus, block_on, read_actor, read_json, read_uuid, respond},
+    service::user::UserService,
+};
+
+/// Create a user from a `UserAddRequest`, the response is the user
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_create(
+    actor_id: *const c_char,
+    request: *const c_char,
+    response: *mut *mut c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(response, || {
+            let actor = read_actor(actor_id)?;
+            let request: UserAddRequest = read_json("request", request)?;
+            block_on(UserService::create(&actor, &request))
+        })
+    }
+}
+
+/// Update the user of a `UserUpdateRequest`, the response is the user
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_update(
+    actor_id: *const c_char,
+    request: *const c_char,
+    response: *mut *mut c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(response, || {
+            let actor = read_actor(actor_id)?;
+            let request: UserUpdateRequest = read_json("request", request)?;
+            block_on(UserService::update(&actor, request.get_id(), &request))
+        })
+    }
+}
+
+/// Find a user by id, the response is the user
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_find_one(
+    actor_id: *const c_char,
+    user_id: *const c_char,
+    response: *mut *mut c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(response, || {
+            let actor = read_actor(actor_id)?;
+            let user_id = read_uuid("user_id", user_id)?;
+            block_on(UserService::find_one(&actor, &user_id))
+        })
+    }
+}
+
+/// Find users from a `FindRequest`, the response is a page of users
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_find(
+    actor_id: *const c_char,
+    request: *const c_char,
+    response: *mut *mut c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(response, || {
+            let actor = read_actor(actor_id)?;
+            let request: FindRequest<UserFindRequestFilter> = read_json("request", request)?;
+            block_on(UserService::find(&actor, &request))
+        })
+    }
+}
+
+/// Delete a user with what depends on it, the response lists what was removed
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_delete(
+    actor_id: *const c_char,
+    user_id: *const c_char,
+    response: *mut *mut c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(response, || {
+            let actor = read_actor(actor_id)?;
+            let user_id = read_uuid("user_id", user_id)?;
+            block_on(UserService::delete(
+                &actor,
+                &UserDeleteRequest::new(&user_id),
+            ))
+        })
+    }
+}
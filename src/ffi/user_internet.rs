@@ -0,0 +1,88 @@
+use std::ffi::c_char;
+
+use crate::{
+    dtos::{
+        find_request::FindRequest,
+        user_internet::{
+            user_internet_add_request::UserInternetAddRequest,
+            user_internet_delete_request::UserInternetDeleteRequest,
+            user_internet_find_request_filter::UserInternetFindRequestFilter,
+        },
+    },
+    ffi::{
+        FototraStatus, block_on, error::FfiError, read_actor, read_json, read_str, read_uuid,
+        respond,
+    },
+    model::email_address::EmailAddress,
+    service::{error::ServiceError, user_internet::UserInternetService},
+};
+
+/// Add an email to a user from a `UserInternetAddRequest`, the response is the email
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_internet_create(
+    actor_id: *const c_char,
+    request: *const c_char,
+    response: *mut *mut c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(response, || {
+            let actor = read_actor(actor_id)?;
+            let request: UserInternetAddRequest = read_json("request", request)?;
+            block_on(UserInternetService::create(&actor, &request))
+        })
+    }
+}
+
+/// Find emails from a `FindRequest`, the response is a page of emails
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_internet_find(
+    actor_id: *const c_char,
+    request: *const c_char,
+    response: *mut *mut c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(response, || {
+            let actor = read_actor(actor_id)?;
+            let request: FindRequest<UserInternetFindRequestFilter> =
+                read_json("request", request)?;
+            block_on(UserInternetService::find(&actor, &request))
+        })
+    }
+}
+
+/// Remove an email of a user
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_internet_delete(
+    actor_id: *const c_char,
+    user_id: *const c_char,
+    email: *const c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(std::ptr::null_mut(), || {
+            let actor = read_actor(actor_id)?;
+            let user_id = read_uuid("user_id", user_id)?;
+            let email = EmailAddress::new(read_str("email", email)?).map_err(|e| {
+                ServiceError::new(FfiError::InvalidArgument {
+                    argument: "email".to_string(),
+                    reason: e.to_string(),
+                })
+            })?;
+            block_on(UserInternetService::delete(
+                &actor,
+                &UserInternetDeleteRequest::new(&user_id, &email),
+            ))
+        })
+    }
+}
@@ -0,0 +1,49 @@
+use std::ffi::c_char;
+
+use crate::{
+    dtos::user_password::user_password_add_request::UserPasswordAddRequest,
+    ffi::{FototraStatus, block_on, read_actor, read_json, read_str, read_uuid, respond},
+    service::user_password::UserPasswordService,
+};
+
+/// Set the password of a user from a `UserPasswordAddRequest`
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_password_create(
+    actor_id: *const c_char,
+    request: *const c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(std::ptr::null_mut(), || {
+            let actor = read_actor(actor_id)?;
+            let request: UserPasswordAddRequest = read_json("request", request)?;
+            block_on(UserPasswordService::create(&actor, &request)).map(|_| ())
+        })
+    }
+}
+
+/// Check the password of a user, `FOTOTRA_STATUS_OK` when it matches
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_password_match(
+    actor_id: *const c_char,
+    user_id: *const c_char,
+    password: *const c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(std::ptr::null_mut(), || {
+            let actor = read_actor(actor_id)?;
+            let user_id = read_uuid("user_id", user_id)?;
+            let password = read_str("password", password)?;
+            block_on(UserPasswordService::match_user_password(
+                &actor, &user_id, password,
+            ))
+        })
+    }
+}
@@ -0,0 +1,99 @@
+use std::ffi::c_char;
+
+use crate::{
+    dtos::{
+        find_request::FindRequest,
+        user_permission::{
+            user_permission_add_request::UserPermissionAddRequest,
+            user_permission_delete_request::UserPermissionDeleteRequest,
+            user_permission_find_request_filter::UserPermissionFindRequestFilter,
+        },
+    },
+    ffi::{FototraStatus, block_on, read_actor, read_json, read_str, read_uuid, respond},
+    service::user_permission::UserPermissionService,
+};
+
+/// Grant a permission from a `UserPermissionAddRequest`, the response is the grant
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_permission_create(
+    actor_id: *const c_char,
+    request: *const c_char,
+    response: *mut *mut c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(response, || {
+            let actor = read_actor(actor_id)?;
+            let request: UserPermissionAddRequest = read_json("request", request)?;
+            block_on(UserPermissionService::create(&actor, &request))
+        })
+    }
+}
+
+/// Find grants from a `FindRequest`, the response is a page of grants
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_permission_find(
+    actor_id: *const c_char,
+    request: *const c_char,
+    response: *mut *mut c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(response, || {
+            let actor = read_actor(actor_id)?;
+            let request: FindRequest<UserPermissionFindRequestFilter> =
+                read_json("request", request)?;
+            block_on(UserPermissionService::find(&actor, &request))
+        })
+    }
+}
+
+/// Revoke a permission of a user
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_permission_delete(
+    actor_id: *const c_char,
+    user_id: *const c_char,
+    permission: *const c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(std::ptr::null_mut(), || {
+            let actor = read_actor(actor_id)?;
+            let user_id = read_uuid("user_id", user_id)?;
+            let permission = read_str("permission", permission)?;
+            block_on(UserPermissionService::delete(
+                &actor,
+                &UserPermissionDeleteRequest::new(&user_id, permission),
+            ))
+        })
+    }
+}
+
+/// List the permissions held by a user, the response gives the grants they come from
+///
+/// # Safety
+///
+/// The pointers follow the contract of the `ffi` module
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fototra_user_permission_find_effective(
+    actor_id: *const c_char,
+    user_id: *const c_char,
+    response: *mut *mut c_char,
+) -> FototraStatus {
+    unsafe {
+        respond(response, || {
+            let actor = read_actor(actor_id)?;
+            let user_id = read_uuid("user_id", user_id)?;
+            block_on(UserPermissionService::find_effective(&actor, &user_id))
+        })
+    }
+}
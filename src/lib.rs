@@ -1,8 +1,10 @@
 // pub mod app;
 pub mod adapters;
 pub mod application;
+pub mod audit;
 pub mod configuration;
 pub mod dtos;
+pub mod ffi;
 pub mod model;
 pub mod repository;
 pub mod runtime;
@@ -1,6 +1,11 @@
+pub mod adapter_health;
+pub mod audit_event;
+pub mod domain_event;
+pub mod effective_permission;
 pub mod email_address;
 pub mod password;
 pub mod permission;
+pub mod permission_check;
 pub mod service_account;
 pub mod user;
 pub mod user_internet;
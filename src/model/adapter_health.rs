@@ -0,0 +1,21 @@
+use serde::Serialize;
+
+/// State reported by an adapter health check
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AdapterHealth {
+    Healthy,
+    /// Working with reduced capacity
+    Degraded {
+        reason: String,
+    },
+    Unhealthy {
+        reason: String,
+    },
+}
+
+impl AdapterHealth {
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, AdapterHealth::Healthy)
+    }
+}
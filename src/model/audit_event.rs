@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Keys whose values never reach an audit snapshot
+pub const REDACTED_KEYS: &[&str] = &["password", "password_hash", "secret", "token"];
+
+pub const REDACTED: &str = "[REDACTED]";
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AuditTarget {
+    entity: String,
+    id: String,
+}
+
+impl AuditTarget {
+    pub fn new(entity: &str, id: &str) -> Self {
+        Self {
+            entity: entity.to_string(),
+            id: id.to_string(),
+        }
+    }
+
+    pub fn get_entity(&self) -> &str {
+        &self.entity
+    }
+
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Denied { code: String, message: String },
+    Failed { code: String, message: String },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    id: Uuid,
+    timestamp: DateTime<Utc>,
+    actor: Option<Uuid>,
+    action: String,
+    target: Option<AuditTarget>,
+    before: Option<Value>,
+    after: Option<Value>,
+    outcome: AuditOutcome,
+}
+
+impl AuditEvent {
+    pub fn new(
+        actor: Option<&Uuid>,
+        action: &str,
+        target: Option<&AuditTarget>,
+        before: Option<&Value>,
+        after: Option<&Value>,
+        outcome: &AuditOutcome,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            actor: actor.copied(),
+            action: action.to_string(),
+            target: target.cloned(),
+            before: before.map(redact),
+            after: after.map(redact),
+            outcome: outcome.clone(),
+        }
+    }
+
+    pub fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    pub fn get_timestamp(&self) -> &DateTime<Utc> {
+        &self.timestamp
+    }
+
+    pub fn get_actor(&self) -> Option<&Uuid> {
+        self.actor.as_ref()
+    }
+
+    pub fn get_action(&self) -> &str {
+        &self.action
+    }
+
+    pub fn get_target(&self) -> Option<&AuditTarget> {
+        self.target.as_ref()
+    }
+
+    pub fn get_before(&self) -> Option<&Value> {
+        self.before.as_ref()
+    }
+
+    pub fn get_after(&self) -> Option<&Value> {
+        self.after.as_ref()
+    }
+
+    pub fn get_outcome(&self) -> &AuditOutcome {
+        &self.outcome
+    }
+}
+
+/// Replace the value of every secret key, at any depth, by a placeholder
+pub fn redact(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| {
+                    if REDACTED_KEYS.contains(&k.as_str()) {
+                        (k.clone(), Value::String(REDACTED.to_string()))
+                    } else {
+                        (k.clone(), redact(v))
+                    }
+                })
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.iter().map(redact).collect()),
+        v => v.clone(),
+    }
+}
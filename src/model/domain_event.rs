@@ -0,0 +1,119 @@
+pub mod error;
+pub mod outbox_policy;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::model::{
+    permission::Permission, user::User, user::UserID, user_internet::UserInternet,
+    user_permission::UserPermission,
+};
+
+/// Published into the runtime once a mutation is stored
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainEvent {
+    UserCreated {
+        user: User,
+    },
+    UserUpdated {
+        user: User,
+    },
+    UserDeleted {
+        user_id: UserID,
+    },
+    EmailAdded {
+        user_internet: UserInternet,
+    },
+    EmailRemoved {
+        user_internet: UserInternet,
+    },
+    PermissionGranted {
+        user_permission: UserPermission,
+    },
+    PermissionRevoked {
+        user_id: UserID,
+        permission: Permission,
+    },
+    PasswordChanged {
+        user_id: UserID,
+    },
+}
+
+impl DomainEvent {
+    /// Name of the event, like `user_created`
+    pub fn name(&self) -> &'static str {
+        match self {
+            DomainEvent::UserCreated { .. } => "user_created",
+            DomainEvent::UserUpdated { .. } => "user_updated",
+            DomainEvent::UserDeleted { .. } => "user_deleted",
+            DomainEvent::EmailAdded { .. } => "email_added",
+            DomainEvent::EmailRemoved { .. } => "email_removed",
+            DomainEvent::PermissionGranted { .. } => "permission_granted",
+            DomainEvent::PermissionRevoked { .. } => "permission_revoked",
+            DomainEvent::PasswordChanged { .. } => "password_changed",
+        }
+    }
+}
+
+/// Domain event with the id subscribers use to discard redeliveries
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DomainEventEnvelope {
+    id: Uuid,
+    occurred_at: DateTime<Utc>,
+    event: DomainEvent,
+}
+
+impl DomainEventEnvelope {
+    pub fn new(event: &DomainEvent) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            occurred_at: Utc::now(),
+            event: event.clone(),
+        }
+    }
+
+    /// Idempotency key, the same for every delivery of the event
+    pub fn get_id(&self) -> &Uuid {
+        &self.id
+    }
+
+    pub fn get_occurred_at(&self) -> &DateTime<Utc> {
+        &self.occurred_at
+    }
+
+    pub fn get_event(&self) -> &DomainEvent {
+        &self.event
+    }
+}
+
+/// Event the relay stopped handing to the subscribers, with why its last delivery failed
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ParkedDomainEvent {
+    envelope: DomainEventEnvelope,
+    reason: String,
+    parked_at: DateTime<Utc>,
+}
+
+impl ParkedDomainEvent {
+    pub fn new(envelope: &DomainEventEnvelope, reason: &str) -> Self {
+        Self {
+            envelope: envelope.clone(),
+            reason: reason.to_string(),
+            parked_at: Utc::now(),
+        }
+    }
+
+    pub fn get_envelope(&self) -> &DomainEventEnvelope {
+        &self.envelope
+    }
+
+    pub fn get_reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn get_parked_at(&self) -> &DateTime<Utc> {
+        &self.parked_at
+    }
+}
@@ -0,0 +1,28 @@
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{service::error::ErrorCategory, traits::service_error_trait::ServiceErrorTrait};
+
+#[derive(Debug, Error)]
+pub enum DomainEventError {
+    #[error("Event {id} is not pending in the outbox")]
+    EventNotPending { id: Uuid },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+impl ServiceErrorTrait for DomainEventError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            DomainEventError::EventNotPending { .. } => ErrorCategory::NotFound,
+            DomainEventError::Unknown(_) => ErrorCategory::Internal,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            DomainEventError::EventNotPending { .. } => "domain_event.event_not_pending",
+            DomainEventError::Unknown(_) => "domain_event.unknown",
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::configuration::{Configuration, error::ConfigurationError};
+
+/// How the relay hands the events of the outbox to the subscribers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutboxPolicy {
+    /// Failed deliveries of an event to one subscriber before the event is parked
+    pub max_attempts: u32,
+    /// Time a subscriber has to handle an event, in milliseconds, after which the
+    /// delivery counts as failed
+    pub delivery_timeout_ms: u64,
+}
+
+impl Default for OutboxPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            delivery_timeout_ms: 30_000,
+        }
+    }
+}
+
+impl OutboxPolicy {
+    /// Read `[outbox]`, the keys not set keeping their default
+    pub fn from_configuration(configuration: &Configuration) -> Result<Self, ConfigurationError> {
+        match configuration.get_path("outbox") {
+            None => Ok(Self::default()),
+            Some(_) => configuration.get_as("outbox"),
+        }
+    }
+
+    pub fn delivery_timeout(&self) -> Duration {
+        Duration::from_millis(self.delivery_timeout_ms)
+    }
+}
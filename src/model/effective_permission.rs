@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+use crate::model::permission::Permission;
+
+/// Where a permission held by a user comes from
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PermissionSource {
+    /// The permission is granted as is to the user
+    Direct,
+    /// The permission is covered by a wildcard grant like `user:*`
+    Wildcard { pattern: Permission },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EffectivePermission {
+    permission: Permission,
+    sources: Vec<PermissionSource>,
+}
+
+impl EffectivePermission {
+    pub fn new(permission: &str, sources: &[PermissionSource]) -> Self {
+        Self {
+            permission: permission.to_string(),
+            sources: sources.to_vec(),
+        }
+    }
+
+    pub fn get_permission(&self) -> &str {
+        &self.permission
+    }
+
+    pub fn get_sources(&self) -> &[PermissionSource] {
+        &self.sources
+    }
+}
@@ -8,7 +8,10 @@ use argon2::{
 use serde::Serialize;
 use thiserror::Error;
 
-use crate::model::password::password_level::PasswordLevel;
+use crate::{
+    model::password::password_level::PasswordLevel, service::error::ErrorCategory,
+    traits::service_error_trait::ServiceErrorTrait,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 pub struct Password(String);
@@ -57,3 +60,21 @@ pub enum PasswordError {
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
+
+impl ServiceErrorTrait for PasswordError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            PasswordError::InvalidPassword(_) => ErrorCategory::Validation,
+            PasswordError::IncorrectPassword => ErrorCategory::Auth,
+            PasswordError::Unknown(_) => ErrorCategory::Internal,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            PasswordError::InvalidPassword(_) => "password.invalid",
+            PasswordError::IncorrectPassword => "password.incorrect",
+            PasswordError::Unknown(_) => "password.unknown",
+        }
+    }
+}
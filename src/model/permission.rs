@@ -2,18 +2,45 @@ pub mod error;
 
 pub type Permission = String;
 
+pub const WILDCARD: &str = "*";
+
 pub const ALL_PERMISSIONS: &[&str] = &[
     "user:create",
     "user:update",
     "user:delete",
     "user:find",
     "user:find_one",
+    "user:onboard",
+    "user:suspend",
+    "user:deactivate",
+    "user:reactivate",
+    "user:soft_delete",
+    "user:restore",
     "user_internet:create",
     "user_internet:delete",
     "user_internet:find",
     "user_permission:create",
     "user_permission:delete",
     "user_permission:find",
+    "user_permission:find_effective",
+    "user_permission:check",
+    "user_permission:purge_expired",
     "user_password:create",
     "user_password:match",
 ];
+
+/// A wildcard permission is either `*` or ends with `:*`, like `user:*`
+pub fn is_wildcard(permission: &str) -> bool {
+    permission == WILDCARD || permission.ends_with(":*")
+}
+
+/// Check if a granted permission, wildcard or not, covers the requested one
+pub fn permission_matches(granted: &str, requested: &str) -> bool {
+    if granted == WILDCARD {
+        return true;
+    }
+    match granted.strip_suffix('*') {
+        Some(prefix) if prefix.ends_with(':') => requested.starts_with(prefix),
+        _ => granted == requested,
+    }
+}
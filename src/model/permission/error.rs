@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::{service::error::ErrorCategory, traits::service_error_trait::ServiceErrorTrait};
+
 #[derive(Debug, Error)]
 pub enum PermissionError {
     #[error("The permission {name} does not exists")]
@@ -7,3 +9,19 @@ pub enum PermissionError {
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
+
+impl ServiceErrorTrait for PermissionError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            PermissionError::PermissionNotExists { .. } => ErrorCategory::NotFound,
+            PermissionError::Unknown(_) => ErrorCategory::Internal,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            PermissionError::PermissionNotExists { .. } => "permission.not_exists",
+            PermissionError::Unknown(_) => "permission.unknown",
+        }
+    }
+}
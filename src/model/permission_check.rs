@@ -0,0 +1,80 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::{effective_permission::PermissionSource, permission::Permission, user::UserID};
+
+/// Why a permission is refused to a user
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DenialReason {
+    /// The permission is not known by the permission repository
+    UnknownPermission,
+    /// No grant, direct or wildcard, covers the permission
+    NoGrant,
+    /// A deny grant covers the permission
+    ExplicitDeny,
+    /// The grants covering the permission are expired
+    Expired,
+    /// The grants covering the permission are not valid yet
+    NotYetValid,
+    /// The principal is locked
+    Locked,
+    /// The principal or its grants could not be loaded
+    Unavailable,
+}
+
+impl Display for DenialReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DenialReason::UnknownPermission => "the permission does not exist",
+            DenialReason::NoGrant => "no grant covers the permission",
+            DenialReason::ExplicitDeny => "the permission is explicitly denied",
+            DenialReason::Expired => "the grant covering the permission is expired",
+            DenialReason::NotYetValid => "the grant covering the permission is not valid yet",
+            DenialReason::Locked => "the principal is locked",
+            DenialReason::Unavailable => "the principal or its grants could not be loaded",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum PermissionDecision {
+    Allowed { sources: Vec<PermissionSource> },
+    Denied { reason: DenialReason },
+}
+
+/// Result of a dry-run permission check
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PermissionCheck {
+    user_id: UserID,
+    permission: Permission,
+    decision: PermissionDecision,
+}
+
+impl PermissionCheck {
+    pub fn new(user_id: &UserID, permission: &str, decision: &PermissionDecision) -> Self {
+        Self {
+            user_id: *user_id,
+            permission: permission.to_string(),
+            decision: decision.clone(),
+        }
+    }
+
+    pub fn get_user_id(&self) -> &UserID {
+        &self.user_id
+    }
+
+    pub fn get_permission(&self) -> &str {
+        &self.permission
+    }
+
+    pub fn get_decision(&self) -> &PermissionDecision {
+        &self.decision
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        matches!(self.decision, PermissionDecision::Allowed { .. })
+    }
+}
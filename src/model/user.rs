@@ -1,12 +1,15 @@
 pub mod error;
 pub mod name;
+pub mod user_delete_policy;
+pub mod user_status;
 
 use std::{str::FromStr, sync::LazyLock};
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::model::user::name::Name;
+use crate::model::user::{name::Name, user_status::UserStatus};
 
 pub type UserID = Uuid;
 
@@ -15,6 +18,12 @@ pub struct User {
     id: UserID,
     lastname: Option<Name>,
     firstname: Name,
+    #[serde(default)]
+    status: UserStatus,
+    #[serde(default)]
+    status_changed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    status_reason: Option<String>,
 }
 
 impl User {
@@ -23,6 +32,24 @@ impl User {
             id: *id,
             firstname: firstname.clone(),
             lastname: lastname.cloned(),
+            status: UserStatus::Active,
+            status_changed_at: None,
+            status_reason: None,
+        }
+    }
+
+    /// Same user with another status, recording when and why it changed
+    pub fn with_status(
+        &self,
+        status: UserStatus,
+        reason: Option<&str>,
+        changed_at: Option<&DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            status,
+            status_reason: reason.map(str::to_string),
+            status_changed_at: changed_at.cloned(),
+            ..self.clone()
         }
     }
 
@@ -37,6 +64,22 @@ impl User {
     pub fn get_lastname(&self) -> Option<&Name> {
         self.lastname.as_ref()
     }
+
+    pub fn get_status(&self) -> UserStatus {
+        self.status
+    }
+
+    pub fn get_status_changed_at(&self) -> Option<&DateTime<Utc>> {
+        self.status_changed_at.as_ref()
+    }
+
+    pub fn get_status_reason(&self) -> Option<&str> {
+        self.status_reason.as_deref()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.status == UserStatus::Active
+    }
 }
 
 pub static DEFAULT_ADMIN_USER: LazyLock<User> = LazyLock::new(|| {
@@ -1,6 +1,14 @@
+use std::fmt::Display;
+
+use serde::Serialize;
 use thiserror::Error;
 
-use crate::model::user::UserID;
+use crate::{
+    configuration::error::ConfigurationError,
+    model::user::{UserID, user_status::UserStatus},
+    service::error::ErrorCategory,
+    traits::service_error_trait::ServiceErrorTrait,
+};
 
 #[derive(Debug, Error)]
 pub enum UserError {
@@ -8,6 +16,82 @@ pub enum UserError {
     MismatchUserId { id1: UserID, id2: UserID },
     #[error("User with id {id} does not exists")]
     UserNotExists { id: UserID },
+    #[error(
+        "User with id {id} still has {emails} email(s), {permissions} permission(s) and {} password",
+        if *password { "a" } else { "no" }
+    )]
+    HasDependents {
+        id: UserID,
+        emails: usize,
+        permissions: usize,
+        password: bool,
+    },
+    #[error("User with id {id} cannot go from {from} to {to}")]
+    InvalidStatusTransition {
+        id: UserID,
+        from: UserStatus,
+        to: UserStatus,
+    },
+    #[error("Invalid onboarding request: {}", violations.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))]
+    InvalidOnboarding { violations: Vec<UserViolation> },
+    #[error("Invalid user.delete_policy, expected cascade or restrict")]
+    InvalidDeletePolicy(#[source] ConfigurationError),
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
+
+impl ServiceErrorTrait for UserError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            UserError::MismatchUserId { .. } => ErrorCategory::Validation,
+            UserError::UserNotExists { .. } => ErrorCategory::NotFound,
+            UserError::HasDependents { .. } => ErrorCategory::Conflict,
+            UserError::InvalidStatusTransition { .. } => ErrorCategory::Conflict,
+            UserError::InvalidOnboarding { .. } => ErrorCategory::Validation,
+            UserError::InvalidDeletePolicy(_) => ErrorCategory::Validation,
+            UserError::Unknown(_) => ErrorCategory::Internal,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            UserError::MismatchUserId { .. } => "user.mismatch_user_id",
+            UserError::UserNotExists { .. } => "user.not_exists",
+            UserError::HasDependents { .. } => "user.has_dependents",
+            UserError::InvalidStatusTransition { .. } => "user.invalid_status_transition",
+            UserError::InvalidOnboarding { .. } => "user.invalid_onboarding",
+            UserError::InvalidDeletePolicy(_) => "user.invalid_delete_policy",
+            UserError::Unknown(_) => "user.unknown",
+        }
+    }
+}
+
+/// One problem of a request, named after the field it comes from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserViolation {
+    field: String,
+    message: String,
+}
+
+impl UserViolation {
+    pub fn new(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn get_field(&self) -> &str {
+        &self.field
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for UserViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
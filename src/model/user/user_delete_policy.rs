@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+use crate::configuration::{Configuration, error::ConfigurationError};
+
+/// What deleting a user does with the emails, grants and password referencing it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UserDeletePolicy {
+    /// Remove the dependents with the user
+    #[default]
+    Cascade,
+    /// Refuse to delete a user that still has dependents
+    Restrict,
+}
+
+impl UserDeletePolicy {
+    /// Read `[user] delete_policy`, rejecting a value that is not a known policy
+    pub fn from_configuration(configuration: &Configuration) -> Result<Self, ConfigurationError> {
+        match configuration.get_path("user.delete_policy") {
+            None => Ok(Self::default()),
+            Some(_) => configuration.get_as("user.delete_policy"),
+        }
+    }
+}
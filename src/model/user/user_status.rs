@@ -0,0 +1,27 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Lifecycle of a user, only an active user can be authenticated
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum UserStatus {
+    #[default]
+    Active,
+    Suspended,
+    Deactivated,
+    Deleted,
+}
+
+impl Display for UserStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            UserStatus::Active => "active",
+            UserStatus::Suspended => "suspended",
+            UserStatus::Deactivated => "deactivated",
+            UserStatus::Deleted => "deleted",
+        })
+    }
+}
@@ -1,6 +1,10 @@
 use thiserror::Error;
 
-use crate::model::{email_address::EmailAddress, user::UserID};
+use crate::{
+    model::{email_address::EmailAddress, user::UserID},
+    service::error::ErrorCategory,
+    traits::service_error_trait::ServiceErrorTrait,
+};
 
 #[derive(Debug, Error)]
 pub enum UserInternetError {
@@ -16,3 +20,25 @@ pub enum UserInternetError {
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
+
+impl ServiceErrorTrait for UserInternetError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            UserInternetError::UserNotExists { .. } => ErrorCategory::NotFound,
+            UserInternetError::EmailNotAssociatedToUser { .. } => ErrorCategory::NotFound,
+            UserInternetError::EmailAlreadyUsed { .. } => ErrorCategory::Conflict,
+            UserInternetError::Unknown(_) => ErrorCategory::Internal,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            UserInternetError::UserNotExists { .. } => "user_internet.user_not_exists",
+            UserInternetError::EmailNotAssociatedToUser { .. } => {
+                "user_internet.email_not_associated_to_user"
+            }
+            UserInternetError::EmailAlreadyUsed { .. } => "user_internet.email_already_used",
+            UserInternetError::Unknown(_) => "user_internet.unknown",
+        }
+    }
+}
@@ -1,6 +1,13 @@
 use thiserror::Error;
 
-use crate::model::{password::PasswordError, user::UserID};
+use crate::{
+    model::{
+        password::PasswordError,
+        user::{UserID, user_status::UserStatus},
+    },
+    service::error::ErrorCategory,
+    traits::service_error_trait::ServiceErrorTrait,
+};
 
 #[derive(Debug, Error)]
 pub enum UserPasswordError {
@@ -8,6 +15,28 @@ pub enum UserPasswordError {
     PasswordError(#[from] PasswordError),
     #[error("User with id {id} does not exists")]
     UserNotExists { id: UserID },
+    #[error("User with id {id} is {status}")]
+    UserNotActive { id: UserID, status: UserStatus },
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
+
+impl ServiceErrorTrait for UserPasswordError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            UserPasswordError::PasswordError(e) => e.category(),
+            UserPasswordError::UserNotExists { .. } => ErrorCategory::NotFound,
+            UserPasswordError::UserNotActive { .. } => ErrorCategory::Auth,
+            UserPasswordError::Unknown(_) => ErrorCategory::Internal,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            UserPasswordError::PasswordError(e) => e.code(),
+            UserPasswordError::UserNotExists { .. } => "user_password.user_not_exists",
+            UserPasswordError::UserNotActive { .. } => "user_password.user_not_active",
+            UserPasswordError::Unknown(_) => "user_password.unknown",
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use thiserror::Error;
 
+use crate::{service::error::ErrorCategory, traits::service_error_trait::ServiceErrorTrait};
+
 #[derive(Debug, Error)]
 pub enum UserPasswordPolicyError {
     #[error("The policy {name} does not exists")]
@@ -7,3 +9,21 @@ pub enum UserPasswordPolicyError {
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
+
+impl ServiceErrorTrait for UserPasswordPolicyError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            UserPasswordPolicyError::PolicyNotExists { .. } => ErrorCategory::NotFound,
+            UserPasswordPolicyError::Unknown(_) => ErrorCategory::Internal,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            UserPasswordPolicyError::PolicyNotExists { .. } => {
+                "user_password_policy.policy_not_exists"
+            }
+            UserPasswordPolicyError::Unknown(_) => "user_password_policy.unknown",
+        }
+    }
+}
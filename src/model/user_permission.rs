@@ -1,4 +1,5 @@
 pub mod error;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -8,13 +9,46 @@ use crate::model::{permission::Permission, user::UserID};
 pub struct UserPermission {
     user_id: UserID,
     permission: Permission,
+    #[serde(default)]
+    valid_from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    valid_until: Option<DateTime<Utc>>,
+    /// The grant refuses the permission instead of giving it
+    #[serde(default)]
+    deny: bool,
 }
 
 impl UserPermission {
     pub fn new(user_id: &uuid::Uuid, permisison: &str) -> Self {
+        Self::new_time_bound(user_id, permisison, None, None)
+    }
+
+    /// Grant which is only effective from `valid_from` (included) to `valid_until` (excluded)
+    pub fn new_time_bound(
+        user_id: &uuid::Uuid,
+        permisison: &str,
+        valid_from: Option<&DateTime<Utc>>,
+        valid_until: Option<&DateTime<Utc>>,
+    ) -> Self {
         Self {
             user_id: *user_id,
             permission: permisison.to_string(),
+            valid_from: valid_from.cloned(),
+            valid_until: valid_until.cloned(),
+            deny: false,
+        }
+    }
+
+    /// Grant refusing the permission while it is effective, whatever other grant covers it
+    pub fn new_deny(
+        user_id: &uuid::Uuid,
+        permisison: &str,
+        valid_from: Option<&DateTime<Utc>>,
+        valid_until: Option<&DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            deny: true,
+            ..Self::new_time_bound(user_id, permisison, valid_from, valid_until)
         }
     }
 
@@ -25,4 +59,28 @@ impl UserPermission {
     pub fn get_permission(&self) -> &str {
         &self.permission
     }
+
+    pub fn get_valid_from(&self) -> Option<&DateTime<Utc>> {
+        self.valid_from.as_ref()
+    }
+
+    pub fn get_valid_until(&self) -> Option<&DateTime<Utc>> {
+        self.valid_until.as_ref()
+    }
+
+    pub fn is_deny(&self) -> bool {
+        self.deny
+    }
+
+    pub fn is_expired_at(&self, instant: &DateTime<Utc>) -> bool {
+        self.valid_until.is_some_and(|until| until <= *instant)
+    }
+
+    pub fn is_pending_at(&self, instant: &DateTime<Utc>) -> bool {
+        self.valid_from.is_some_and(|from| from > *instant)
+    }
+
+    pub fn is_active_at(&self, instant: &DateTime<Utc>) -> bool {
+        !self.is_expired_at(instant) && !self.is_pending_at(instant)
+    }
 }
@@ -1,6 +1,11 @@
+use chrono::{DateTime, Utc};
 use thiserror::Error;
 
-use crate::model::{permission::Permission, user::UserID};
+use crate::{
+    model::{permission::Permission, user::UserID},
+    service::error::ErrorCategory,
+    traits::service_error_trait::ServiceErrorTrait,
+};
 
 #[derive(Debug, Error)]
 pub enum UserPermissionError {
@@ -12,6 +17,46 @@ pub enum UserPermissionError {
     PermissionAlreadyAssigned { permission: Permission },
     #[error("Permission {permission} is already not assigned")]
     PermissionAlreadyNotAssigned { permission: Permission },
+    #[error(
+        "Permission {permission} cannot be valid until {valid_until} before being valid from {valid_from}"
+    )]
+    InvalidValidityPeriod {
+        permission: Permission,
+        valid_from: DateTime<Utc>,
+        valid_until: DateTime<Utc>,
+    },
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
+
+impl ServiceErrorTrait for UserPermissionError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            UserPermissionError::UserNotExists { .. } => ErrorCategory::NotFound,
+            UserPermissionError::PermissionNotExists { .. } => ErrorCategory::NotFound,
+            UserPermissionError::PermissionAlreadyAssigned { .. } => ErrorCategory::Conflict,
+            UserPermissionError::PermissionAlreadyNotAssigned { .. } => ErrorCategory::NotFound,
+            UserPermissionError::InvalidValidityPeriod { .. } => ErrorCategory::Validation,
+            UserPermissionError::Unknown(_) => ErrorCategory::Internal,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            UserPermissionError::UserNotExists { .. } => "user_permission.user_not_exists",
+            UserPermissionError::PermissionNotExists { .. } => {
+                "user_permission.permission_not_exists"
+            }
+            UserPermissionError::PermissionAlreadyAssigned { .. } => {
+                "user_permission.permission_already_assigned"
+            }
+            UserPermissionError::PermissionAlreadyNotAssigned { .. } => {
+                "user_permission.permission_already_not_assigned"
+            }
+            UserPermissionError::InvalidValidityPeriod { .. } => {
+                "user_permission.invalid_validity_period"
+            }
+            UserPermissionError::Unknown(_) => "user_permission.unknown",
+        }
+    }
+}
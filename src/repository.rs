@@ -1,4 +1,7 @@
+pub mod outbox_repository;
 pub mod permission_repository;
+pub mod transaction;
+pub mod unit_of_work;
 pub mod user_internet_repository;
 pub mod user_password_policy_repository;
 pub mod user_password_repository;
@@ -0,0 +1,22 @@
+use std::{ops::Deref, sync::Arc};
+
+use crate::traits::domain_event::outbox_repository_trait::OutboxRepositoryTrait;
+
+pub struct OutboxRepository {
+    inner: Arc<dyn OutboxRepositoryTrait>,
+}
+
+impl OutboxRepository {
+    pub fn new(outbox_repository: Arc<dyn OutboxRepositoryTrait>) -> Self {
+        Self {
+            inner: outbox_repository.clone(),
+        }
+    }
+}
+
+impl Deref for OutboxRepository {
+    type Target = dyn OutboxRepositoryTrait;
+    fn deref(&self) -> &Self::Target {
+        self.inner.deref()
+    }
+}
@@ -0,0 +1,25 @@
+pub mod error;
+
+use std::{ops::Deref, sync::Arc};
+
+use crate::traits::transaction_trait::TransactionTrait;
+
+/// Repositories bound to a transaction, returned by `UnitOfWork::begin`
+pub struct Transaction {
+    inner: Arc<dyn TransactionTrait>,
+}
+
+impl Transaction {
+    pub fn new(transaction: Arc<dyn TransactionTrait>) -> Self {
+        Self {
+            inner: transaction.clone(),
+        }
+    }
+}
+
+impl Deref for Transaction {
+    type Target = dyn TransactionTrait;
+    fn deref(&self) -> &Self::Target {
+        self.inner.deref()
+    }
+}
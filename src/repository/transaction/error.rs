@@ -0,0 +1,37 @@
+use thiserror::Error;
+
+use crate::{service::error::ErrorCategory, traits::service_error_trait::ServiceErrorTrait};
+
+#[derive(Debug, Error)]
+pub enum TransactionError {
+    #[error("Transaction already committed or rolled back")]
+    AlreadyFinished,
+    #[error("No unit of work registered by the repository adapters")]
+    NotSupported,
+    #[error("Transaction conflicts with a concurrent write: {reason}")]
+    Conflict { reason: String },
+    #[error(transparent)]
+    Unknown(#[from] anyhow::Error),
+}
+
+impl ServiceErrorTrait for TransactionError {
+    fn category(&self) -> ErrorCategory {
+        match self {
+            TransactionError::AlreadyFinished | TransactionError::Conflict { .. } => {
+                ErrorCategory::Conflict
+            }
+            TransactionError::NotSupported | TransactionError::Unknown(_) => {
+                ErrorCategory::Internal
+            }
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            TransactionError::AlreadyFinished => "transaction.already_finished",
+            TransactionError::NotSupported => "transaction.not_supported",
+            TransactionError::Conflict { .. } => "transaction.conflict",
+            TransactionError::Unknown(_) => "transaction.unknown",
+        }
+    }
+}
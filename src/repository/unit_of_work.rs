@@ -0,0 +1,22 @@
+use std::{ops::Deref, sync::Arc};
+
+use crate::traits::unit_of_work_trait::UnitOfWorkTrait;
+
+pub struct UnitOfWork {
+    inner: Arc<dyn UnitOfWorkTrait>,
+}
+
+impl UnitOfWork {
+    pub fn new(unit_of_work: Arc<dyn UnitOfWorkTrait>) -> Self {
+        Self {
+            inner: unit_of_work.clone(),
+        }
+    }
+}
+
+impl Deref for UnitOfWork {
+    type Target = dyn UnitOfWorkTrait;
+    fn deref(&self) -> &Self::Target {
+        self.inner.deref()
+    }
+}
@@ -1,79 +1,903 @@
+pub mod error;
+pub mod plugin;
+
 use std::{
+    any::type_name,
     any::{Any, TypeId},
-    collections::{HashMap, HashSet},
-    ffi::c_void,
-    mem::zeroed,
-    sync::{Arc, OnceLock},
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{self, Debug, Display, Formatter},
+    path::Path,
+    pin::Pin,
+    sync::{Arc, LazyLock, OnceLock, Weak},
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail};
+use futures::future::join_all;
+use tokio::{
+    sync::{Mutex, Notify, OnceCell, RwLock},
+    task::{JoinHandle, futures::TaskLocalFuture},
+};
+use uuid::Uuid;
+
+use crate::{
+    configuration::{
+        Configuration,
+        diff::ConfigurationDifference,
+        layer::ConfigurationOrigins,
+        loader::ConfigurationLoader,
+        schema::ConfigurationSchema,
+        watch::{ConfigurationChange, FileStamps},
+    },
+    model::{
+        adapter_health::AdapterHealth,
+        domain_event::{DomainEvent, DomainEventEnvelope, outbox_policy::OutboxPolicy},
+    },
+    repository::outbox_repository::OutboxRepository,
+    runtime::{
+        error::RuntimeError,
+        plugin::{load_plugin, plugin_paths},
+    },
+    traits::{
+        adapter_loader_trait::AdapterLoaderTrait,
+        configuration_subscriber_trait::ConfigurationSubscriberTrait,
+        event_subscriber_trait::EventSubscriberTrait,
+    },
 };
 
-use tokio::sync::{Mutex, RwLock};
+type Service = Arc<dyn Any + Send + Sync>;
+type ServiceFactory = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = Service> + Send>> + Send + Sync>;
+/// Type of the service and its name, none for the default registration
+type ServiceKey = (TypeId, Option<String>);
+type ServiceRegistry = HashMap<ServiceKey, Registration>;
+type SubscriberRegistry = HashMap<TypeId, Vec<Arc<dyn Any + Send + Sync>>>;
+/// Subscribers of the configuration with the dotted path each one watches
+type ConfigurationSubscribers = Vec<(String, Arc<dyn ConfigurationSubscriberTrait>)>;
+const OUTBOX_RELAY_BATCH: u16 = 100;
 
-use crate::{configuration::load_configuration, traits::adapter_loader_trait::AdapterLoaderTrait};
+/// Runtime used outside of any `Runtime::scope`, the one handed to the C ABI
+static GLOBAL_RUNTIME: LazyLock<Runtime> = LazyLock::new(Runtime::new);
+
+tokio::task_local! {
+    static CURRENT_RUNTIME: Runtime;
+    static REQUEST_SERVICES: Arc<Mutex<HashMap<ServiceKey, Service>>>;
+}
+
+/// How a registered service is obtained
+#[derive(Clone)]
+enum Registration {
+    /// The same instance for every get
+    Instance(Service),
+    /// Created by the factory on the first get, then shared
+    Lazy(ServiceFactory, Arc<OnceCell<Service>>),
+    /// Created by the factory once per request scope
+    Scoped(ServiceFactory),
+}
+
+/// Adapters added but not started yet, and the started ones in startup order
+struct AdapterRegistry {
+    pending: Vec<Arc<dyn AdapterLoaderTrait>>,
+    started: Vec<Arc<dyn AdapterLoaderTrait>>,
+}
+
+struct OutboxRelay {
+    wake_up: Arc<Notify>,
+    task: JoinHandle<()>,
+}
+
+/// Progress of the delivery of a pending event, subscribers being told apart by address
+#[derive(Default)]
+struct OutboxDelivery {
+    /// Subscribers that handled the event, not handed it again
+    handled: HashSet<usize>,
+    /// Failed deliveries to each subscriber that did not handle it yet
+    attempts: HashMap<usize, u32>,
+}
+
+/// Everything owned by one runtime instance
+struct RuntimeState {
+    registry: OnceLock<RwLock<ServiceRegistry>>,
+    adapters: Mutex<AdapterRegistry>,
+    subscribers: RwLock<SubscriberRegistry>,
+    outbox_relay: Mutex<Option<OutboxRelay>>,
+    outbox_relay_lock: Mutex<()>,
+    /// Delivery of each pending event handed to the subscribers, until it leaves the outbox
+    outbox_deliveries: Mutex<HashMap<Uuid, OutboxDelivery>>,
+    configuration_schemas: Mutex<Vec<ConfigurationSchema>>,
+    /// Loader of the registered configuration, run again by each reload
+    configuration_loader: Mutex<Option<ConfigurationLoader>>,
+    configuration_subscribers: RwLock<ConfigurationSubscribers>,
+    configuration_watcher: Mutex<Option<JoinHandle<()>>>,
+    /// Last error of each background task, until the task succeeds again
+    failures: Mutex<BTreeMap<String, String>>,
+}
 
-type ServiceRegistry = HashMap<TypeId, Arc<dyn Any + Send + Sync>>;
-static REGISTRY: OnceLock<Arc<RwLock<ServiceRegistry>>> = OnceLock::new();
-static ADAPTER_LIST: OnceLock<Arc<Mutex<HashSet<Arc<dyn AdapterLoaderTrait>>>>> = OnceLock::new();
+impl Drop for RuntimeState {
+    fn drop(&mut self) {
+        if let Some(relay) = self.outbox_relay.get_mut().take() {
+            relay.task.abort();
+        }
+        if let Some(watcher) = self.configuration_watcher.get_mut().take() {
+            watcher.abort();
+        }
+    }
+}
 
-#[repr(C)]
+/// Handle on a runtime instance, cloning it shares the same registry and adapters
+#[derive(Clone)]
 pub struct Runtime {
-    _phantom: c_void,
+    state: Arc<RuntimeState>,
 }
 
+/// Global runtime, for the hosts loading the library
 #[unsafe(no_mangle)]
-pub extern "C" fn get_runtime() -> Runtime {
-    Runtime::get_instance()
+pub extern "C" fn get_runtime() -> *const Runtime {
+    &*GLOBAL_RUNTIME
+}
+
+impl Debug for Runtime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Runtime").finish_non_exhaustive()
+    }
+}
+
+impl Default for Runtime {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Runtime {
-    pub fn get_instance() -> Self {
+    /// Create a runtime with its own registry, adapters and subscribers
+    pub fn new() -> Self {
         Self {
-            _phantom: unsafe { zeroed() },
+            state: Arc::new(RuntimeState {
+                registry: OnceLock::new(),
+                adapters: Mutex::new(AdapterRegistry {
+                    pending: Vec::new(),
+                    started: Vec::new(),
+                }),
+                subscribers: RwLock::new(HashMap::new()),
+                outbox_relay: Mutex::new(None),
+                outbox_relay_lock: Mutex::new(()),
+                outbox_deliveries: Mutex::new(HashMap::new()),
+                configuration_schemas: Mutex::new(Vec::new()),
+                configuration_loader: Mutex::new(None),
+                configuration_subscribers: RwLock::new(Vec::new()),
+                configuration_watcher: Mutex::new(None),
+                failures: Mutex::new(BTreeMap::new()),
+            }),
+        }
+    }
+
+    /// The runtime of the current `scope`, the global runtime outside of any
+    pub fn get_instance() -> Self {
+        CURRENT_RUNTIME
+            .try_with(Runtime::clone)
+            .unwrap_or_else(|_| GLOBAL_RUNTIME.clone())
+    }
+
+    /// Run the future with this runtime as the one returned by `get_instance`
+    pub fn scope<F: Future>(&self, future: F) -> TaskLocalFuture<Runtime, F> {
+        CURRENT_RUNTIME.scope(self.clone(), future)
+    }
+
+    /// Initialize the runtime (call once at startup) and add the adapters of the
+    /// plugins listed in the configuration
+    pub async fn init(&self) -> Result<(), RuntimeError> {
+        self.init_with(&ConfigurationLoader::new()).await
+    }
+
+    /// Initialize the runtime with the configuration of the loader, registering its
+    /// `ConfigurationOrigins` next to it
+    pub async fn init_with(&self, loader: &ConfigurationLoader) -> Result<(), RuntimeError> {
+        if self.state.registry.get().is_some() {
+            return Ok(());
+        }
+        let loader = self
+            .state
+            .configuration_schemas
+            .lock()
+            .await
+            .iter()
+            .fold(loader.clone(), |loader, schema| {
+                loader.with_schema(schema.clone())
+            });
+        // loaded first so that a failure leaves the runtime uninitialized
+        let (configuration, origins) = loader.load_async().await?;
+        if self.state.registry.set(RwLock::new(HashMap::new())).is_ok() {
+            self.register(configuration).await?;
+            self.register(origins).await?;
+            *self.state.configuration_loader.lock().await = Some(loader);
+            self.load_plugins().await?;
+        }
+        Ok(())
+    }
+
+    /// Declare the configuration expected by a component, validated by `init` with the
+    /// other schemas
+    pub async fn add_configuration_schema(
+        &self,
+        schema: ConfigurationSchema,
+    ) -> Result<(), RuntimeError> {
+        if self.is_initialized() {
+            return Err(RuntimeError::AlreadyInitialized);
+        }
+        self.state.configuration_schemas.lock().await.push(schema);
+        Ok(())
+    }
+
+    /// Add an adapter, loaded by `start` once the adapters it depends on are
+    pub async fn add_adapter(
+        &self,
+        adapter: Arc<dyn AdapterLoaderTrait>,
+    ) -> Result<(), RuntimeError> {
+        let mut adapters = self.state.adapters.lock().await;
+        if adapters
+            .pending
+            .iter()
+            .chain(adapters.started.iter())
+            .any(|added| added.name() == adapter.name())
+        {
+            return Err(RuntimeError::DuplicateAdapter {
+                name: adapter.name().to_string(),
+            });
+        }
+        adapters.pending.push(adapter);
+        Ok(())
+    }
+
+    /// Load the shared library of a plugin and add its adapter
+    pub async fn add_plugin(&self, path: &Path) -> Result<(), RuntimeError> {
+        self.add_adapter(load_plugin(self, path)?).await
+    }
+
+    /// Add the adapter of each plugin listed under `[plugins] paths` in the configuration
+    pub async fn load_plugins(&self) -> Result<(), RuntimeError> {
+        let Some(configuration) = self.get::<Configuration>().await else {
+            return Err(RuntimeError::NotInitialized);
+        };
+        for path in plugin_paths(&configuration)? {
+            self.add_plugin(Path::new(&path)).await?;
         }
+        Ok(())
     }
 
-    /// Initialize the runtime (call once at startup)
-    pub async fn init(&self) -> Result<(), &'static str> {
-        if REGISTRY.get().is_none() {
-            REGISTRY
-                .set(Arc::new(RwLock::new(HashMap::new())))
-                .map_err(|_| "Runtime already initialized")?;
-            self.register(load_configuration()).await;
+    /// Load every adapter added since the last start, each one after its dependencies.
+    ///
+    /// The configuration is checked first against the schemas of these adapters, and
+    /// completed with their defaults, so that nothing is loaded when it does not fit.
+    pub async fn start(&self) -> Result<(), RuntimeError> {
+        if !self.is_initialized() {
+            return Err(RuntimeError::NotInitialized);
+        }
+        let order = {
+            let adapters = self.state.adapters.lock().await;
+            startup_order(&adapters.pending, &adapters.started)?
+        };
+        self.apply_adapter_schemas(&order).await?;
+        for adapter in order {
+            self.scope(adapter.load())
+                .await
+                .map_err(|source| RuntimeError::StartFailed {
+                    adapter: adapter.name().to_string(),
+                    source,
+                })?;
+            let mut adapters = self.state.adapters.lock().await;
+            adapters
+                .pending
+                .retain(|pending| pending.name() != adapter.name());
+            adapters.started.push(adapter);
         }
         Ok(())
     }
 
-    pub async fn add_adapter(&self, adapter: Arc<dyn AdapterLoaderTrait>) {
-        let adapter = adapter.clone();
-        if let Some(list) = ADAPTER_LIST.get() {
-            let mut list = list.lock().await;
-            list.insert(adapter.clone());
+    /// Load the configuration again with the schemas of the adapters added, so that it
+    /// is checked against every layer as a reload would, the schemas being kept for the
+    /// next reloads
+    async fn apply_adapter_schemas(
+        &self,
+        adapters: &[Arc<dyn AdapterLoaderTrait>],
+    ) -> Result<(), RuntimeError> {
+        let schemas: Vec<ConfigurationSchema> = adapters
+            .iter()
+            .filter_map(|adapter| adapter.configuration_schema())
+            .collect();
+        if schemas.is_empty() {
+            return Ok(());
+        }
+        let mut loader = self.state.configuration_loader.lock().await;
+        let with_schemas = schemas.iter().fold(
+            loader.clone().ok_or(RuntimeError::NotInitialized)?,
+            |loader, schema| loader.with_schema(schema.clone()),
+        );
+        let (configuration, origins) = with_schemas.load_async().await?;
+        self.register(configuration).await?;
+        self.register(origins).await?;
+        *loader = Some(with_schemas);
+        self.state
+            .configuration_schemas
+            .lock()
+            .await
+            .extend(schemas);
+        Ok(())
+    }
+
+    /// Health of every started adapter, in startup order, followed by the background
+    /// tasks whose last run failed, degraded with their error
+    pub async fn health(&self) -> Vec<(String, AdapterHealth)> {
+        let started = self.state.adapters.lock().await.started.clone();
+        let mut health = Vec::new();
+        for adapter in started {
+            health.push((
+                adapter.name().to_string(),
+                self.scope(adapter.health()).await,
+            ));
+        }
+        for (task, reason) in self.state.failures.lock().await.iter() {
+            health.push((
+                task.clone(),
+                AdapterHealth::Degraded {
+                    reason: reason.clone(),
+                },
+            ));
+        }
+        health
+    }
+
+    /// Report the failure of a background task, like a subscriber or a sink, in `health`
+    pub async fn report_failure(&self, task: &str, reason: &str) {
+        self.state
+            .failures
+            .lock()
+            .await
+            .insert(task.to_string(), reason.to_string());
+    }
+
+    /// Forget the failure of a background task once it succeeded
+    pub async fn clear_failure(&self, task: &str) {
+        self.state.failures.lock().await.remove(task);
+    }
+
+    /// Report the outcome of a background task in `health`
+    async fn report<T, E: Display>(&self, task: &str, result: &Result<T, E>) {
+        match result.as_ref().map_err(|e| format!("{e:#}")) {
+            Ok(_) => self.clear_failure(task).await,
+            Err(reason) => self.report_failure(task, &reason).await,
+        }
+    }
+
+    /// Shut the started adapters down in the reverse order of the startup, then stop
+    /// the outbox relay. Every adapter is shut down even if another one fails.
+    pub async fn shutdown(&self) -> Result<(), RuntimeError> {
+        let started = std::mem::take(&mut self.state.adapters.lock().await.started);
+        let mut failures = Vec::new();
+        for adapter in started.iter().rev() {
+            if let Err(e) = self.scope(adapter.shutdown()).await {
+                failures.push((adapter.name().to_string(), e.to_string()));
+            }
+        }
+        if let Some(relay) = self.state.outbox_relay.lock().await.take() {
+            relay.task.abort();
+        }
+        if let Some(watcher) = self.state.configuration_watcher.lock().await.take() {
+            watcher.abort();
+        }
+        if failures.is_empty() {
+            Ok(())
         } else {
-            let mut hashset = HashSet::new();
-            hashset.insert(adapter.clone());
-            ADAPTER_LIST.set(Arc::new(Mutex::new(hashset))).unwrap();
+            Err(RuntimeError::ShutdownFailed { failures })
         }
-        adapter.load().await.unwrap();
     }
 
-    /// Register a service
-    pub async fn register<T: Send + Sync + 'static>(&self, service: T) {
-        let registry = REGISTRY.get().expect("Runtime not initialized");
+    /// Run the future as one request: services registered with `register_scoped` are
+    /// created once for it, and dropped with it
+    pub async fn request_scope<F: Future>(&self, future: F) -> F::Output {
+        self.scope(REQUEST_SERVICES.scope(Arc::new(Mutex::new(HashMap::new())), future))
+            .await
+    }
+
+    /// Register a service, silently replacing the one already registered for the type
+    ///
+    /// The replaced service stays alive for those already holding it, only the next `get`
+    /// returns the new one: this is how the configuration and the adapters are swapped.
+    /// Use `try_register` to fail with `RuntimeError::AlreadyRegistered` instead.
+    pub async fn register<T: Send + Sync + 'static>(&self, service: T) -> Result<(), RuntimeError> {
+        self.insert::<T>(None, Registration::Instance(Arc::new(service)), true)
+            .await
+    }
+
+    /// Register a service under a name, silently replacing the one already registered with it
+    ///
+    /// Use `try_register_named` to fail with `RuntimeError::AlreadyRegistered` instead.
+    pub async fn register_named<T: Send + Sync + 'static>(
+        &self,
+        name: &str,
+        service: T,
+    ) -> Result<(), RuntimeError> {
+        self.insert::<T>(Some(name), Registration::Instance(Arc::new(service)), true)
+            .await
+    }
+
+    /// Register a service, refusing to replace the one already registered
+    pub async fn try_register<T: Send + Sync + 'static>(
+        &self,
+        service: T,
+    ) -> Result<(), RuntimeError> {
+        self.insert::<T>(None, Registration::Instance(Arc::new(service)), false)
+            .await
+    }
+
+    /// Register a service under a name, refusing to replace the one already registered with it
+    pub async fn try_register_named<T: Send + Sync + 'static>(
+        &self,
+        name: &str,
+        service: T,
+    ) -> Result<(), RuntimeError> {
+        self.insert::<T>(Some(name), Registration::Instance(Arc::new(service)), false)
+            .await
+    }
+
+    /// Register a factory creating the service on the first get, shared afterwards
+    pub async fn register_factory<T, F, Fut>(&self, factory: F) -> Result<(), RuntimeError>
+    where
+        T: Send + Sync + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        self.insert::<T>(None, lazy(factory), true).await
+    }
+
+    /// Register under a name a factory creating the service on the first get
+    pub async fn register_factory_named<T, F, Fut>(
+        &self,
+        name: &str,
+        factory: F,
+    ) -> Result<(), RuntimeError>
+    where
+        T: Send + Sync + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        self.insert::<T>(Some(name), lazy(factory), true).await
+    }
+
+    /// Register a factory creating the service once per `request_scope`, and on each
+    /// get outside of any
+    pub async fn register_scoped<T, F, Fut>(&self, factory: F) -> Result<(), RuntimeError>
+    where
+        T: Send + Sync + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        self.insert::<T>(None, Registration::Scoped(service_factory(factory)), true)
+            .await
+    }
+
+    /// Register under a name a factory creating the service once per `request_scope`
+    pub async fn register_scoped_named<T, F, Fut>(
+        &self,
+        name: &str,
+        factory: F,
+    ) -> Result<(), RuntimeError>
+    where
+        T: Send + Sync + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+    {
+        self.insert::<T>(
+            Some(name),
+            Registration::Scoped(service_factory(factory)),
+            true,
+        )
+        .await
+    }
+
+    async fn insert<T: 'static>(
+        &self,
+        name: Option<&str>,
+        registration: Registration,
+        overwrite: bool,
+    ) -> Result<(), RuntimeError> {
+        let registry = self
+            .state
+            .registry
+            .get()
+            .ok_or(RuntimeError::NotInitialized)?;
         let mut map = registry.write().await;
-        map.insert(TypeId::of::<T>(), Arc::new(service));
+        let key = (TypeId::of::<T>(), name.map(str::to_string));
+        if !overwrite && map.contains_key(&key) {
+            return Err(RuntimeError::AlreadyRegistered {
+                service: type_name::<T>().to_string(),
+                name: key.1,
+            });
+        }
+        map.insert(key, registration);
+        Ok(())
     }
 
-    /// Get a service
+    /// Get a service, none before the runtime is initialized
     pub async fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
-        let registry = REGISTRY.get().expect("Runtime not initialized");
-        let map = registry.read().await;
-        map.get(&TypeId::of::<T>())
-            .and_then(|any| any.clone().downcast::<T>().ok())
+        self.resolve(None).await
+    }
+
+    /// Get a service registered under a name
+    pub async fn get_named<T: Send + Sync + 'static>(&self, name: &str) -> Option<Arc<T>> {
+        self.resolve(Some(name)).await
+    }
+
+    async fn resolve<T: Send + Sync + 'static>(&self, name: Option<&str>) -> Option<Arc<T>> {
+        let key = (TypeId::of::<T>(), name.map(str::to_string));
+        let registration = self.state.registry.get()?.read().await.get(&key)?.clone();
+        let service = match registration {
+            Registration::Instance(service) => service,
+            Registration::Lazy(factory, created) => created.get_or_init(|| factory()).await.clone(),
+            Registration::Scoped(factory) => match REQUEST_SERVICES.try_with(Arc::clone) {
+                Ok(services) => {
+                    if let Some(service) = services.lock().await.get(&key) {
+                        return service.clone().downcast::<T>().ok();
+                    }
+                    let service = factory().await;
+                    services.lock().await.entry(key).or_insert(service).clone()
+                }
+                Err(_) => factory().await,
+            },
+        };
+        service.downcast::<T>().ok()
+    }
+
+    /// Subscribe to every event of type E published from now on
+    pub async fn subscribe<E: Clone + Send + Sync + 'static>(
+        &self,
+        subscriber: Arc<dyn EventSubscriberTrait<E>>,
+    ) {
+        let mut map = self.state.subscribers.write().await;
+        map.entry(TypeId::of::<E>())
+            .or_default()
+            .push(Arc::new(subscriber));
+    }
+
+    /// Hand the event to each subscriber on its own task, without waiting for them
+    pub async fn publish<E: Clone + Send + Sync + 'static>(&self, event: E) {
+        for subscriber in self.subscribers_of::<E>().await {
+            let event = event.clone();
+            let runtime = self.clone();
+            tokio::spawn(self.scope(async move {
+                let result = subscriber.handle(&event).await;
+                runtime
+                    .report(&format!("subscriber {}", subscriber.name()), &result)
+                    .await;
+            }));
+        }
+    }
+
+    async fn subscribers_of<E: Clone + Send + Sync + 'static>(
+        &self,
+    ) -> Vec<Arc<dyn EventSubscriberTrait<E>>> {
+        let map = self.state.subscribers.read().await;
+        map.get(&TypeId::of::<E>())
+            .into_iter()
+            .flatten()
+            .filter_map(|any| any.downcast_ref::<Arc<dyn EventSubscriberTrait<E>>>())
+            .cloned()
+            .collect()
+    }
+
+    /// Hand the event to the subscribers at once and wait for all of them, each one
+    /// failing when it does not handle the event within the timeout
+    async fn deliver<E: Clone + Send + Sync + 'static>(
+        &self,
+        event: &E,
+        subscribers: &[Arc<dyn EventSubscriberTrait<E>>],
+        timeout: Duration,
+    ) -> Vec<anyhow::Result<()>> {
+        self.scope(join_all(subscribers.iter().map(|subscriber| async move {
+            tokio::time::timeout(timeout, subscriber.handle(event))
+                .await
+                .unwrap_or_else(|_| Err(anyhow!("not handled within {timeout:?}")))
+        })))
+        .await
+    }
+
+    /// Deliver a domain event produced by a service once its mutation succeeded.
+    ///
+    /// When an outbox is registered the repository already stored the event with the
+    /// mutation, so the relay is woken up, or run once on its own task when it was not
+    /// started. Without an outbox the event is published now.
+    pub async fn dispatch(&self, event: DomainEvent) {
+        if self.get::<OutboxRepository>().await.is_none() {
+            self.publish(DomainEventEnvelope::new(&event)).await;
+            return;
+        }
+        match self.state.outbox_relay.lock().await.as_ref() {
+            Some(relay) => relay.wake_up.notify_one(),
+            None => {
+                let runtime = self.clone();
+                tokio::spawn(async move {
+                    let relayed = runtime.relay_outbox().await;
+                    runtime.report("outbox relay", &relayed).await;
+                });
+            }
+        }
+    }
+
+    /// Publish the pending events of the outbox in order, acknowledging each one once
+    /// every subscriber handled it.
+    ///
+    /// The pass stops at the first event a subscriber fails to handle, which stays
+    /// pending: the next pass hands it again to the subscribers that did not handle it
+    /// yet, so delivery is at least once and subscribers recognize an event already
+    /// handled by its id. Once a subscriber failed `[outbox] max_attempts` times the
+    /// event is parked instead, reported in `health`, and the pass goes on.
+    pub async fn relay_outbox(&self) -> anyhow::Result<usize> {
+        let Some(outbox) = self.get::<OutboxRepository>().await else {
+            return Ok(0);
+        };
+        let policy = match self.get::<Configuration>().await {
+            Some(configuration) => OutboxPolicy::from_configuration(&configuration)?,
+            None => OutboxPolicy::default(),
+        };
+        let _guard = self.state.outbox_relay_lock.lock().await;
+        let pending = outbox.find_pending(OUTBOX_RELAY_BATCH).await?;
+        let subscribers = self.subscribers_of::<DomainEventEnvelope>().await;
+        let mut deliveries = self.state.outbox_deliveries.lock().await;
+        for envelope in &pending {
+            let delivery = deliveries.entry(*envelope.get_id()).or_default();
+            let waiting: Vec<_> = subscribers
+                .iter()
+                .filter(|subscriber| !delivery.handled.contains(&subscriber_key(subscriber)))
+                .cloned()
+                .collect();
+            let results = self
+                .deliver(envelope, &waiting, policy.delivery_timeout())
+                .await;
+            let mut failures = Vec::new();
+            let mut exhausted = false;
+            for (subscriber, result) in waiting.iter().zip(results) {
+                let key = subscriber_key(subscriber);
+                match result {
+                    Ok(()) => {
+                        delivery.handled.insert(key);
+                        delivery.attempts.remove(&key);
+                    }
+                    Err(e) => {
+                        let attempts = delivery.attempts.entry(key).or_default();
+                        *attempts += 1;
+                        exhausted |= *attempts >= policy.max_attempts;
+                        failures.push(format!("{}: {e:#}", subscriber.name()));
+                    }
+                }
+            }
+            if failures.is_empty() {
+                outbox.acknowledge(envelope.get_id()).await?;
+            } else if exhausted {
+                let reason = format!("subscribers failed: {}", failures.join(", "));
+                outbox.park(envelope.get_id(), &reason).await?;
+                self.report_failure(
+                    "outbox parked events",
+                    &format!("event {} parked, {reason}", envelope.get_id()),
+                )
+                .await;
+            } else {
+                bail!(
+                    "event {} left pending, subscribers failed: {}",
+                    envelope.get_id(),
+                    failures.join(", ")
+                );
+            }
+            deliveries.remove(envelope.get_id());
+        }
+        Ok(pending.len())
+    }
+
+    /// Start the task relaying the outbox, woken by each dispatch and at least every period.
+    ///
+    /// The task stops once the runtime is dropped.
+    pub async fn start_outbox_relay(&self, period: Duration) {
+        let mut relay = self.state.outbox_relay.lock().await;
+        if relay.is_some() {
+            return;
+        }
+        let wake_up = Arc::new(Notify::new());
+        let notified = wake_up.clone();
+        let state: Weak<RuntimeState> = Arc::downgrade(&self.state);
+        let task = tokio::spawn(async move {
+            loop {
+                let Some(state) = state.upgrade() else {
+                    return;
+                };
+                let runtime = Runtime { state };
+                let relayed = runtime.relay_outbox().await;
+                runtime.report("outbox relay", &relayed).await;
+                if matches!(relayed, Ok(relayed) if relayed == OUTBOX_RELAY_BATCH as usize) {
+                    continue;
+                }
+                tokio::select! {
+                    _ = notified.notified() => {}
+                    _ = tokio::time::sleep(period) => {}
+                }
+            }
+        });
+        *relay = Some(OutboxRelay { wake_up, task });
+    }
+
+    /// Call the subscriber after each reload changing the value at the dotted path
+    pub async fn subscribe_configuration(
+        &self,
+        path: &str,
+        subscriber: Arc<dyn ConfigurationSubscriberTrait>,
+    ) {
+        self.state
+            .configuration_subscribers
+            .write()
+            .await
+            .push((path.to_string(), subscriber));
+    }
+
+    /// Load the configuration again and swap it in, then notify the subscribers of the
+    /// changed paths. On failure the current configuration is kept.
+    ///
+    /// Return the differences from the previous configuration, empty if it did not change.
+    pub async fn reload_configuration(&self) -> Result<Vec<ConfigurationDifference>, RuntimeError> {
+        let loader = self
+            .state
+            .configuration_loader
+            .lock()
+            .await
+            .clone()
+            .ok_or(RuntimeError::NotInitialized)?;
+        let (configuration, origins) = loader.load_async().await?;
+        let configuration = Arc::new(configuration);
+        let old = {
+            let registry = self
+                .state
+                .registry
+                .get()
+                .ok_or(RuntimeError::NotInitialized)?;
+            let mut map = registry.write().await;
+            let key = (TypeId::of::<Configuration>(), None);
+            let old = match map.get(&key) {
+                Some(Registration::Instance(old)) => old.clone().downcast::<Configuration>().ok(),
+                _ => None,
+            };
+            if old.as_ref().is_some_and(|old| old.same_as(&configuration)) {
+                return Ok(Vec::new());
+            }
+            map.insert(key, Registration::Instance(configuration.clone()));
+            map.insert(
+                (TypeId::of::<ConfigurationOrigins>(), None),
+                Registration::Instance(Arc::new(origins)),
+            );
+            old
+        };
+        let old = old.unwrap_or_else(|| Arc::new(Configuration::Map(HashMap::new())));
+        let subscribers = self.state.configuration_subscribers.read().await.clone();
+        for (path, subscriber) in subscribers {
+            if let Some(change) = ConfigurationChange::between(&path, &old, &configuration) {
+                let result = self.scope(subscriber.handle(&change)).await;
+                self.report(
+                    &format!("configuration subscriber {}", subscriber.name()),
+                    &result,
+                )
+                .await;
+            }
+        }
+        Ok(old.diff(&configuration))
+    }
+
+    /// Start the task reloading the configuration when one of its files is created,
+    /// modified or removed, checked every period.
+    ///
+    /// A failed reload keeps the current configuration until the files change again.
+    /// The task stops once the runtime is dropped.
+    pub async fn watch_configuration(&self, period: Duration) -> Result<(), RuntimeError> {
+        let mut watcher = self.state.configuration_watcher.lock().await;
+        if watcher.is_some() {
+            return Ok(());
+        }
+        let Some(origins) = self.get::<ConfigurationOrigins>().await else {
+            return Err(RuntimeError::NotInitialized);
+        };
+        let mut stamps = FileStamps::of(&origins).await;
+        let state: Weak<RuntimeState> = Arc::downgrade(&self.state);
+        *watcher = Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(period).await;
+                let Some(state) = state.upgrade() else {
+                    return;
+                };
+                let runtime = Runtime { state };
+                let Some(origins) = runtime.get::<ConfigurationOrigins>().await else {
+                    continue;
+                };
+                let current = FileStamps::of(&origins).await;
+                if current == stamps {
+                    continue;
+                }
+                let reloaded = runtime.reload_configuration().await;
+                runtime.report("configuration reload", &reloaded).await;
+                // the files of the new load, a profile file may have appeared, the others
+                // compared to what was read
+                stamps = match runtime.get::<ConfigurationOrigins>().await {
+                    Some(origins) => FileStamps::of(&origins).await.keeping(&current),
+                    None => current,
+                };
+            }
+        }));
+        Ok(())
     }
 
     /// Check if runtime is initialized
     pub fn is_initialized(&self) -> bool {
-        REGISTRY.get().is_some()
+        self.state.registry.get().is_some()
+    }
+}
+
+/// Address of the subscriber, the same for each of its deliveries
+fn subscriber_key<E>(subscriber: &Arc<dyn EventSubscriberTrait<E>>) -> usize {
+    Arc::as_ptr(subscriber) as *const () as usize
+}
+
+fn lazy<T, F, Fut>(factory: F) -> Registration
+where
+    T: Send + Sync + 'static,
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    Registration::Lazy(service_factory(factory), Arc::new(OnceCell::new()))
+}
+
+fn service_factory<T, F, Fut>(factory: F) -> ServiceFactory
+where
+    T: Send + Sync + 'static,
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    Arc::new(move || {
+        let created = factory();
+        Box::pin(async move { Arc::new(created.await) as Service })
+    })
+}
+
+/// Order the pending adapters so each one comes after its dependencies, keeping the
+/// order they were added in otherwise
+fn startup_order(
+    pending: &[Arc<dyn AdapterLoaderTrait>],
+    started: &[Arc<dyn AdapterLoaderTrait>],
+) -> Result<Vec<Arc<dyn AdapterLoaderTrait>>, RuntimeError> {
+    for adapter in pending {
+        for dependency in adapter.dependencies() {
+            if !pending
+                .iter()
+                .chain(started.iter())
+                .any(|added| added.name() == *dependency)
+            {
+                return Err(RuntimeError::MissingDependency {
+                    adapter: adapter.name().to_string(),
+                    dependency: dependency.to_string(),
+                });
+            }
+        }
+    }
+    let mut ready: Vec<String> = started.iter().map(|a| a.name().to_string()).collect();
+    let mut remaining = pending.to_vec();
+    let mut order = Vec::new();
+    while !remaining.is_empty() {
+        let Some(index) = remaining.iter().position(|adapter| {
+            adapter
+                .dependencies()
+                .iter()
+                .all(|dependency| ready.iter().any(|name| name == dependency))
+        }) else {
+            return Err(RuntimeError::DependencyCycle {
+                adapters: remaining.iter().map(|a| a.name().to_string()).collect(),
+            });
+        };
+        let adapter = remaining.remove(index);
+        ready.push(adapter.name().to_string());
+        order.push(adapter);
     }
+    Ok(order)
 }
 
 // Usage:
@@ -0,0 +1,40 @@
+use thiserror::Error;
+
+use crate::configuration::error::ConfigurationError;
+
+#[derive(Debug, Error)]
+pub enum RuntimeError {
+    #[error("Runtime not initialized")]
+    NotInitialized,
+    #[error("Runtime already initialized")]
+    AlreadyInitialized,
+    #[error("Adapter {name} already added")]
+    DuplicateAdapter { name: String },
+    #[error("Adapter {adapter} depends on {dependency} which is not added")]
+    MissingDependency { adapter: String, dependency: String },
+    #[error("Adapters {} depend on each other", adapters.join(", "))]
+    DependencyCycle { adapters: Vec<String> },
+    #[error("Adapter {adapter} failed to start: {source}")]
+    StartFailed {
+        adapter: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("Adapters failed to shut down: {}", failures.iter().map(|(adapter, e)| format!("{adapter}: {e}")).collect::<Vec<_>>().join("; "))]
+    ShutdownFailed { failures: Vec<(String, String)> },
+    #[error("Service {service}{} already registered", name.as_ref().map(|name| format!(" named {name}")).unwrap_or_default())]
+    AlreadyRegistered {
+        service: String,
+        name: Option<String>,
+    },
+    #[error("Configuration cannot be loaded: {0}")]
+    Configuration(#[from] ConfigurationError),
+    #[error("Plugin {path} cannot be loaded: {reason}")]
+    PluginLoadFailed { path: String, reason: String },
+    #[error("Plugin {path} is built for the ABI version {found}, expected {expected}")]
+    IncompatiblePlugin {
+        path: String,
+        expected: u32,
+        found: u32,
+    },
+}
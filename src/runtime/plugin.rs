@@ -0,0 +1,428 @@
+pub mod export;
+
+use std::{
+    ffi::{CStr, CString, c_char, c_void},
+    fmt::{self, Debug, Formatter},
+    path::Path,
+    pin::Pin,
+    ptr,
+    sync::{Arc, Weak},
+};
+
+use anyhow::anyhow;
+use libloading::{Library, Symbol};
+use zeroize::Zeroize;
+
+use crate::{
+    configuration::{Configuration, schema::ConfigurationSchema},
+    model::adapter_health::AdapterHealth,
+    runtime::{Runtime, RuntimeState, error::RuntimeError},
+    traits::{adapter_loader_trait::AdapterLoaderTrait, initialize_trait::InitializeTrait},
+};
+
+/// Version of the plugin ABI, the structures below, raised on each breaking change
+pub const PLUGIN_ABI_VERSION: u32 = 3;
+
+/// Symbol returning the `PLUGIN_ABI_VERSION` the plugin implements
+pub const PLUGIN_ABI_VERSION_SYMBOL: &[u8] = b"fototra_plugin_abi_version";
+
+/// Symbol filling the `FototraPluginAdapter` of the plugin
+pub const PLUGIN_ENTRY_POINT_SYMBOL: &[u8] = b"fototra_plugin_create";
+
+pub type PluginAbiVersion = unsafe extern "C" fn() -> u32;
+pub type PluginEntryPoint =
+    unsafe extern "C" fn(adapter: *mut FototraPluginAdapter) -> FototraPluginStatus;
+
+/// Outcome of a plugin callback
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FototraPluginStatus {
+    Ok = 0,
+    Failed = 1,
+}
+
+/// Health reported by a plugin adapter
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FototraPluginHealth {
+    Healthy = 0,
+    Degraded = 1,
+    Unhealthy = 2,
+}
+
+/// Services of the host given to the `load` callback of a plugin, valid during the call.
+///
+/// Strings returned by the host are released with its `string_free`.
+#[repr(C)]
+pub struct FototraPluginHost {
+    pub context: *const c_void,
+    /// Value at the dotted path of the configuration as JSON with the secrets redacted,
+    /// the whole configuration for an empty path, null if it is not set
+    pub configuration:
+        unsafe extern "C" fn(context: *const c_void, path: *const c_char) -> *mut c_char,
+    /// Text of the secret at the dotted path of the configuration, null if it is not set
+    pub secret: unsafe extern "C" fn(context: *const c_void, path: *const c_char) -> *mut c_char,
+    /// Register the string in the runtime under the name
+    pub register_string: unsafe extern "C" fn(
+        context: *const c_void,
+        name: *const c_char,
+        value: *const c_char,
+    ) -> FototraPluginStatus,
+    pub string_free: unsafe extern "C" fn(string: *mut c_char),
+}
+
+/// Adapter of a plugin: callbacks over its opaque handle.
+///
+/// The callbacks may be called from any thread, even at the same time, and block until
+/// done. The strings returned by `name`, `dependencies` and `configuration_schema` live
+/// as long as the handle; the host releases the messages written to `error` or `reason`
+/// with `string_free` of the adapter. `destroy` releases the handle once the runtime
+/// drops the adapter.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct FototraPluginAdapter {
+    pub handle: *mut c_void,
+    pub name: unsafe extern "C" fn(handle: *mut c_void) -> *const c_char,
+    /// Names of the adapters to start before this one, their number written to `count`
+    pub dependencies:
+        unsafe extern "C" fn(handle: *mut c_void, count: *mut usize) -> *const *const c_char,
+    /// Configuration expected by the adapter as a TOML schema, null without one
+    pub configuration_schema: unsafe extern "C" fn(handle: *mut c_void) -> *const c_char,
+    pub load: unsafe extern "C" fn(
+        handle: *mut c_void,
+        host: *const FototraPluginHost,
+        error: *mut *mut c_char,
+    ) -> FototraPluginStatus,
+    pub health:
+        unsafe extern "C" fn(handle: *mut c_void, reason: *mut *mut c_char) -> FototraPluginHealth,
+    pub shutdown:
+        unsafe extern "C" fn(handle: *mut c_void, error: *mut *mut c_char) -> FototraPluginStatus,
+    pub string_free: unsafe extern "C" fn(string: *mut c_char),
+    pub destroy: unsafe extern "C" fn(handle: *mut c_void),
+}
+
+/// Callbacks of a plugin, which accepts them from any thread
+#[derive(Clone, Copy)]
+struct Callbacks(FototraPluginAdapter);
+
+unsafe impl Send for Callbacks {}
+unsafe impl Sync for Callbacks {}
+
+impl Callbacks {
+    /// Take the message written by the plugin, releasing its string
+    unsafe fn message(&self, message: *mut c_char) -> Option<String> {
+        if message.is_null() {
+            return None;
+        }
+        let text = unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { (self.0.string_free)(message) };
+        Some(text)
+    }
+
+    /// Run the callback writing an error on the blocking pool, the plugin blocking
+    async fn call(
+        self,
+        callback: unsafe extern "C" fn(*mut c_void, *mut *mut c_char) -> FototraPluginStatus,
+    ) -> anyhow::Result<()> {
+        tokio::task::spawn_blocking(move || {
+            let mut error = ptr::null_mut();
+            let status = unsafe { callback(self.0.handle, &mut error) };
+            let error = unsafe { self.message(error) };
+            match status {
+                FototraPluginStatus::Ok => Ok(()),
+                FototraPluginStatus::Failed => Err(anyhow!(
+                    error.unwrap_or_else(|| "the plugin failed".to_string())
+                )),
+            }
+        })
+        .await?
+    }
+}
+
+/// Adapter of the host calling the callbacks of a plugin
+pub(crate) struct PluginAdapter {
+    name: String,
+    // the adapter lends its dependencies for its whole life, plugins are never unloaded
+    dependencies: Vec<&'static str>,
+    schema: Option<ConfigurationSchema>,
+    callbacks: Callbacks,
+    runtime: Weak<RuntimeState>,
+}
+
+impl PluginAdapter {
+    /// Read what the plugin declares once, then keep its handle
+    unsafe fn new(runtime: &Runtime, adapter: FototraPluginAdapter) -> Result<Self, String> {
+        let text = |string: *const c_char| {
+            (!string.is_null()).then(|| {
+                unsafe { CStr::from_ptr(string) }
+                    .to_string_lossy()
+                    .into_owned()
+            })
+        };
+        let name = text(unsafe { (adapter.name)(adapter.handle) })
+            .ok_or("the plugin gives no name".to_string())?;
+        let mut count = 0;
+        let dependencies = unsafe { (adapter.dependencies)(adapter.handle, &mut count) };
+        let dependencies = match dependencies.is_null() {
+            true => Vec::new(),
+            false => unsafe { std::slice::from_raw_parts(dependencies, count) }
+                .iter()
+                .map(|dependency| {
+                    text(*dependency)
+                        .map(|dependency| &*Box::leak(dependency.into_boxed_str()))
+                        .ok_or("the plugin gives a null dependency".to_string())
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        let schema = text(unsafe { (adapter.configuration_schema)(adapter.handle) })
+            .map(|schema| ConfigurationSchema::from_toml(&schema))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            name,
+            dependencies,
+            schema,
+            callbacks: Callbacks(adapter),
+            runtime: Arc::downgrade(&runtime.state),
+        })
+    }
+}
+
+impl Debug for PluginAdapter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PluginAdapter")
+            .field("name", &self.name)
+            .field("dependencies", &self.dependencies)
+            .finish()
+    }
+}
+
+impl Drop for PluginAdapter {
+    fn drop(&mut self) {
+        unsafe { (self.callbacks.0.destroy)(self.callbacks.0.handle) };
+    }
+}
+
+impl InitializeTrait for PluginAdapter {}
+
+impl AdapterLoaderTrait for PluginAdapter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn dependencies(&self) -> &[&str] {
+        &self.dependencies
+    }
+
+    fn configuration_schema(&self) -> Option<ConfigurationSchema> {
+        self.schema.clone()
+    }
+
+    fn load<'a>(&'a self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            let state = self
+                .runtime
+                .upgrade()
+                .ok_or(anyhow!("the runtime of the plugin is dropped"))?;
+            let host = HostContext {
+                runtime: Runtime { state },
+                handle: tokio::runtime::Handle::current(),
+            };
+            let callbacks = self.callbacks;
+            tokio::task::spawn_blocking(move || {
+                let services = FototraPluginHost {
+                    context: &host as *const HostContext as *const c_void,
+                    configuration: host_configuration,
+                    secret: host_secret,
+                    register_string: host_register_string,
+                    string_free: host_string_free,
+                };
+                let mut error = ptr::null_mut();
+                let status =
+                    unsafe { (callbacks.0.load)(callbacks.0.handle, &services, &mut error) };
+                let error = unsafe { callbacks.message(error) };
+                match status {
+                    FototraPluginStatus::Ok => Ok(()),
+                    FototraPluginStatus::Failed => Err(anyhow!(
+                        error.unwrap_or_else(|| "the plugin failed to load".to_string())
+                    )),
+                }
+            })
+            .await?
+        })
+    }
+
+    fn health<'a>(&'a self) -> Pin<Box<dyn Future<Output = AdapterHealth> + Send + 'a>> {
+        Box::pin(async {
+            let callbacks = self.callbacks;
+            tokio::task::spawn_blocking(move || {
+                let mut reason = ptr::null_mut();
+                let health = unsafe { (callbacks.0.health)(callbacks.0.handle, &mut reason) };
+                let reason = unsafe { callbacks.message(reason) }.unwrap_or_default();
+                match health {
+                    FototraPluginHealth::Healthy => AdapterHealth::Healthy,
+                    FototraPluginHealth::Degraded => AdapterHealth::Degraded { reason },
+                    FototraPluginHealth::Unhealthy => AdapterHealth::Unhealthy { reason },
+                }
+            })
+            .await
+            .unwrap_or_else(|e| AdapterHealth::Unhealthy {
+                reason: e.to_string(),
+            })
+        })
+    }
+
+    fn shutdown<'a>(&'a self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(self.callbacks.call(self.callbacks.0.shutdown))
+    }
+}
+
+/// What the callbacks of the host reach through the context of `FototraPluginHost`
+struct HostContext {
+    runtime: Runtime,
+    handle: tokio::runtime::Handle,
+}
+
+impl HostContext {
+    /// Host and text behind the pointers given to a callback, none if invalid
+    unsafe fn read<'a>(context: *const c_void, text: *const c_char) -> Option<(&'a Self, &'a str)> {
+        if context.is_null() || text.is_null() {
+            return None;
+        }
+        let host = unsafe { &*(context as *const HostContext) };
+        let text = unsafe { CStr::from_ptr(text) }.to_str().ok()?;
+        Some((host, text))
+    }
+
+    fn configuration(&self) -> Option<Arc<Configuration>> {
+        self.handle.block_on(self.runtime.get::<Configuration>())
+    }
+}
+
+fn into_raw(text: String) -> *mut c_char {
+    CString::new(text).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+unsafe extern "C" fn host_configuration(
+    context: *const c_void,
+    path: *const c_char,
+) -> *mut c_char {
+    let Some((host, path)) = (unsafe { HostContext::read(context, path) }) else {
+        return ptr::null_mut();
+    };
+    let Some(configuration) = host.configuration() else {
+        return ptr::null_mut();
+    };
+    let value = match path {
+        "" => Some(&*configuration),
+        path => configuration.get_path(path),
+    };
+    value
+        .and_then(|value| serde_json::to_string(value).ok())
+        .map_or(ptr::null_mut(), into_raw)
+}
+
+unsafe extern "C" fn host_secret(context: *const c_void, path: *const c_char) -> *mut c_char {
+    let Some((host, path)) = (unsafe { HostContext::read(context, path) }) else {
+        return ptr::null_mut();
+    };
+    host.configuration()
+        .and_then(|configuration| configuration.get_secret(path).ok().cloned())
+        .map_or(ptr::null_mut(), |secret| {
+            into_raw(secret.expose().to_string())
+        })
+}
+
+unsafe extern "C" fn host_register_string(
+    context: *const c_void,
+    name: *const c_char,
+    value: *const c_char,
+) -> FototraPluginStatus {
+    let (Some((host, name)), false) =
+        (unsafe { HostContext::read(context, name) }, value.is_null())
+    else {
+        return FototraPluginStatus::Failed;
+    };
+    let value = unsafe { CStr::from_ptr(value) }
+        .to_string_lossy()
+        .into_owned();
+    match host
+        .handle
+        .block_on(host.runtime.register_named(name, value))
+    {
+        Ok(()) => FototraPluginStatus::Ok,
+        Err(_) => FototraPluginStatus::Failed,
+    }
+}
+
+/// Release a string of the host, wiping it as it may hold a secret
+unsafe extern "C" fn host_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        unsafe { CString::from_raw(string) }.into_bytes().zeroize();
+    }
+}
+
+/// Load the library and create its adapter after checking it implements the
+/// `PLUGIN_ABI_VERSION` of the host.
+///
+/// The library is never unloaded: the callbacks of its adapter may outlive the runtime.
+pub(crate) fn load_plugin(
+    runtime: &Runtime,
+    path: &Path,
+) -> Result<Arc<dyn AdapterLoaderTrait>, RuntimeError> {
+    let failed = |reason: String| RuntimeError::PluginLoadFailed {
+        path: path.display().to_string(),
+        reason,
+    };
+    let library = unsafe { Library::new(path) }.map_err(|e| failed(e.to_string()))?;
+    let adapter = unsafe {
+        let abi_version: Symbol<PluginAbiVersion> = library
+            .get(PLUGIN_ABI_VERSION_SYMBOL)
+            .map_err(|e| failed(e.to_string()))?;
+        let found = abi_version();
+        if found != PLUGIN_ABI_VERSION {
+            return Err(RuntimeError::IncompatiblePlugin {
+                path: path.display().to_string(),
+                expected: PLUGIN_ABI_VERSION,
+                found,
+            });
+        }
+        let create: Symbol<PluginEntryPoint> = library
+            .get(PLUGIN_ENTRY_POINT_SYMBOL)
+            .map_err(|e| failed(e.to_string()))?;
+        let mut adapter = std::mem::MaybeUninit::<FototraPluginAdapter>::uninit();
+        if create(adapter.as_mut_ptr()) != FototraPluginStatus::Ok {
+            return Err(failed("the entry point created no adapter".to_string()));
+        }
+        let adapter = adapter.assume_init();
+        PluginAdapter::new(runtime, adapter).map_err(|reason| {
+            (adapter.destroy)(adapter.handle);
+            failed(reason)
+        })?
+    };
+    std::mem::forget(library);
+    Ok(Arc::new(adapter))
+}
+
+/// Paths listed under `[plugins] paths` in the configuration
+pub(crate) fn plugin_paths(configuration: &Configuration) -> Result<Vec<String>, RuntimeError> {
+    let Some(paths) = configuration.get_path("plugins.paths") else {
+        return Ok(Vec::new());
+    };
+    let invalid = || RuntimeError::PluginLoadFailed {
+        path: "plugins.paths".to_string(),
+        reason: "expected an array of paths".to_string(),
+    };
+    match paths {
+        Configuration::Array(paths) => paths
+            .iter()
+            .map(|path| match path {
+                Configuration::String(path) => Ok(path.clone()),
+                _ => Err(invalid()),
+            })
+            .collect(),
+        _ => Err(invalid()),
+    }
+}
@@ -0,0 +1,248 @@
+//! Plugin side of the ABI: the callbacks `declare_adapter_plugin!` exports for a
+//! `PluginAdapterTrait`.
+
+use std::{
+    ffi::{CStr, CString, c_char, c_void},
+    panic::{AssertUnwindSafe, catch_unwind},
+    ptr,
+};
+
+use anyhow::{anyhow, bail};
+use zeroize::Zeroizing;
+
+use crate::{
+    model::adapter_health::AdapterHealth,
+    runtime::plugin::{
+        FototraPluginAdapter, FototraPluginHealth, FototraPluginHost, FototraPluginStatus,
+    },
+    traits::plugin_adapter_trait::PluginAdapterTrait,
+};
+
+/// Export the symbols of an adapter plugin, the constructor taking no argument.
+///
+/// Nothing but the C structures of `runtime::plugin` crosses the boundary, so the plugin
+/// only needs the same `PLUGIN_ABI_VERSION` as the host.
+#[macro_export]
+macro_rules! declare_adapter_plugin {
+    ($constructor:path) => {
+        #[unsafe(no_mangle)]
+        pub extern "C" fn fototra_plugin_abi_version() -> u32 {
+            $crate::runtime::plugin::PLUGIN_ABI_VERSION
+        }
+
+        /// # Safety
+        ///
+        /// `adapter` must be writable
+        #[unsafe(no_mangle)]
+        pub unsafe extern "C" fn fototra_plugin_create(
+            adapter: *mut $crate::runtime::plugin::FototraPluginAdapter,
+        ) -> $crate::runtime::plugin::FototraPluginStatus {
+            unsafe { $crate::runtime::plugin::export::export_adapter(adapter, $constructor) }
+        }
+    };
+}
+
+/// Services of the host, reachable while the adapter loads
+pub struct PluginHost<'a> {
+    host: &'a FototraPluginHost,
+}
+
+impl PluginHost<'_> {
+    /// Take a string returned by the host, releasing it
+    fn take(&self, string: *mut c_char) -> Option<Zeroizing<String>> {
+        if string.is_null() {
+            return None;
+        }
+        let text = unsafe { CStr::from_ptr(string) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { (self.host.string_free)(string) };
+        Some(Zeroizing::new(text))
+    }
+
+    /// Value at the dotted path of the configuration, the secrets redacted
+    pub fn configuration(&self, path: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        let path = CString::new(path)?;
+        let value = unsafe { (self.host.configuration)(self.host.context, path.as_ptr()) };
+        self.take(value)
+            .map(|json| serde_json::from_str(&json))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// Text of the secret at the dotted path of the configuration
+    pub fn secret(&self, path: &str) -> anyhow::Result<Option<Zeroizing<String>>> {
+        let path = CString::new(path)?;
+        Ok(self.take(unsafe { (self.host.secret)(self.host.context, path.as_ptr()) }))
+    }
+
+    /// Register the string in the runtime of the host under the name
+    pub fn register_string(&self, name: &str, value: &str) -> anyhow::Result<()> {
+        let (name, value) = (CString::new(name)?, CString::new(value)?);
+        match unsafe {
+            (self.host.register_string)(self.host.context, name.as_ptr(), value.as_ptr())
+        } {
+            FototraPluginStatus::Ok => Ok(()),
+            FototraPluginStatus::Failed => bail!("the host refused to register {name:?}"),
+        }
+    }
+}
+
+/// Adapter behind the handle, with the strings it lends to the host
+struct Exported<T> {
+    adapter: T,
+    name: CString,
+    // owns the strings behind the pointers
+    _dependencies: Vec<CString>,
+    dependency_pointers: Vec<*const c_char>,
+    schema: Option<CString>,
+}
+
+/// Fill the callbacks of the adapter created by the constructor, failing if it panics
+/// or declares what the host cannot read
+///
+/// # Safety
+///
+/// `adapter` must be writable
+pub unsafe fn export_adapter<T: PluginAdapterTrait + 'static>(
+    adapter: *mut FototraPluginAdapter,
+    constructor: impl FnOnce() -> T,
+) -> FototraPluginStatus {
+    let exported = catch_unwind(AssertUnwindSafe(|| -> anyhow::Result<Exported<T>> {
+        let exported = constructor();
+        let dependencies = exported
+            .dependencies()
+            .iter()
+            .map(|dependency| CString::new(*dependency))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Exported {
+            name: CString::new(exported.name())?,
+            dependency_pointers: dependencies.iter().map(|d| d.as_ptr()).collect(),
+            _dependencies: dependencies,
+            schema: exported
+                .configuration_schema()
+                .map(|schema| anyhow::Ok(CString::new(schema.to_toml()?)?))
+                .transpose()?,
+            adapter: exported,
+        })
+    }));
+    let Ok(Ok(exported)) = exported else {
+        return FototraPluginStatus::Failed;
+    };
+    unsafe {
+        adapter.write(FototraPluginAdapter {
+            handle: Box::into_raw(Box::new(exported)) as *mut c_void,
+            name: name::<T>,
+            dependencies: dependencies::<T>,
+            configuration_schema: configuration_schema::<T>,
+            load: load::<T>,
+            health: health::<T>,
+            shutdown: shutdown::<T>,
+            string_free,
+            destroy: destroy::<T>,
+        })
+    };
+    FototraPluginStatus::Ok
+}
+
+unsafe fn exported<'a, T>(handle: *mut c_void) -> &'a Exported<T> {
+    unsafe { &*(handle as *const Exported<T>) }
+}
+
+/// String handed to the host, which releases it with `string_free`
+fn into_raw(text: String) -> *mut c_char {
+    CString::new(text.replace('\0', "")).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// Run the callback of the adapter, reporting its error or panic to the host
+unsafe fn run(
+    error: *mut *mut c_char,
+    callback: impl FnOnce() -> anyhow::Result<()>,
+) -> FototraPluginStatus {
+    let result = catch_unwind(AssertUnwindSafe(callback))
+        .unwrap_or_else(|_| Err(anyhow!("the plugin panicked")));
+    match result {
+        Ok(()) => FototraPluginStatus::Ok,
+        Err(e) => {
+            if !error.is_null() {
+                unsafe { error.write(into_raw(format!("{e:#}"))) };
+            }
+            FototraPluginStatus::Failed
+        }
+    }
+}
+
+unsafe extern "C" fn name<T>(handle: *mut c_void) -> *const c_char {
+    unsafe { exported::<T>(handle) }.name.as_ptr()
+}
+
+unsafe extern "C" fn dependencies<T>(
+    handle: *mut c_void,
+    count: *mut usize,
+) -> *const *const c_char {
+    let exported = unsafe { exported::<T>(handle) };
+    if !count.is_null() {
+        unsafe { count.write(exported.dependency_pointers.len()) };
+    }
+    exported.dependency_pointers.as_ptr()
+}
+
+unsafe extern "C" fn configuration_schema<T>(handle: *mut c_void) -> *const c_char {
+    unsafe { exported::<T>(handle) }
+        .schema
+        .as_ref()
+        .map_or(ptr::null(), |schema| schema.as_ptr())
+}
+
+unsafe extern "C" fn load<T: PluginAdapterTrait>(
+    handle: *mut c_void,
+    host: *const FototraPluginHost,
+    error: *mut *mut c_char,
+) -> FototraPluginStatus {
+    unsafe {
+        run(error, || {
+            let host = host.as_ref().ok_or(anyhow!("no host given"))?;
+            exported::<T>(handle).adapter.load(&PluginHost { host })
+        })
+    }
+}
+
+unsafe extern "C" fn health<T: PluginAdapterTrait>(
+    handle: *mut c_void,
+    reason: *mut *mut c_char,
+) -> FototraPluginHealth {
+    let health = catch_unwind(AssertUnwindSafe(|| {
+        unsafe { exported::<T>(handle) }.adapter.health()
+    }))
+    .unwrap_or_else(|_| AdapterHealth::Unhealthy {
+        reason: "the plugin panicked".to_string(),
+    });
+    let (health, text) = match health {
+        AdapterHealth::Healthy => return FototraPluginHealth::Healthy,
+        AdapterHealth::Degraded { reason } => (FototraPluginHealth::Degraded, reason),
+        AdapterHealth::Unhealthy { reason } => (FototraPluginHealth::Unhealthy, reason),
+    };
+    if !reason.is_null() {
+        unsafe { reason.write(into_raw(text)) };
+    }
+    health
+}
+
+unsafe extern "C" fn shutdown<T: PluginAdapterTrait>(
+    handle: *mut c_void,
+    error: *mut *mut c_char,
+) -> FototraPluginStatus {
+    unsafe { run(error, || exported::<T>(handle).adapter.shutdown()) }
+}
+
+unsafe extern "C" fn string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+unsafe extern "C" fn destroy<T>(handle: *mut c_void) {
+    if !handle.is_null() {
+        drop(unsafe { Box::from_raw(handle as *mut Exported<T>) });
+    }
+}
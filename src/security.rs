@@ -1 +1,4 @@
 pub mod error;
+pub mod guard;
+pub mod permission_resolver;
+pub mod user_authorizer;
@@ -1,9 +1,124 @@
+use serde_json::{Value, json};
 use thiserror::Error;
+use uuid::Uuid;
 
-#[derive(Debug, Error)]
+use crate::{
+    model::{permission::Permission, permission_check::DenialReason},
+    service::error::ErrorCategory,
+    traits::service_error_trait::ServiceErrorTrait,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum SecurityError {
     #[error("Authentication required")]
     NotAuthenticated,
-    #[error("Operation forbiden")]
-    NotAuthorized,
+    #[error(
+        "Operation forbiden: {reason} (permission: {}, principal: {})",
+        .permission.as_deref().unwrap_or("unknown"),
+        .principal_id.map_or("unknown".to_string(), |id| id.to_string())
+    )]
+    NotAuthorized {
+        permission: Option<Permission>,
+        principal_id: Option<Uuid>,
+        reason: DenialReason,
+    },
+}
+
+impl SecurityError {
+    pub fn not_authorized(reason: DenialReason) -> Self {
+        Self::NotAuthorized {
+            permission: None,
+            principal_id: None,
+            reason,
+        }
+    }
+
+    /// Set the requested permission if the authorizer did not
+    pub fn with_permission(self, requested: &str) -> Self {
+        match self {
+            Self::NotAuthorized {
+                permission,
+                principal_id,
+                reason,
+            } => Self::NotAuthorized {
+                permission: permission.or(Some(requested.to_string())),
+                principal_id,
+                reason,
+            },
+            e => e,
+        }
+    }
+
+    /// Set the denied principal if the authorizer did not
+    pub fn with_principal_id(self, principal: Option<&Uuid>) -> Self {
+        match self {
+            Self::NotAuthorized {
+                permission,
+                principal_id,
+                reason,
+            } => Self::NotAuthorized {
+                permission,
+                principal_id: principal_id.or(principal.copied()),
+                reason,
+            },
+            e => e,
+        }
+    }
+
+    pub fn get_permission(&self) -> Option<&str> {
+        match self {
+            Self::NotAuthorized { permission, .. } => permission.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_principal_id(&self) -> Option<&Uuid> {
+        match self {
+            Self::NotAuthorized { principal_id, .. } => principal_id.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_reason(&self) -> Option<&DenialReason> {
+        match self {
+            Self::NotAuthorized { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+}
+
+impl ServiceErrorTrait for SecurityError {
+    fn category(&self) -> ErrorCategory {
+        ErrorCategory::Auth
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            Self::NotAuthenticated => "security.not_authenticated",
+            Self::NotAuthorized { reason, .. } => match reason {
+                DenialReason::UnknownPermission => "security.not_authorized.unknown_permission",
+                DenialReason::NoGrant => "security.not_authorized.no_grant",
+                DenialReason::ExplicitDeny => "security.not_authorized.explicit_deny",
+                DenialReason::Expired => "security.not_authorized.expired",
+                DenialReason::NotYetValid => "security.not_authorized.not_yet_valid",
+                DenialReason::Locked => "security.not_authorized.locked",
+                DenialReason::Unavailable => "security.not_authorized.unavailable",
+            },
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            Self::NotAuthenticated => None,
+            Self::NotAuthorized {
+                permission,
+                principal_id,
+                reason,
+            } => Some(json!({
+                "permission": permission,
+                "principal_id": principal_id,
+                "reason": reason,
+            })),
+        }
+    }
 }
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use crate::{
+    model::{permission_check::DenialReason, user::error::UserError},
+    repository::user_repository::UserRepository,
+    runtime::Runtime,
+    security::error::SecurityError,
+    traits::{authentication_trait::AuthenticationTrait, authorization_trait::AuthorizationTrait},
+};
+
+/// Authenticate then authorize the permission, completing the denial with the
+/// requested permission and the principal.
+///
+/// A principal that is not a known user is not authenticated, and one that is a user
+/// which is not active is refused as locked. Without a user repository to check it, or
+/// when it cannot be looked up, the principal is refused as unavailable.
+pub async fn authorize(
+    authenticatable: &dyn AuthenticationTrait,
+    permission: &str,
+) -> Result<Arc<dyn AuthorizationTrait>, SecurityError> {
+    let authorizable = authenticatable.authenticate().await?;
+    if let Some(principal_id) = authorizable.get_principal_id() {
+        let denied = |reason| {
+            SecurityError::not_authorized(reason)
+                .with_permission(permission)
+                .with_principal_id(Some(principal_id))
+        };
+        let user_repository = Runtime::get_instance()
+            .get::<UserRepository>()
+            .await
+            .ok_or_else(|| denied(DenialReason::Unavailable))?;
+        match user_repository.find_by_id(principal_id).await {
+            Ok(user) if user.is_active() => {}
+            Ok(_) => return Err(denied(DenialReason::Locked)),
+            Err(UserError::UserNotExists { .. }) => return Err(SecurityError::NotAuthenticated),
+            Err(_) => return Err(denied(DenialReason::Unavailable)),
+        }
+    }
+    authorizable.authorize(permission).await.map_err(|e| {
+        e.with_permission(permission)
+            .with_principal_id(authorizable.get_principal_id())
+    })?;
+    Ok(authorizable)
+}
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+
+use crate::{
+    dtos::{
+        find_request::FindRequest,
+        user_permission::user_permission_find_request_filter::UserPermissionFindRequestFilter,
+    },
+    model::{
+        effective_permission::{EffectivePermission, PermissionSource},
+        permission::{Permission, is_wildcard, permission_matches},
+        permission_check::{DenialReason, PermissionCheck, PermissionDecision},
+        user::UserID,
+        user_permission::{UserPermission, error::UserPermissionError},
+    },
+    repository::{
+        permission_repository::PermissionRepository,
+        user_permission_repository::UserPermissionRepository,
+    },
+    runtime::Runtime,
+    traits::find_result_trait::FindResultTrait,
+};
+
+const PAGE_SIZE: u16 = 1000;
+
+/// Resolve the grants of one user against the known permissions at a given instant
+#[derive(Debug, Clone)]
+pub struct PermissionResolver {
+    grants: Vec<UserPermission>,
+    known_permissions: Vec<Permission>,
+    instant: DateTime<Utc>,
+}
+
+impl PermissionResolver {
+    pub fn new(grants: &[UserPermission], known_permissions: &[Permission]) -> Self {
+        Self {
+            grants: grants.to_vec(),
+            known_permissions: known_permissions.to_vec(),
+            instant: Utc::now(),
+        }
+    }
+
+    /// Evaluate the time-bound grants at another instant than now
+    pub fn at(mut self, instant: &DateTime<Utc>) -> Self {
+        self.instant = *instant;
+        self
+    }
+
+    /// Load the grants of the user and the known permissions from the registered repositories
+    pub async fn load(user_id: &UserID) -> Result<Self, UserPermissionError> {
+        let user_permission_repository = Runtime::get_instance()
+            .get::<UserPermissionRepository>()
+            .await
+            .ok_or(UserPermissionError::Unknown(anyhow!(
+                "Cannot get user_permission repository"
+            )))?;
+
+        let filter = UserPermissionFindRequestFilter {
+            user_id: Some(*user_id),
+            include_expired: true,
+            ..Default::default()
+        };
+        let mut grants = Vec::new();
+        let mut offset = 1;
+        loop {
+            let request = FindRequest::new(&filter, "permission", &PAGE_SIZE, &offset)
+                .map_err(|e| UserPermissionError::Unknown(anyhow!(e)))?;
+            let page = user_permission_repository.find_all(&request).await?;
+            grants.extend(page.get_result());
+            if offset >= page.get_page_count() {
+                break;
+            }
+            offset += 1;
+        }
+
+        Ok(Self::new(&grants, &Self::load_known_permissions().await?))
+    }
+
+    /// Load every permission of the registered permission repository
+    pub async fn load_known_permissions() -> Result<Vec<Permission>, UserPermissionError> {
+        let permission_repository = Runtime::get_instance()
+            .get::<PermissionRepository>()
+            .await
+            .ok_or(UserPermissionError::Unknown(anyhow!(
+                "Cannot get permission repository"
+            )))?;
+        let mut known_permissions = Vec::new();
+        let mut offset = 1;
+        loop {
+            let request = FindRequest::new(&String::new(), "", &PAGE_SIZE, &offset)
+                .map_err(|e| UserPermissionError::Unknown(anyhow!(e)))?;
+            let page = permission_repository
+                .find_all(&request)
+                .await
+                .map_err(|e| UserPermissionError::Unknown(anyhow!(e.to_string())))?;
+            known_permissions.extend(page.get_result());
+            if offset >= page.get_page_count() {
+                break;
+            }
+            offset += 1;
+        }
+
+        Ok(known_permissions)
+    }
+
+    /// Every known permission held by the user, each with the grants it comes from
+    pub fn effective_permissions(&self) -> Vec<EffectivePermission> {
+        let mut effective: BTreeMap<&str, Vec<PermissionSource>> = BTreeMap::new();
+        for permission in &self.known_permissions {
+            let sources = self.sources_of(permission);
+            if !sources.is_empty() {
+                effective.insert(permission, sources);
+            }
+        }
+        effective
+            .into_iter()
+            .map(|(permission, sources)| EffectivePermission::new(permission, &sources))
+            .collect()
+    }
+
+    /// Decide if the user holds the permission and explain why
+    pub fn check(&self, user_id: &UserID, permission: &str) -> PermissionCheck {
+        let decision = if !self.known_permissions.iter().any(|p| p == permission) {
+            PermissionDecision::Denied {
+                reason: DenialReason::UnknownPermission,
+            }
+        } else if self.is_denied(permission) {
+            PermissionDecision::Denied {
+                reason: DenialReason::ExplicitDeny,
+            }
+        } else {
+            let sources = self.sources_of(permission);
+            if !sources.is_empty() {
+                PermissionDecision::Allowed { sources }
+            } else {
+                let covering: Vec<&UserPermission> = self
+                    .grants
+                    .iter()
+                    .filter(|grant| {
+                        !grant.is_deny() && permission_matches(grant.get_permission(), permission)
+                    })
+                    .collect();
+                let reason = if covering
+                    .iter()
+                    .any(|grant| grant.is_expired_at(&self.instant))
+                {
+                    DenialReason::Expired
+                } else if covering
+                    .iter()
+                    .any(|grant| grant.is_pending_at(&self.instant))
+                {
+                    DenialReason::NotYetValid
+                } else {
+                    DenialReason::NoGrant
+                };
+                PermissionDecision::Denied { reason }
+            }
+        };
+        PermissionCheck::new(user_id, permission, &decision)
+    }
+
+    /// Whether an effective deny grant covers the permission, which no other grant overrides
+    fn is_denied(&self, permission: &str) -> bool {
+        self.grants.iter().any(|grant| {
+            grant.is_deny()
+                && grant.is_active_at(&self.instant)
+                && permission_matches(grant.get_permission(), permission)
+        })
+    }
+
+    fn sources_of(&self, permission: &str) -> Vec<PermissionSource> {
+        if self.is_denied(permission) {
+            return Vec::new();
+        }
+        let mut sources: Vec<PermissionSource> = self
+            .grants
+            .iter()
+            .filter(|grant| {
+                !grant.is_deny()
+                    && grant.is_active_at(&self.instant)
+                    && permission_matches(grant.get_permission(), permission)
+            })
+            .map(|grant| {
+                if is_wildcard(grant.get_permission()) {
+                    PermissionSource::Wildcard {
+                        pattern: grant.get_permission().to_string(),
+                    }
+                } else {
+                    PermissionSource::Direct
+                }
+            })
+            .collect();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+}
@@ -0,0 +1,54 @@
+use std::pin::Pin;
+
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        permission_check::{DenialReason, PermissionDecision},
+        user::UserID,
+    },
+    security::{error::SecurityError, permission_resolver::PermissionResolver},
+    traits::authorization_trait::AuthorizationTrait,
+};
+
+/// Authorize a user from the grants stored in the user_permission repository
+#[derive(Debug, Clone)]
+pub struct UserAuthorizer {
+    user_id: UserID,
+}
+
+impl UserAuthorizer {
+    pub fn new(user_id: &UserID) -> Self {
+        Self { user_id: *user_id }
+    }
+
+    pub fn get_user_id(&self) -> &UserID {
+        &self.user_id
+    }
+}
+
+impl AuthorizationTrait for UserAuthorizer {
+    fn authorize<'a>(
+        &'a self,
+        permission: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), SecurityError>> + Send + 'a>> {
+        let permission = permission.to_string();
+        Box::pin(async move {
+            let resolver = PermissionResolver::load(&self.user_id)
+                .await
+                .map_err(|_| SecurityError::not_authorized(DenialReason::Unavailable))?;
+            match resolver.check(&self.user_id, &permission).get_decision() {
+                PermissionDecision::Allowed { .. } => Ok(()),
+                PermissionDecision::Denied { reason } => Err(SecurityError::NotAuthorized {
+                    permission: Some(permission.clone()),
+                    principal_id: Some(self.user_id),
+                    reason: reason.clone(),
+                }),
+            }
+        })
+    }
+
+    fn get_principal_id(&self) -> Option<&Uuid> {
+        Some(&self.user_id)
+    }
+}
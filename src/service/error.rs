@@ -1,17 +1,93 @@
-use std::{any::Any, sync::Arc};
+use std::{any::Any, error::Error, fmt::Display, sync::Arc};
 
-#[derive(Debug)]
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::traits::service_error_trait::ServiceErrorTrait;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    Auth,
+    Validation,
+    NotFound,
+    Conflict,
+    Internal,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ServiceError {
+    #[serde(skip)]
     inner: Arc<dyn Any + Sync + Send>,
+    #[serde(skip)]
+    error: Arc<dyn ServiceErrorTrait>,
+    category: ErrorCategory,
+    code: &'static str,
+    message: String,
+    sources: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<Box<Value>>,
 }
 
 impl ServiceError {
-    pub fn new<T: Any + Send + Sync + 'static>(error: T) -> Self {
+    pub fn new<T: ServiceErrorTrait>(error: T) -> Self {
+        let category = error.category();
+        let code = error.code();
+        let details = error.details().map(Box::new);
+        let message = error.to_string();
+        let mut sources = Vec::new();
+        let mut source = error.source();
+        while let Some(e) = source {
+            sources.push(e.to_string());
+            source = e.source();
+        }
+        let error = Arc::new(error);
         Self {
-            inner: Arc::new(error),
+            inner: error.clone(),
+            error,
+            category,
+            code,
+            message,
+            sources,
+            details,
         }
     }
+
     pub fn get<T: Any + Send + Sync + 'static>(&self) -> Option<Arc<T>> {
         self.inner.clone().downcast::<T>().ok()
     }
+
+    pub fn get_category(&self) -> ErrorCategory {
+        self.category
+    }
+
+    pub fn get_code(&self) -> &'static str {
+        self.code
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    /// Messages of the underlying errors, from the closest to the root cause
+    pub fn get_sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    /// Structured data of the error, like what was denied to whom
+    pub fn get_details(&self) -> Option<&Value> {
+        self.details.as_deref()
+    }
+}
+
+impl Display for ServiceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ServiceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
 }
@@ -1,10 +1,41 @@
 use anyhow::anyhow;
+use chrono::Utc;
 use std::future::Future;
+use std::sync::Arc;
 
+use crate::audit::audit_trail::AuditTrail;
+use crate::configuration::Configuration;
+use crate::dtos::user::user_delete_response::UserDeleteResponse;
+use crate::dtos::user::user_onboard_request::UserOnboardRequest;
+use crate::dtos::user::user_onboard_response::UserOnboardResponse;
+use crate::dtos::user::user_status_change_request::UserStatusChangeRequest;
+use crate::dtos::user_internet::user_internet_find_request_filter::UserInternetFindRequestFilter;
+use crate::dtos::user_permission::user_permission_find_request_filter::UserPermissionFindRequestFilter;
+use crate::model::domain_event::DomainEvent;
+use crate::model::email_address::EmailAddress;
+use crate::model::password::Password;
+use crate::model::permission::{Permission, permission_matches};
 use crate::model::user::UserID;
+use crate::model::user::error::UserViolation;
+use crate::model::user::name::Name;
+use crate::model::user::user_delete_policy::UserDeletePolicy;
+use crate::model::user::user_status::UserStatus;
+use crate::model::user_internet::UserInternet;
+use crate::model::user_password::UserPassword;
+use crate::model::user_permission::UserPermission;
+use crate::repository::transaction::Transaction;
+use crate::repository::transaction::error::TransactionError;
+use crate::repository::unit_of_work::UnitOfWork;
+use crate::repository::user_internet_repository::UserInternetRepository;
+use crate::repository::user_password_policy_repository::UserPasswordPolicyRepository;
+use crate::repository::user_password_repository::UserPasswordRepository;
+use crate::repository::user_permission_repository::UserPermissionRepository;
 use crate::runtime::Runtime;
+use crate::security::guard::authorize;
+use crate::security::permission_resolver::PermissionResolver;
 use crate::service::error::ServiceError;
 use crate::traits::authentication_trait::AuthenticationTrait;
+use crate::traits::find_result_trait::FindResultTrait;
 use crate::{
     dtos::{
         find_request::FindRequest,
@@ -19,6 +50,8 @@ use crate::{
     repository::user_repository::UserRepository,
 };
 
+const PAGE_SIZE: u16 = 1000;
+
 #[derive(Debug, Clone)]
 pub struct UserService;
 
@@ -28,24 +61,27 @@ impl UserService {
         req: &UserAddRequest,
     ) -> impl Future<Output = Result<User, ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user:create")
-                .await
-                .map_err(ServiceError::new)?;
-            Runtime::get_instance()
-                .get::<UserRepository>()
-                .await
-                .ok_or(ServiceError::new(UserError::Unknown(anyhow!(
-                    "Cannot get user repository"
-                ))))?
-                .clone()
-                .save(&req.into())
-                .await
-                .map_err(ServiceError::new)
+            let mut audit = AuditTrail::new("user:create");
+            let result: Result<User, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user:create")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                let user = Self::get_repository()
+                    .await?
+                    .save(&req.into())
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_target("user", &user.get_id().to_string());
+                audit.set_after(&user);
+                Runtime::get_instance()
+                    .dispatch(DomainEvent::UserCreated { user: user.clone() })
+                    .await;
+                Ok(user)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
 
@@ -55,24 +91,36 @@ impl UserService {
         req: &UserUpdateRequest,
     ) -> impl Future<Output = Result<User, ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user:update")
-                .await
-                .map_err(ServiceError::new)?;
-            Runtime::get_instance()
-                .get::<UserRepository>()
-                .await
-                .ok_or(ServiceError::new(UserError::Unknown(anyhow!(
-                    "Cannot get user repository"
-                ))))?
-                .clone()
-                .update(user_id, &req.into())
-                .await
-                .map_err(ServiceError::new)
+            let mut audit = AuditTrail::new("user:update");
+            audit.set_target("user", &user_id.to_string());
+            let result: Result<User, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user:update")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                let repository = Self::get_repository().await?;
+                let mut user: User = req.into();
+                if let Ok(before) = repository.find_by_id(user_id).await {
+                    audit.set_before(&before);
+                    user = user.with_status(
+                        before.get_status(),
+                        before.get_status_reason(),
+                        before.get_status_changed_at(),
+                    );
+                }
+                let user = repository
+                    .update(user_id, &user)
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_after(&user);
+                Runtime::get_instance()
+                    .dispatch(DomainEvent::UserUpdated { user: user.clone() })
+                    .await;
+                Ok(user)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
 
@@ -81,24 +129,22 @@ impl UserService {
         user_id: &UserID,
     ) -> impl Future<Output = Result<User, ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user:find_one")
-                .await
-                .map_err(ServiceError::new)?;
-            Runtime::get_instance()
-                .get::<UserRepository>()
-                .await
-                .ok_or(ServiceError::new(UserError::Unknown(anyhow!(
-                    "Cannot get user repository"
-                ))))?
-                .clone()
-                .find_by_id(user_id)
-                .await
-                .map_err(ServiceError::new)
+            let mut audit = AuditTrail::new("user:find_one");
+            audit.set_target("user", &user_id.to_string());
+            let result: Result<User, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user:find_one")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                Self::get_repository()
+                    .await?
+                    .find_by_id(user_id)
+                    .await
+                    .map_err(ServiceError::new)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
 
@@ -107,50 +153,595 @@ impl UserService {
         req: &FindRequest<UserFindRequestFilter>,
     ) -> impl Future<Output = Result<FindResponse<User>, ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user:find")
-                .await
-                .map_err(ServiceError::new)?;
-            Runtime::get_instance()
-                .get::<UserRepository>()
-                .await
-                .ok_or(ServiceError::new(UserError::Unknown(anyhow!(
-                    "Cannot get user repository"
-                ))))?
-                .clone()
-                .find_all(req)
-                .await
-                .map_err(ServiceError::new)
+            let mut audit = AuditTrail::new("user:find");
+            let result: Result<FindResponse<User>, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user:find")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                Self::get_repository()
+                    .await?
+                    .find_all(req)
+                    .await
+                    .map_err(ServiceError::new)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
 
+    /// Refuse authentication to an active user until reactivated
+    pub fn suspend(
+        authenticatable: &dyn AuthenticationTrait,
+        req: &UserStatusChangeRequest,
+    ) -> impl Future<Output = Result<User, ServiceError>> + Send {
+        Self::change_status(
+            authenticatable,
+            "user:suspend",
+            req,
+            UserStatus::Suspended,
+            &[UserStatus::Active],
+        )
+    }
+
+    /// Refuse authentication to a user that left, until reactivated
+    pub fn deactivate(
+        authenticatable: &dyn AuthenticationTrait,
+        req: &UserStatusChangeRequest,
+    ) -> impl Future<Output = Result<User, ServiceError>> + Send {
+        Self::change_status(
+            authenticatable,
+            "user:deactivate",
+            req,
+            UserStatus::Deactivated,
+            &[UserStatus::Active, UserStatus::Suspended],
+        )
+    }
+
+    /// Make a suspended or deactivated user active again
+    pub fn reactivate(
+        authenticatable: &dyn AuthenticationTrait,
+        req: &UserStatusChangeRequest,
+    ) -> impl Future<Output = Result<User, ServiceError>> + Send {
+        Self::change_status(
+            authenticatable,
+            "user:reactivate",
+            req,
+            UserStatus::Active,
+            &[UserStatus::Suspended, UserStatus::Deactivated],
+        )
+    }
+
+    /// Mark the user deleted while keeping it for the audits
+    pub fn soft_delete(
+        authenticatable: &dyn AuthenticationTrait,
+        req: &UserStatusChangeRequest,
+    ) -> impl Future<Output = Result<User, ServiceError>> + Send {
+        Self::change_status(
+            authenticatable,
+            "user:soft_delete",
+            req,
+            UserStatus::Deleted,
+            &[
+                UserStatus::Active,
+                UserStatus::Suspended,
+                UserStatus::Deactivated,
+            ],
+        )
+    }
+
+    /// Make a soft-deleted user active again
+    pub fn restore(
+        authenticatable: &dyn AuthenticationTrait,
+        req: &UserStatusChangeRequest,
+    ) -> impl Future<Output = Result<User, ServiceError>> + Send {
+        Self::change_status(
+            authenticatable,
+            "user:restore",
+            req,
+            UserStatus::Active,
+            &[UserStatus::Deleted],
+        )
+    }
+
+    fn change_status(
+        authenticatable: &dyn AuthenticationTrait,
+        action: &'static str,
+        req: &UserStatusChangeRequest,
+        status: UserStatus,
+        allowed_from: &'static [UserStatus],
+    ) -> impl Future<Output = Result<User, ServiceError>> + Send {
+        Box::pin(async move {
+            let mut audit = AuditTrail::new(action);
+            audit.set_target("user", &req.get_user_id().to_string());
+            let result: Result<User, ServiceError> = async {
+                let authorizable = authorize(authenticatable, action)
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                let repository = Self::get_repository().await?;
+                let before = repository
+                    .find_by_id(req.get_user_id())
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_before(&before);
+                if !allowed_from.contains(&before.get_status()) {
+                    return Err(ServiceError::new(UserError::InvalidStatusTransition {
+                        id: *before.get_id(),
+                        from: before.get_status(),
+                        to: status,
+                    }));
+                }
+                let user = repository
+                    .update(
+                        before.get_id(),
+                        &before.with_status(status, req.get_reason(), Some(&Utc::now())),
+                    )
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_after(&user);
+                Runtime::get_instance()
+                    .dispatch(DomainEvent::UserUpdated { user: user.clone() })
+                    .await;
+                Ok(user)
+            }
+            .await;
+            audit.record(&result).await;
+            result
+        })
+    }
+
+    /// Delete the user, removing or refusing its dependents according to `[user] delete_policy`
+    ///
+    /// The cascade needs a `UnitOfWork`: without one a user that still has dependents is
+    /// refused with `TransactionError::NotSupported` rather than partly deleted.
     pub fn delete(
         authenticatable: &dyn AuthenticationTrait,
         req: &UserDeleteRequest,
-    ) -> impl Future<Output = Result<(), ServiceError>> + Send {
+    ) -> impl Future<Output = Result<UserDeleteResponse, ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
+            let mut audit = AuditTrail::new("user:delete");
+            audit.set_target("user", &req.get_user_id().to_string());
+            let result: Result<UserDeleteResponse, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user:delete")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                let runtime = Runtime::get_instance();
+                let policy = match runtime.get::<Configuration>().await {
+                    Some(configuration) => UserDeletePolicy::from_configuration(&configuration)
+                        .map_err(|e| ServiceError::new(UserError::InvalidDeletePolicy(e)))?,
+                    None => UserDeletePolicy::default(),
+                };
+                if let Ok(before) = Self::get_repository()
+                    .await?
+                    .find_by_id(req.get_user_id())
+                    .await
+                {
+                    audit.set_before(&before);
+                }
+                let deleted = match runtime.get::<UnitOfWork>().await {
+                    Some(unit_of_work) => {
+                        let transaction = unit_of_work.begin().await.map_err(ServiceError::new)?;
+                        match Self::delete_with_dependents(
+                            req.get_user_id(),
+                            policy,
+                            &transaction.get_user_repository(),
+                            &transaction.get_user_internet_repository(),
+                            &transaction.get_user_password_repository(),
+                            &transaction.get_user_permission_repository(),
+                        )
+                        .await
+                        {
+                            Ok(deleted) => {
+                                transaction.commit().await.map_err(ServiceError::new)?;
+                                deleted
+                            }
+                            Err(e) => {
+                                let _ = transaction.rollback().await;
+                                return Err(e);
+                            }
+                        }
+                    }
+                    // without a transaction only the user row can go, alone it cannot be left half deleted
+                    None => Self::delete_with_dependents(
+                        req.get_user_id(),
+                        UserDeletePolicy::Restrict,
+                        &*Self::get_repository().await?,
+                        &*runtime.get::<UserInternetRepository>().await.ok_or(
+                            ServiceError::new(UserError::Unknown(anyhow!(
+                                "Cannot get user_internet repository"
+                            ))),
+                        )?,
+                        &*runtime.get::<UserPasswordRepository>().await.ok_or(
+                            ServiceError::new(UserError::Unknown(anyhow!(
+                                "Cannot get user_password repository"
+                            ))),
+                        )?,
+                        &*runtime.get::<UserPermissionRepository>().await.ok_or(
+                            ServiceError::new(UserError::Unknown(anyhow!(
+                                "Cannot get user_permission repository"
+                            ))),
+                        )?,
+                    )
+                    .await
+                    .map_err(|e| match e.get::<UserError>().as_deref() {
+                        Some(UserError::HasDependents { .. })
+                            if policy == UserDeletePolicy::Cascade =>
+                        {
+                            ServiceError::new(TransactionError::NotSupported)
+                        }
+                        _ => e,
+                    })?,
+                };
+                for user_internet in deleted.get_emails() {
+                    runtime
+                        .dispatch(DomainEvent::EmailRemoved {
+                            user_internet: user_internet.clone(),
+                        })
+                        .await;
+                }
+                for user_permission in deleted.get_permissions() {
+                    runtime
+                        .dispatch(DomainEvent::PermissionRevoked {
+                            user_id: *user_permission.get_user_id(),
+                            permission: user_permission.get_permission().to_string(),
+                        })
+                        .await;
+                }
+                runtime
+                    .dispatch(DomainEvent::UserDeleted {
+                        user_id: *req.get_user_id(),
+                    })
+                    .await;
+                Ok(deleted)
+            }
+            .await;
+            audit.record(&result).await;
+            result
+        })
+    }
+
+    async fn delete_with_dependents(
+        user_id: &UserID,
+        policy: UserDeletePolicy,
+        user_repository: &UserRepository,
+        user_internet_repository: &UserInternetRepository,
+        user_password_repository: &UserPasswordRepository,
+        user_permission_repository: &UserPermissionRepository,
+    ) -> Result<UserDeleteResponse, ServiceError> {
+        user_repository
+            .find_by_id(user_id)
+            .await
+            .map_err(ServiceError::new)?;
+
+        let filter = UserInternetFindRequestFilter {
+            user_id: Some(*user_id),
+            ..Default::default()
+        };
+        let mut emails = Vec::new();
+        let mut offset = 1;
+        loop {
+            let request = FindRequest::new(&filter, "email", &PAGE_SIZE, &offset)
+                .map_err(|e| ServiceError::new(UserError::Unknown(anyhow!(e))))?;
+            let page = user_internet_repository
+                .find_all(&request)
+                .await
+                .map_err(ServiceError::new)?;
+            emails.extend(page.get_result());
+            if offset >= page.get_page_count() {
+                break;
+            }
+            offset += 1;
+        }
+
+        let filter = UserPermissionFindRequestFilter {
+            user_id: Some(*user_id),
+            include_expired: true,
+            ..Default::default()
+        };
+        let mut permissions = Vec::new();
+        let mut offset = 1;
+        loop {
+            let request = FindRequest::new(&filter, "permission", &PAGE_SIZE, &offset)
+                .map_err(|e| ServiceError::new(UserError::Unknown(anyhow!(e))))?;
+            let page = user_permission_repository
+                .find_all(&request)
+                .await
+                .map_err(ServiceError::new)?;
+            permissions.extend(page.get_result());
+            if offset >= page.get_page_count() {
+                break;
+            }
+            offset += 1;
+        }
+
+        let password = user_password_repository
+            .has_password(user_id)
+            .await
+            .map_err(ServiceError::new)?;
+
+        if policy == UserDeletePolicy::Restrict
+            && (!emails.is_empty() || !permissions.is_empty() || password)
+        {
+            return Err(ServiceError::new(UserError::HasDependents {
+                id: *user_id,
+                emails: emails.len(),
+                permissions: permissions.len(),
+                password,
+            }));
+        }
+
+        for user_internet in &emails {
+            user_internet_repository
+                .delete(&(*user_id, user_internet.get_email().clone()))
                 .await
                 .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user:delete")
+        }
+        for user_permission in &permissions {
+            user_permission_repository
+                .delete(&(*user_id, user_permission.get_permission().to_string()))
                 .await
                 .map_err(ServiceError::new)?;
-            Runtime::get_instance()
-                .get::<UserRepository>()
+        }
+        if password {
+            user_password_repository
+                .delete(user_id)
                 .await
-                .ok_or(ServiceError::new(UserError::Unknown(anyhow!(
-                    "Cannot get user repository"
-                ))))?
-                .clone()
-                .delete(req.get_user_id())
+                .map_err(ServiceError::new)?;
+        }
+        user_repository
+            .delete(user_id)
+            .await
+            .map_err(ServiceError::new)?;
+        Ok(UserDeleteResponse::new(
+            user_id,
+            &emails,
+            &permissions,
+            password,
+        ))
+    }
+
+    /// Create a user with its emails, password and grants, all or nothing.
+    ///
+    /// The whole request is validated before anything is written and every problem is reported.
+    pub fn onboard(
+        authenticatable: &dyn AuthenticationTrait,
+        req: &UserOnboardRequest,
+    ) -> impl Future<Output = Result<UserOnboardResponse, ServiceError>> + Send {
+        Box::pin(async {
+            let mut audit = AuditTrail::new("user:onboard");
+            let result: Result<UserOnboardResponse, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user:onboard")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                // each write of the onboarding needs the permission of its own service
+                let mut actions = vec!["user_internet:create", "user_password:create"];
+                if !req.get_permissions().is_empty() {
+                    actions.push("user_permission:create");
+                }
+                for action in actions {
+                    authorize(authenticatable, action)
+                        .await
+                        .map_err(ServiceError::new)?;
+                }
+                let (user, emails, password, permissions) = Self::validate_onboarding(req).await?;
+                let transaction = Runtime::get_instance()
+                    .get::<UnitOfWork>()
+                    .await
+                    .ok_or(ServiceError::new(TransactionError::NotSupported))?
+                    .begin()
+                    .await
+                    .map_err(ServiceError::new)?;
+                let onboarded = match Self::write_onboarding(
+                    &transaction,
+                    &user,
+                    &emails,
+                    &password,
+                    &permissions,
+                )
                 .await
-                .map_err(ServiceError::new)
+                {
+                    Ok(onboarded) => onboarded,
+                    Err(e) => {
+                        let _ = transaction.rollback().await;
+                        return Err(e);
+                    }
+                };
+                transaction.commit().await.map_err(ServiceError::new)?;
+                let user = onboarded.get_user();
+                audit.set_target("user", &user.get_id().to_string());
+                audit.set_after(&onboarded);
+                let runtime = Runtime::get_instance();
+                runtime
+                    .dispatch(DomainEvent::UserCreated { user: user.clone() })
+                    .await;
+                for user_internet in onboarded.get_emails() {
+                    runtime
+                        .dispatch(DomainEvent::EmailAdded {
+                            user_internet: user_internet.clone(),
+                        })
+                        .await;
+                }
+                runtime
+                    .dispatch(DomainEvent::PasswordChanged {
+                        user_id: *user.get_id(),
+                    })
+                    .await;
+                for user_permission in onboarded.get_permissions() {
+                    runtime
+                        .dispatch(DomainEvent::PermissionGranted {
+                            user_permission: user_permission.clone(),
+                        })
+                        .await;
+                }
+                Ok(onboarded)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
+
+    async fn validate_onboarding(
+        req: &UserOnboardRequest,
+    ) -> Result<(User, Vec<EmailAddress>, Password, Vec<Permission>), ServiceError> {
+        let mut violations = Vec::new();
+
+        let firstname = Name::new(req.get_firstname())
+            .map_err(|e| violations.push(UserViolation::new("firstname", &e.to_string())))
+            .ok();
+        let lastname = req.get_lastname().and_then(|lastname| {
+            Name::new(lastname)
+                .map_err(|e| violations.push(UserViolation::new("lastname", &e.to_string())))
+                .ok()
+        });
+
+        if req.get_emails().is_empty() {
+            violations.push(UserViolation::new(
+                "emails",
+                "At least one email is required",
+            ));
+        }
+        let user_internet_repository = Runtime::get_instance()
+            .get::<UserInternetRepository>()
+            .await
+            .ok_or(ServiceError::new(UserError::Unknown(anyhow!(
+                "Cannot get user_internet repository"
+            ))))?;
+        let mut emails: Vec<EmailAddress> = Vec::new();
+        for (index, raw) in req.get_emails().iter().enumerate() {
+            let field = format!("emails[{index}]");
+            let email = match EmailAddress::new(raw) {
+                Ok(email) => email,
+                Err(e) => {
+                    violations.push(UserViolation::new(&field, &e.to_string()));
+                    continue;
+                }
+            };
+            if emails.contains(&email) {
+                violations.push(UserViolation::new(&field, "Email given twice"));
+                continue;
+            }
+            let filter = UserInternetFindRequestFilter {
+                email: Some(raw.to_string()),
+                ..Default::default()
+            };
+            let request = FindRequest::new(&filter, "", &1, &1)
+                .map_err(|e| ServiceError::new(UserError::Unknown(anyhow!(e))))?;
+            let existing = user_internet_repository
+                .find_all(&request)
+                .await
+                .map_err(ServiceError::new)?;
+            if existing.get_result().next().is_some() {
+                violations.push(UserViolation::new(
+                    &field,
+                    "Email already associated to a user",
+                ));
+            }
+            emails.push(email);
+        }
+
+        let password_level = Runtime::get_instance()
+            .get::<UserPasswordPolicyRepository>()
+            .await
+            .ok_or(ServiceError::new(UserError::Unknown(anyhow!(
+                "Cannot get user_password_policy repository"
+            ))))?
+            .get_policy()
+            .await
+            .map_err(ServiceError::new)?
+            .0;
+        let password = Password::new(req.get_password(), &password_level)
+            .map_err(|e| violations.push(UserViolation::new("password", &e.to_string())))
+            .ok();
+
+        let known_permissions = PermissionResolver::load_known_permissions()
+            .await
+            .map_err(ServiceError::new)?;
+        let mut permissions: Vec<Permission> = Vec::new();
+        for (index, permission) in req.get_permissions().iter().enumerate() {
+            let field = format!("permissions[{index}]");
+            if permissions.contains(permission) {
+                violations.push(UserViolation::new(&field, "Permission given twice"));
+            } else if !known_permissions
+                .iter()
+                .any(|known| permission_matches(permission, known))
+            {
+                violations.push(UserViolation::new(
+                    &field,
+                    &format!("Permission {permission} does not exists"),
+                ));
+            } else {
+                permissions.push(permission.to_string());
+            }
+        }
+
+        match (firstname, password) {
+            (Some(firstname), Some(password)) if violations.is_empty() => Ok((
+                User::new(&UserID::nil(), &firstname, lastname.as_ref()),
+                emails,
+                password,
+                permissions,
+            )),
+            _ => Err(ServiceError::new(UserError::InvalidOnboarding {
+                violations,
+            })),
+        }
+    }
+
+    async fn write_onboarding(
+        transaction: &Transaction,
+        user: &User,
+        emails: &[EmailAddress],
+        password: &Password,
+        permissions: &[Permission],
+    ) -> Result<UserOnboardResponse, ServiceError> {
+        let user = transaction
+            .get_user_repository()
+            .save(user)
+            .await
+            .map_err(ServiceError::new)?;
+        let user_internet_repository = transaction.get_user_internet_repository();
+        let mut user_internets = Vec::new();
+        for email in emails {
+            user_internets.push(
+                user_internet_repository
+                    .save(&UserInternet::new(user.get_id(), email))
+                    .await
+                    .map_err(ServiceError::new)?,
+            );
+        }
+        transaction
+            .get_user_password_repository()
+            .save(&UserPassword::new(user.get_id(), password))
+            .await
+            .map_err(ServiceError::new)?;
+        let user_permission_repository = transaction.get_user_permission_repository();
+        let mut user_permissions = Vec::new();
+        for permission in permissions {
+            user_permissions.push(
+                user_permission_repository
+                    .save(&UserPermission::new(user.get_id(), permission))
+                    .await
+                    .map_err(ServiceError::new)?,
+            );
+        }
+        Ok(UserOnboardResponse::new(
+            &user,
+            &user_internets,
+            &user_permissions,
+        ))
+    }
+
+    async fn get_repository() -> Result<Arc<UserRepository>, ServiceError> {
+        Runtime::get_instance()
+            .get::<UserRepository>()
+            .await
+            .ok_or(ServiceError::new(UserError::Unknown(anyhow!(
+                "Cannot get user repository"
+            ))))
+    }
 }
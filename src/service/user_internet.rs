@@ -1,6 +1,9 @@
+use std::sync::Arc;
+
 use anyhow::anyhow;
 
 use crate::{
+    audit::audit_trail::AuditTrail,
     dtos::{
         find_request::FindRequest,
         find_response::FindResponse,
@@ -10,9 +13,13 @@ use crate::{
             user_internet_find_request_filter::UserInternetFindRequestFilter,
         },
     },
-    model::user_internet::{UserInternet, error::UserInternetError},
+    model::{
+        domain_event::DomainEvent,
+        user_internet::{UserInternet, error::UserInternetError},
+    },
     repository::user_internet_repository::UserInternetRepository,
     runtime::Runtime,
+    security::guard::authorize,
     service::error::ServiceError,
     traits::authentication_trait::AuthenticationTrait,
 };
@@ -26,25 +33,33 @@ impl UserInternetService {
         req: &UserInternetAddRequest,
     ) -> impl Future<Output = Result<UserInternet, ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user_internet:create")
-                .await
-                .map_err(ServiceError::new)?;
+            let mut audit = AuditTrail::new("user_internet:create");
+            audit.set_target(
+                "user_internet",
+                &format!("{}/{}", req.get_user_id(), req.get_email()),
+            );
+            let result: Result<UserInternet, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_internet:create")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
 
-            Runtime::get_instance()
-                .get::<UserInternetRepository>()
-                .await
-                .ok_or(ServiceError::new(UserInternetError::Unknown(anyhow!(
-                    "Cannot get user_internet repository"
-                ))))?
-                .clone()
-                .save(&req.into())
-                .await
-                .map_err(ServiceError::new)
+                let user_internet = Self::get_repository()
+                    .await?
+                    .save(&req.into())
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_after(&user_internet);
+                Runtime::get_instance()
+                    .dispatch(DomainEvent::EmailAdded {
+                        user_internet: user_internet.clone(),
+                    })
+                    .await;
+                Ok(user_internet)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
 
@@ -53,25 +68,22 @@ impl UserInternetService {
         req: &FindRequest<UserInternetFindRequestFilter>,
     ) -> impl Future<Output = Result<FindResponse<UserInternet>, ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user_internet:find")
-                .await
-                .map_err(ServiceError::new)?;
+            let mut audit = AuditTrail::new("user_internet:find");
+            let result: Result<FindResponse<UserInternet>, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_internet:find")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
 
-            Runtime::get_instance()
-                .get::<UserInternetRepository>()
-                .await
-                .ok_or(ServiceError::new(UserInternetError::Unknown(anyhow!(
-                    "Cannot get user_internet repository"
-                ))))?
-                .clone()
-                .find_all(req)
-                .await
-                .map_err(ServiceError::new)
+                Self::get_repository()
+                    .await?
+                    .find_all(req)
+                    .await
+                    .map_err(ServiceError::new)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
 
@@ -80,25 +92,42 @@ impl UserInternetService {
         req: &UserInternetDeleteRequest,
     ) -> impl Future<Output = Result<(), ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user_internet:delete")
-                .await
-                .map_err(ServiceError::new)?;
+            let mut audit = AuditTrail::new("user_internet:delete");
+            audit.set_target(
+                "user_internet",
+                &format!("{}/{}", req.get_user_id(), req.get_email()),
+            );
+            audit.set_before(&UserInternet::new(req.get_user_id(), req.get_email()));
+            let result: Result<(), ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_internet:delete")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
 
-            Runtime::get_instance()
-                .get::<UserInternetRepository>()
-                .await
-                .ok_or(ServiceError::new(UserInternetError::Unknown(anyhow!(
-                    "Cannot get user_internet repository"
-                ))))?
-                .clone()
-                .delete(&(*req.get_user_id(), req.get_email().clone()))
-                .await
-                .map_err(ServiceError::new)
+                Self::get_repository()
+                    .await?
+                    .delete(&(*req.get_user_id(), req.get_email().clone()))
+                    .await
+                    .map_err(ServiceError::new)?;
+                Runtime::get_instance()
+                    .dispatch(DomainEvent::EmailRemoved {
+                        user_internet: UserInternet::new(req.get_user_id(), req.get_email()),
+                    })
+                    .await;
+                Ok(())
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
+
+    async fn get_repository() -> Result<Arc<UserInternetRepository>, ServiceError> {
+        Runtime::get_instance()
+            .get::<UserInternetRepository>()
+            .await
+            .ok_or(ServiceError::new(UserInternetError::Unknown(anyhow!(
+                "Cannot get user_internet repository"
+            ))))
+    }
 }
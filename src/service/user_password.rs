@@ -1,10 +1,12 @@
 use anyhow::anyhow;
 
 use crate::{
+    audit::audit_trail::AuditTrail,
     dtos::user_password::user_password_add_request::UserPasswordAddRequest,
     model::{
+        domain_event::DomainEvent,
         password::Password,
-        user::UserID,
+        user::{UserID, error::UserError},
         user_password::{
             UserPassword, error::UserPasswordError,
             user_password_policy::error::UserPasswordPolicyError,
@@ -12,9 +14,10 @@ use crate::{
     },
     repository::{
         user_password_policy_repository::UserPasswordPolicyRepository,
-        user_password_repository::UserPasswordRepository,
+        user_password_repository::UserPasswordRepository, user_repository::UserRepository,
     },
     runtime::Runtime,
+    security::guard::authorize,
     service::error::ServiceError,
     traits::authentication_trait::AuthenticationTrait,
 };
@@ -28,40 +31,50 @@ impl UserPasswordService {
         req: &UserPasswordAddRequest,
     ) -> impl Future<Output = Result<UserPassword, ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user_password:create")
-                .await
-                .map_err(ServiceError::new)?;
-            let password = Password::new(
-                req.get_password(),
-                &Runtime::get_instance()
-                    .get::<UserPasswordPolicyRepository>()
+            let mut audit = AuditTrail::new("user_password:create");
+            audit.set_target("user_password", &req.get_user_id().to_string());
+            let result: Result<UserPassword, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_password:create")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                let password = Password::new(
+                    req.get_password(),
+                    &Runtime::get_instance()
+                        .get::<UserPasswordPolicyRepository>()
+                        .await
+                        .ok_or(ServiceError::new(UserPasswordPolicyError::Unknown(
+                            anyhow!("Cannot get user_password_policy repository"),
+                        )))?
+                        .clone()
+                        .get_policy()
+                        .await
+                        .map_err(|e| ServiceError::new(UserPasswordError::Unknown(anyhow!(e))))?
+                        .0,
+                )
+                .map_err(|e| ServiceError::new(UserPasswordError::PasswordError(e)))?;
+                let user_password = UserPassword::new(req.get_user_id(), &password);
+                let user_password = Runtime::get_instance()
+                    .get::<UserPasswordRepository>()
                     .await
                     .ok_or(ServiceError::new(UserPasswordPolicyError::Unknown(
-                        anyhow!("Cannot get user_password_policy repository"),
+                        anyhow!("Cannot get user_password repository"),
                     )))?
                     .clone()
-                    .get_policy()
+                    .save(&user_password)
                     .await
-                    .map_err(|e| ServiceError::new(UserPasswordError::Unknown(anyhow!(e))))?
-                    .0,
-            )
-            .map_err(|e| ServiceError::new(UserPasswordError::PasswordError(e)))?;
-            let user_password = UserPassword::new(req.get_user_id(), &password);
-            Runtime::get_instance()
-                .get::<UserPasswordRepository>()
-                .await
-                .ok_or(ServiceError::new(UserPasswordPolicyError::Unknown(
-                    anyhow!("Cannot get user_password repository"),
-                )))?
-                .clone()
-                .save(&user_password)
-                .await
-                .map_err(ServiceError::new)
+                    .map_err(ServiceError::new)?;
+                audit.set_after(&user_password);
+                Runtime::get_instance()
+                    .dispatch(DomainEvent::PasswordChanged {
+                        user_id: *user_password.get_user_id(),
+                    })
+                    .await;
+                Ok(user_password)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
 
@@ -71,24 +84,47 @@ impl UserPasswordService {
         password: &str,
     ) -> impl Future<Output = Result<(), ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user_password:match")
-                .await
-                .map_err(ServiceError::new)?;
-            Runtime::get_instance()
-                .get::<UserPasswordRepository>()
-                .await
-                .ok_or(ServiceError::new(UserPasswordError::Unknown(anyhow!(
-                    "Cannot get user_password repository"
-                ))))?
-                .clone()
-                .verify_password(user_id, password)
-                .await
-                .map_err(ServiceError::new)
+            let mut audit = AuditTrail::new("user_password:match");
+            audit.set_target("user_password", &user_id.to_string());
+            let result: Result<(), ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_password:match")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                let user = Runtime::get_instance()
+                    .get::<UserRepository>()
+                    .await
+                    .ok_or(ServiceError::new(UserPasswordError::Unknown(anyhow!(
+                        "Cannot get user repository"
+                    ))))?
+                    .find_by_id(user_id)
+                    .await
+                    .map_err(|e| match e {
+                        UserError::UserNotExists { id } => {
+                            ServiceError::new(UserPasswordError::UserNotExists { id })
+                        }
+                        e => ServiceError::new(UserPasswordError::Unknown(anyhow!(e))),
+                    })?;
+                if !user.is_active() {
+                    return Err(ServiceError::new(UserPasswordError::UserNotActive {
+                        id: *user_id,
+                        status: user.get_status(),
+                    }));
+                }
+                Runtime::get_instance()
+                    .get::<UserPasswordRepository>()
+                    .await
+                    .ok_or(ServiceError::new(UserPasswordError::Unknown(anyhow!(
+                        "Cannot get user_password repository"
+                    ))))?
+                    .clone()
+                    .verify_password(user_id, password)
+                    .await
+                    .map_err(ServiceError::new)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
 }
@@ -1,15 +1,26 @@
 use std::future::Future;
+use std::sync::Arc;
 
 use anyhow::anyhow;
+use chrono::Utc;
 
+use crate::audit::audit_trail::AuditTrail;
 use crate::dtos::user_permission::user_permission_add_request::UserPermissionAddRequest;
 use crate::dtos::user_permission::user_permission_delete_request::UserPermissionDeleteRequest;
 use crate::dtos::user_permission::user_permission_find_request_filter::UserPermissionFindRequestFilter;
 use crate::dtos::{find_request::FindRequest, find_response::FindResponse};
+use crate::model::domain_event::DomainEvent;
+use crate::model::effective_permission::EffectivePermission;
+use crate::model::permission_check::PermissionCheck;
+use crate::model::user::UserID;
+use crate::model::user::error::UserError;
 use crate::model::user_permission::UserPermission;
 use crate::model::user_permission::error::UserPermissionError;
 use crate::repository::user_permission_repository::UserPermissionRepository;
+use crate::repository::user_repository::UserRepository;
 use crate::runtime::Runtime;
+use crate::security::guard::authorize;
+use crate::security::permission_resolver::PermissionResolver;
 use crate::service::error::ServiceError;
 use crate::traits::authentication_trait::AuthenticationTrait;
 
@@ -22,24 +33,45 @@ impl UserPermissionService {
         req: &UserPermissionAddRequest,
     ) -> impl Future<Output = Result<UserPermission, ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user_permission:create")
-                .await
-                .map_err(ServiceError::new)?;
-            Runtime::get_instance()
-                .get::<UserPermissionRepository>()
-                .await
-                .ok_or(ServiceError::new(UserPermissionError::Unknown(anyhow!(
-                    "Cannot get user_permission repository"
-                ))))?
-                .clone()
-                .save(&req.into())
-                .await
-                .map_err(ServiceError::new)
+            let mut audit = AuditTrail::new("user_permission:create");
+            audit.set_target(
+                "user_permission",
+                &format!("{}/{}", req.get_user_id(), req.get_permission()),
+            );
+            let result: Result<UserPermission, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_permission:create")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                if let (Some(valid_from), Some(valid_until)) =
+                    (req.get_valid_from(), req.get_valid_until())
+                {
+                    if valid_until <= valid_from {
+                        return Err(ServiceError::new(
+                            UserPermissionError::InvalidValidityPeriod {
+                                permission: req.get_permission().to_string(),
+                                valid_from: *valid_from,
+                                valid_until: *valid_until,
+                            },
+                        ));
+                    }
+                }
+                let user_permission = Self::get_repository()
+                    .await?
+                    .save(&req.into())
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_after(&user_permission);
+                Runtime::get_instance()
+                    .dispatch(DomainEvent::PermissionGranted {
+                        user_permission: user_permission.clone(),
+                    })
+                    .await;
+                Ok(user_permission)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
 
@@ -48,24 +80,21 @@ impl UserPermissionService {
         req: &FindRequest<UserPermissionFindRequestFilter>,
     ) -> impl Future<Output = Result<FindResponse<UserPermission>, ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user_permission:find")
-                .await
-                .map_err(ServiceError::new)?;
-            Runtime::get_instance()
-                .get::<UserPermissionRepository>()
-                .await
-                .ok_or(ServiceError::new(UserPermissionError::Unknown(anyhow!(
-                    "Cannot get user_permisison repository"
-                ))))?
-                .clone()
-                .find_all(req)
-                .await
-                .map_err(ServiceError::new)
+            let mut audit = AuditTrail::new("user_permission:find");
+            let result: Result<FindResponse<UserPermission>, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_permission:find")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                Self::get_repository()
+                    .await?
+                    .find_all(req)
+                    .await
+                    .map_err(ServiceError::new)
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
 
@@ -74,24 +103,144 @@ impl UserPermissionService {
         req: &UserPermissionDeleteRequest,
     ) -> impl Future<Output = Result<(), ServiceError>> + Send {
         Box::pin(async {
-            let authorizable = authenticatable
-                .authenticate()
-                .await
-                .map_err(ServiceError::new)?;
-            authorizable
-                .authorize("user_permission:delete")
-                .await
-                .map_err(ServiceError::new)?;
-            Runtime::get_instance()
-                .get::<UserPermissionRepository>()
-                .await
-                .ok_or(ServiceError::new(UserPermissionError::Unknown(anyhow!(
-                    "Cannot get user_permission repository"
-                ))))?
-                .clone()
-                .delete(&(*req.get_user_id(), req.get_permission().clone()))
-                .await
-                .map_err(ServiceError::new)
+            let mut audit = AuditTrail::new("user_permission:delete");
+            audit.set_target(
+                "user_permission",
+                &format!("{}/{}", req.get_user_id(), req.get_permission()),
+            );
+            let result: Result<(), ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_permission:delete")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                let repository = Self::get_repository().await?;
+                let id = (*req.get_user_id(), req.get_permission().clone());
+                if let Ok(before) = repository.find_by_id(&id).await {
+                    audit.set_before(&before);
+                }
+                repository.delete(&id).await.map_err(ServiceError::new)?;
+                Runtime::get_instance()
+                    .dispatch(DomainEvent::PermissionRevoked {
+                        user_id: id.0,
+                        permission: id.1,
+                    })
+                    .await;
+                Ok(())
+            }
+            .await;
+            audit.record(&result).await;
+            result
         })
     }
+
+    /// List every permission held by the user with the grants it comes from
+    pub fn find_effective(
+        authenticatable: &dyn AuthenticationTrait,
+        user_id: &UserID,
+    ) -> impl Future<Output = Result<Vec<EffectivePermission>, ServiceError>> + Send {
+        Box::pin(async {
+            let mut audit = AuditTrail::new("user_permission:find_effective");
+            audit.set_target("user", &user_id.to_string());
+            let result: Result<Vec<EffectivePermission>, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_permission:find_effective")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                Self::ensure_user_exists(user_id).await?;
+                Ok(PermissionResolver::load(user_id)
+                    .await
+                    .map_err(ServiceError::new)?
+                    .effective_permissions())
+            }
+            .await;
+            audit.record(&result).await;
+            result
+        })
+    }
+
+    /// Dry-run the authorization of a permission for the user and explain the decision
+    pub fn check(
+        authenticatable: &dyn AuthenticationTrait,
+        user_id: &UserID,
+        permission: &str,
+    ) -> impl Future<Output = Result<PermissionCheck, ServiceError>> + Send {
+        Box::pin(async move {
+            let mut audit = AuditTrail::new("user_permission:check");
+            audit.set_target("user", &user_id.to_string());
+            let result: Result<PermissionCheck, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_permission:check")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                Self::ensure_user_exists(user_id).await?;
+                Ok(PermissionResolver::load(user_id)
+                    .await
+                    .map_err(ServiceError::new)?
+                    .check(user_id, permission))
+            }
+            .await;
+            audit.record(&result).await;
+            result
+        })
+    }
+
+    /// Remove every expired grant and report what was removed
+    pub fn purge_expired(
+        authenticatable: &dyn AuthenticationTrait,
+    ) -> impl Future<Output = Result<Vec<UserPermission>, ServiceError>> + Send {
+        Box::pin(async {
+            let mut audit = AuditTrail::new("user_permission:purge_expired");
+            let result: Result<Vec<UserPermission>, ServiceError> = async {
+                let authorizable = authorize(authenticatable, "user_permission:purge_expired")
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_actor(authorizable.get_principal_id());
+                let purged = Self::get_repository()
+                    .await?
+                    .purge_expired(&Utc::now())
+                    .await
+                    .map_err(ServiceError::new)?;
+                audit.set_before(&purged);
+                for user_permission in &purged {
+                    Runtime::get_instance()
+                        .dispatch(DomainEvent::PermissionRevoked {
+                            user_id: *user_permission.get_user_id(),
+                            permission: user_permission.get_permission().to_string(),
+                        })
+                        .await;
+                }
+                Ok(purged)
+            }
+            .await;
+            audit.record(&result).await;
+            result
+        })
+    }
+
+    async fn get_repository() -> Result<Arc<UserPermissionRepository>, ServiceError> {
+        Runtime::get_instance()
+            .get::<UserPermissionRepository>()
+            .await
+            .ok_or(ServiceError::new(UserPermissionError::Unknown(anyhow!(
+                "Cannot get user_permission repository"
+            ))))
+    }
+
+    async fn ensure_user_exists(user_id: &UserID) -> Result<(), ServiceError> {
+        Runtime::get_instance()
+            .get::<UserRepository>()
+            .await
+            .ok_or(ServiceError::new(UserPermissionError::Unknown(anyhow!(
+                "Cannot get user repository"
+            ))))?
+            .find_by_id(user_id)
+            .await
+            .map(|_| ())
+            .map_err(|e| match e {
+                UserError::UserNotExists { id } => {
+                    ServiceError::new(UserPermissionError::UserNotExists { id })
+                }
+                e => ServiceError::new(UserPermissionError::Unknown(anyhow!(e.to_string()))),
+            })
+    }
 }
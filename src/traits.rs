@@ -1,12 +1,20 @@
 pub mod adapter_loader_trait;
+pub mod audit_sink_trait;
 pub mod authentication_trait;
 pub mod authorization_trait;
+pub mod configuration_subscriber_trait;
+pub mod domain_event;
+pub mod event_subscriber_trait;
 pub mod find_option_trait;
 pub mod find_request_trait;
 pub mod find_result_trait;
 pub mod initialize_trait;
 pub mod permission;
+pub mod plugin_adapter_trait;
 pub mod repository_trait;
+pub mod service_error_trait;
+pub mod transaction_trait;
+pub mod unit_of_work_trait;
 pub mod user;
 pub mod user_internet;
 pub mod user_password;
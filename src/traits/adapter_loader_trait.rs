@@ -1,14 +1,36 @@
 use std::{fmt::Debug, hash::Hash, pin::Pin};
 
-use crate::traits::initialize_trait::InitializeTrait;
+use crate::{
+    configuration::schema::ConfigurationSchema, model::adapter_health::AdapterHealth,
+    traits::initialize_trait::InitializeTrait,
+};
 
 pub trait AdapterLoaderTrait: InitializeTrait + Debug + Send + Sync {
     fn name(&self) -> &str;
 
+    /// Names of the adapters to start before this one
+    fn dependencies(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Configuration expected by the adapter, validated by `start` before any adapter is loaded
+    fn configuration_schema(&self) -> Option<ConfigurationSchema> {
+        None
+    }
+
     /// Add adapter into the runtime
     /// Adapter should be prepared before initialized
     /// call load before initialiaze
     fn load<'a>(&'a self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>;
+
+    fn health<'a>(&'a self) -> Pin<Box<dyn Future<Output = AdapterHealth> + Send + 'a>> {
+        Box::pin(async { AdapterHealth::Healthy })
+    }
+
+    /// Release what the adapter holds, called in the reverse order of the startup
+    fn shutdown<'a>(&'a self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async { Ok(()) })
+    }
 }
 
 impl Hash for dyn AdapterLoaderTrait {
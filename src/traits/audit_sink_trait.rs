@@ -0,0 +1,11 @@
+use std::{fmt::Debug, pin::Pin};
+
+use crate::model::audit_event::AuditEvent;
+
+pub trait AuditSinkTrait: Debug + Sync + Send + 'static {
+    /// Store the event, sinks are append-only
+    fn record<'a>(
+        &'a self,
+        event: &'a AuditEvent,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>;
+}
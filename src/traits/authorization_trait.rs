@@ -1,5 +1,7 @@
 use std::pin::Pin;
 
+use uuid::Uuid;
+
 use crate::security::error::SecurityError;
 
 pub trait AuthorizationTrait: Sync + Send + 'static {
@@ -7,4 +9,9 @@ pub trait AuthorizationTrait: Sync + Send + 'static {
         &'a self,
         permission: &str,
     ) -> Pin<Box<dyn Future<Output = Result<(), SecurityError>> + Send + 'a>>;
+
+    /// Id of the authenticated principal, reported when a permission is refused
+    fn get_principal_id(&self) -> Option<&Uuid> {
+        None
+    }
 }
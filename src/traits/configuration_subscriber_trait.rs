@@ -0,0 +1,13 @@
+use std::pin::Pin;
+
+use crate::configuration::watch::ConfigurationChange;
+
+pub trait ConfigurationSubscriberTrait: Sync + Send + 'static {
+    fn name(&self) -> &str;
+
+    /// Called after a reload changed the value at the subscribed path
+    fn handle<'a>(
+        &'a self,
+        change: &'a ConfigurationChange,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>;
+}
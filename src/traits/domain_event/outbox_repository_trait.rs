@@ -0,0 +1,44 @@
+use std::pin::Pin;
+
+use uuid::Uuid;
+
+use crate::{
+    model::domain_event::{DomainEventEnvelope, ParkedDomainEvent, error::DomainEventError},
+    traits::initialize_trait::InitializeTrait,
+};
+
+/// Events waiting to be relayed to the subscribers.
+///
+/// An adapter registering an outbox promises that its repositories append the
+/// events of a mutation in the same unit of work as the mutation itself.
+pub trait OutboxRepositoryTrait: InitializeTrait + Sync + Send + 'static {
+    fn append<'a>(
+        &'a self,
+        envelopes: &'a [DomainEventEnvelope],
+    ) -> Pin<Box<dyn Future<Output = Result<(), DomainEventError>> + Send + 'a>>;
+
+    /// Oldest pending events first
+    fn find_pending<'a>(
+        &'a self,
+        limit: u16,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<DomainEventEnvelope>, DomainEventError>> + Send + 'a>>;
+
+    /// Remove the event from the outbox once delivered
+    fn acknowledge<'a>(
+        &'a self,
+        id: &'a Uuid,
+    ) -> Pin<Box<dyn Future<Output = Result<(), DomainEventError>> + Send + 'a>>;
+
+    /// Move a pending event out of the queue once the relay gave up delivering it
+    fn park<'a>(
+        &'a self,
+        id: &'a Uuid,
+        reason: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), DomainEventError>> + Send + 'a>>;
+
+    /// Oldest parked events first
+    fn find_parked<'a>(
+        &'a self,
+        limit: u16,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<ParkedDomainEvent>, DomainEventError>> + Send + 'a>>;
+}
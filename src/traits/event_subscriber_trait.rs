@@ -0,0 +1,16 @@
+use std::pin::Pin;
+
+pub trait EventSubscriberTrait<E>: Sync + Send + 'static
+where
+    E: Clone + Send + Sync + 'static,
+{
+    fn name(&self) -> &str;
+
+    /// Called off the task of the publisher for every published event, so a slow
+    /// subscriber never delays the mutation. An error leaves an event of the outbox
+    /// pending, to be handed again by the next relay pass
+    fn handle<'a>(
+        &'a self,
+        event: &'a E,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>>;
+}
@@ -0,0 +1,33 @@
+use crate::{
+    configuration::schema::ConfigurationSchema, model::adapter_health::AdapterHealth,
+    runtime::plugin::export::PluginHost,
+};
+
+/// Adapter of a plugin written in Rust, exported by `declare_adapter_plugin!`.
+///
+/// It only reaches the host through the `PluginHost` given to `load`. Its methods block
+/// and run on the blocking pool of the host, so it may drive its own tokio runtime.
+pub trait PluginAdapterTrait: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Names of the adapters to start before this one
+    fn dependencies(&self) -> &[&str] {
+        &[]
+    }
+
+    /// Configuration expected by the adapter, validated by the host before any adapter is loaded
+    fn configuration_schema(&self) -> Option<ConfigurationSchema> {
+        None
+    }
+
+    fn load(&self, host: &PluginHost) -> anyhow::Result<()>;
+
+    fn health(&self) -> AdapterHealth {
+        AdapterHealth::Healthy
+    }
+
+    /// Release what the adapter holds, called in the reverse order of the startup
+    fn shutdown(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+use std::error::Error;
+
+use serde_json::Value;
+
+use crate::service::error::ErrorCategory;
+
+/// Error which can be returned by a service
+pub trait ServiceErrorTrait: Error + Sync + Send + 'static {
+    fn category(&self) -> ErrorCategory;
+
+    /// Stable machine-readable code
+    fn code(&self) -> &'static str;
+
+    /// Structured data of the error for the clients, serialized with it
+    fn details(&self) -> Option<Value> {
+        None
+    }
+}
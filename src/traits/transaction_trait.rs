@@ -0,0 +1,27 @@
+use std::pin::Pin;
+
+use crate::repository::{
+    transaction::error::TransactionError, user_internet_repository::UserInternetRepository,
+    user_password_repository::UserPasswordRepository,
+    user_permission_repository::UserPermissionRepository, user_repository::UserRepository,
+};
+
+/// Writes made through the bound repositories are visible to the rest of the
+/// runtime only once committed, and are all discarded on rollback or drop.
+pub trait TransactionTrait: Sync + Send {
+    fn get_user_repository(&self) -> UserRepository;
+
+    fn get_user_internet_repository(&self) -> UserInternetRepository;
+
+    fn get_user_password_repository(&self) -> UserPasswordRepository;
+
+    fn get_user_permission_repository(&self) -> UserPermissionRepository;
+
+    fn commit<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), TransactionError>> + Send + 'a>>;
+
+    fn rollback<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<(), TransactionError>> + Send + 'a>>;
+}
@@ -0,0 +1,9 @@
+use std::pin::Pin;
+
+use crate::repository::transaction::{Transaction, error::TransactionError};
+
+pub trait UnitOfWorkTrait: Sync + Send {
+    fn begin<'a>(
+        &'a self,
+    ) -> Pin<Box<dyn Future<Output = Result<Transaction, TransactionError>> + Send + 'a>>;
+}
@@ -19,6 +19,12 @@ pub trait UserPasswordRepositoryTrait:
         FindResult = FindResponse<()>,
     >
 {
+    /// Whether a password is stored for the user
+    fn has_password<'a>(
+        &'a self,
+        user_id: &'a Self::Id,
+    ) -> Pin<Box<dyn Future<Output = Result<bool, Self::Error>> + Send + 'a>>;
+
     fn verify_password<'a>(
         &'a self,
         user_id: &'a Self::Id,
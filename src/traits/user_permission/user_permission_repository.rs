@@ -1,3 +1,7 @@
+use std::pin::Pin;
+
+use chrono::{DateTime, Utc};
+
 use crate::{
     dtos::{
         find_request::FindRequest, find_response::FindResponse,
@@ -11,6 +15,10 @@ use crate::{
     traits::{initialize_trait::InitializeTrait, repository_trait::RepositoryTrait},
 };
 
+/// Grants removed by a purge
+pub type PurgedGrants<'a> =
+    Pin<Box<dyn Future<Output = Result<Vec<UserPermission>, UserPermissionError>> + Send + 'a>>;
+
 pub trait UserPermissionRepositoryTrait:
     InitializeTrait
     + RepositoryTrait<
@@ -21,4 +29,6 @@ pub trait UserPermissionRepositoryTrait:
         FindResult = FindResponse<UserPermission>,
     >
 {
+    /// Remove every grant expired at the given instant and return them
+    fn purge_expired<'a>(&'a self, instant: &'a DateTime<Utc>) -> PurgedGrants<'a>;
 }
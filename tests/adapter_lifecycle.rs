@@ -0,0 +1,145 @@
+use std::{
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+
+use fototra::{
+    model::adapter_health::AdapterHealth,
+    runtime::{Runtime, error::RuntimeError},
+    traits::{adapter_loader_trait::AdapterLoaderTrait, initialize_trait::InitializeTrait},
+};
+
+#[derive(Debug)]
+struct Probe {
+    name: &'static str,
+    dependencies: &'static [&'static str],
+    health: AdapterHealth,
+    fail_shutdown: bool,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Probe {
+    fn new(
+        name: &'static str,
+        dependencies: &'static [&'static str],
+        log: &Arc<Mutex<Vec<String>>>,
+    ) -> Self {
+        Self {
+            name,
+            dependencies,
+            health: AdapterHealth::Healthy,
+            fail_shutdown: false,
+            log: log.clone(),
+        }
+    }
+}
+
+impl InitializeTrait for Probe {}
+
+impl AdapterLoaderTrait for Probe {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn dependencies(&self) -> &[&str] {
+        self.dependencies
+    }
+
+    fn load<'a>(&'a self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            self.log.lock().unwrap().push(format!("load {}", self.name));
+            Ok(())
+        })
+    }
+
+    fn health<'a>(&'a self) -> Pin<Box<dyn Future<Output = AdapterHealth> + Send + 'a>> {
+        Box::pin(async { self.health.clone() })
+    }
+
+    fn shutdown<'a>(&'a self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("shutdown {}", self.name));
+            if self.fail_shutdown {
+                anyhow::bail!("connection already closed");
+            }
+            Ok(())
+        })
+    }
+}
+
+pub async fn test_adapter_lifecycle() {
+    let runtime = Runtime::get_instance();
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    // dependencies start first whatever the order they were added in
+    runtime
+        .add_adapter(Arc::new(Probe::new(
+            "Cache",
+            &["Database", "InMemoryRepository"],
+            &log,
+        )))
+        .await
+        .unwrap();
+    runtime
+        .add_adapter(Arc::new(Probe {
+            health: AdapterHealth::Degraded {
+                reason: "read only replica".to_string(),
+            },
+            fail_shutdown: true,
+            ..Probe::new("Database", &[], &log)
+        }))
+        .await
+        .unwrap();
+    assert!(matches!(
+        runtime
+            .add_adapter(Arc::new(Probe::new("Database", &[], &log)))
+            .await,
+        Err(RuntimeError::DuplicateAdapter { .. })
+    ));
+    runtime.start().await.unwrap();
+    assert_eq!(*log.lock().unwrap(), ["load Database", "load Cache"]);
+
+    assert_eq!(
+        runtime.health().await,
+        vec![
+            ("InMemoryRepository".to_string(), AdapterHealth::Healthy),
+            (
+                "Database".to_string(),
+                AdapterHealth::Degraded {
+                    reason: "read only replica".to_string()
+                }
+            ),
+            ("Cache".to_string(), AdapterHealth::Healthy),
+        ]
+    );
+
+    runtime
+        .add_adapter(Arc::new(Probe::new("Search", &["Index"], &log)))
+        .await
+        .unwrap();
+    assert!(matches!(
+        runtime.start().await,
+        Err(RuntimeError::MissingDependency { dependency, .. }) if dependency == "Index"
+    ));
+
+    // teardown in reverse order, going on after a failure
+    log.lock().unwrap().clear();
+    let Err(RuntimeError::ShutdownFailed { failures }) = runtime.shutdown().await else {
+        panic!("the database shutdown should fail");
+    };
+    assert_eq!(
+        failures,
+        [(
+            "Database".to_string(),
+            "connection already closed".to_string()
+        )]
+    );
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["shutdown Cache", "shutdown Database"]
+    );
+    assert!(runtime.health().await.is_empty());
+}
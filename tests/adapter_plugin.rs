@@ -0,0 +1,139 @@
+use std::{collections::HashMap, fs, path::PathBuf, sync::Arc};
+
+use fototra::{
+    adapters::repository::in_memory::InMemoryRepository,
+    configuration::{
+        Configuration, error::ConfigurationError, loader::ConfigurationLoader,
+        schema::ConfigurationViolation,
+    },
+    model::adapter_health::AdapterHealth,
+    runtime::{Runtime, error::RuntimeError},
+};
+
+use crate::common::write_files;
+
+/// Path of a library built next to the tests, the plugin being built as a dev-dependency
+fn library_path(name: &str) -> PathBuf {
+    let mut path = std::env::current_exe().unwrap();
+    path.set_file_name(format!(
+        "{}{name}{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    ));
+    path
+}
+
+pub async fn test_adapter_plugin() {
+    let plugin = library_path("fototra_test_plugin");
+    let runtime = Runtime::get_instance();
+
+    // the adapter of the plugin starts like any other one, its schema completing the
+    // configuration with the default
+    runtime.add_plugin(&plugin).await.unwrap();
+    runtime.start().await.unwrap();
+    assert_eq!(
+        runtime
+            .get::<Configuration>()
+            .await
+            .unwrap()
+            .get_str("greeting.message")
+            .unwrap(),
+        "Hello from a plugin"
+    );
+    assert_eq!(
+        *runtime.get_named::<String>("greeting").await.unwrap(),
+        "Hello from a plugin"
+    );
+    assert!(runtime.health().await.contains(&(
+        "GreetingPlugin".to_string(),
+        AdapterHealth::Degraded {
+            reason: "loaded from a plugin".to_string()
+        }
+    )));
+    assert!(matches!(
+        runtime.add_plugin(&plugin).await,
+        Err(RuntimeError::DuplicateAdapter { name }) if name == "GreetingPlugin"
+    ));
+
+    // a library which is not a plugin is refused
+    assert!(matches!(
+        runtime.add_plugin(&library_path("fototra")).await,
+        Err(RuntimeError::PluginLoadFailed { .. })
+    ));
+    assert!(matches!(
+        runtime.add_plugin(&plugin.with_file_name("missing")).await,
+        Err(RuntimeError::PluginLoadFailed { .. })
+    ));
+
+    // plugins listed in the configuration, the default of the schema not overriding it
+    let directory = write_files(&[
+        (
+            "config.toml",
+            &format!(
+                "[plugins]\npaths = [{:?}]\n\n[greeting]\nmessage = \"Salama\"\n",
+                plugin.display().to_string()
+            ),
+        ),
+        ("config.misconfigured.toml", "[greeting]\nmessage = 42\n"),
+    ]);
+    let loader = ConfigurationLoader::new()
+        .with_path(&directory.join("config.toml"))
+        .with_env_vars([]);
+    let configured = Runtime::new();
+    configured
+        .add_adapter(Arc::new(InMemoryRepository::new()))
+        .await
+        .unwrap();
+    configured.init_with(&loader).await.unwrap();
+    configured.start().await.unwrap();
+    assert_eq!(
+        *configured.get_named::<String>("greeting").await.unwrap(),
+        "Salama"
+    );
+    assert_eq!(
+        configured
+            .get::<Configuration>()
+            .await
+            .unwrap()
+            .get_str("greeting.message")
+            .unwrap(),
+        "Salama"
+    );
+    configured
+        .register(Configuration::Map(HashMap::from([(
+            "plugins".to_string(),
+            Configuration::Map(HashMap::from([(
+                "paths".to_string(),
+                Configuration::String(plugin.display().to_string()),
+            )])),
+        )])))
+        .await
+        .unwrap();
+    assert!(matches!(
+        configured.load_plugins().await,
+        Err(RuntimeError::PluginLoadFailed { path, .. }) if path == "plugins.paths"
+    ));
+
+    // the schema of the plugin is checked before any adapter is loaded
+    let misconfigured = Runtime::new();
+    misconfigured
+        .add_adapter(Arc::new(InMemoryRepository::new()))
+        .await
+        .unwrap();
+    misconfigured
+        .init_with(&loader.with_profile("misconfigured"))
+        .await
+        .unwrap();
+    let Err(RuntimeError::Configuration(ConfigurationError::Invalid { violations })) =
+        misconfigured.start().await
+    else {
+        panic!("the greeting must be a string");
+    };
+    assert!(matches!(
+        violations.as_slice(),
+        [ConfigurationViolation::InvalidType { key, .. }] if key == "greeting.message"
+    ));
+    assert!(misconfigured.health().await.is_empty());
+
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,132 @@
+use std::{fs, sync::Arc};
+
+use fototra::{
+    adapters::audit::{in_memory::InMemoryAuditSink, json_lines::JsonLinesAuditSink},
+    audit::audit_sink::AuditSink,
+    dtos::{
+        user::{user_add_request::UserAddRequest, user_delete_request::UserDeleteRequest},
+        user_password::user_password_add_request::UserPasswordAddRequest,
+    },
+    model::{
+        audit_event::{AuditEvent, AuditOutcome, REDACTED},
+        user::{DEFAULT_ADMIN_USER, name::Name},
+    },
+    runtime::Runtime,
+    service::{user::UserService, user_password::UserPasswordService},
+};
+
+use crate::common::UserToken;
+
+pub async fn test_audit() {
+    let audit_sink = InMemoryAuditSink::new();
+    Runtime::get_instance()
+        .register(AuditSink::new(Arc::new(audit_sink.clone())))
+        .await
+        .unwrap();
+    let admin = UserToken {
+        user_id: *DEFAULT_ADMIN_USER.get_id(),
+    };
+
+    let user = UserService::create(
+        &admin,
+        &UserAddRequest::new(&Name::new("Fara").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    UserPasswordService::create(
+        &admin,
+        &UserPasswordAddRequest::new(user.get_id(), "secret123"),
+    )
+    .await
+    .unwrap();
+
+    // a user without grant is denied
+    let user_token = UserToken {
+        user_id: *user.get_id(),
+    };
+    UserService::delete(&user_token, &UserDeleteRequest::new(user.get_id()))
+        .await
+        .unwrap_err();
+
+    UserService::delete(&admin, &UserDeleteRequest::new(user.get_id()))
+        .await
+        .unwrap();
+
+    let events = audit_sink.get_events().await;
+    assert_eq!(
+        events.iter().map(|e| e.get_action()).collect::<Vec<_>>(),
+        vec![
+            "user:create",
+            "user_password:create",
+            "user:delete",
+            "user:delete"
+        ]
+    );
+    assert!(
+        events
+            .iter()
+            .all(|e| e.get_target().unwrap().get_id() == user.get_id().to_string())
+    );
+
+    // creation
+    assert_eq!(events[0].get_actor(), Some(DEFAULT_ADMIN_USER.get_id()));
+    assert_eq!(events[0].get_outcome(), &AuditOutcome::Success);
+    assert!(events[0].get_before().is_none());
+    assert_eq!(
+        events[0].get_after().unwrap()["firstname"],
+        serde_json::json!("Fara")
+    );
+
+    // secrets are redacted
+    assert_eq!(
+        events[1].get_after().unwrap()["password"],
+        serde_json::json!(REDACTED)
+    );
+    assert!(
+        !serde_json::to_string(&events[1])
+            .unwrap()
+            .contains("secret123")
+    );
+
+    // denied attempt
+    assert_eq!(events[2].get_actor(), Some(user.get_id()));
+    assert!(matches!(
+        events[2].get_outcome(),
+        AuditOutcome::Denied { code, .. } if code == "security.not_authorized.no_grant"
+    ));
+
+    // deletion
+    assert_eq!(events[3].get_outcome(), &AuditOutcome::Success);
+    assert_eq!(
+        events[3].get_before().unwrap()["id"],
+        serde_json::json!(user.get_id())
+    );
+    assert!(events[3].get_after().is_none());
+
+    // append-only json lines file
+    let path = std::env::temp_dir().join(format!("fototra-audit-{}.jsonl", user.get_id()));
+    Runtime::get_instance()
+        .register(AuditSink::new(Arc::new(JsonLinesAuditSink::new(&path))))
+        .await
+        .unwrap();
+    for _ in 0..2 {
+        UserService::find_one(&admin, user.get_id())
+            .await
+            .unwrap_err();
+    }
+    let content = fs::read_to_string(&path).unwrap();
+    let lines: Vec<AuditEvent> = content
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().all(|e| e.get_action() == "user:find_one"
+        && matches!(e.get_outcome(), AuditOutcome::Failed { code, .. } if code == "user.not_exists")));
+    fs::remove_file(&path).unwrap();
+
+    // stop writing into the removed file
+    Runtime::get_instance()
+        .register(AuditSink::new(Arc::new(InMemoryAuditSink::new())))
+        .await
+        .unwrap();
+}
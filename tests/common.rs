@@ -0,0 +1,39 @@
+use std::{env, fs, path::PathBuf, pin::Pin, sync::Arc};
+
+use fototra::{
+    model::user::UserID,
+    security::{error::SecurityError, user_authorizer::UserAuthorizer},
+    traits::{authentication_trait::AuthenticationTrait, authorization_trait::AuthorizationTrait},
+};
+use uuid::Uuid;
+
+/// Token of a user already authenticated, authorized from its stored grants
+pub struct UserToken {
+    pub user_id: UserID,
+}
+
+impl AuthenticationTrait for UserToken {
+    fn authenticate<'a>(
+        &'a self,
+    ) -> Pin<
+        Box<
+            dyn Future<Output = Result<Arc<dyn AuthorizationTrait + 'static>, SecurityError>>
+                + Send
+                + 'a,
+        >,
+    > {
+        Box::pin(async {
+            Ok(Arc::new(UserAuthorizer::new(&self.user_id)) as Arc<dyn AuthorizationTrait>)
+        })
+    }
+}
+
+/// Directory holding the given files, removed by the caller
+pub fn write_files(files: &[(&str, &str)]) -> PathBuf {
+    let directory = env::temp_dir().join(format!("fototra-configuration-{}", Uuid::new_v4()));
+    fs::create_dir_all(&directory).unwrap();
+    for (name, content) in files {
+        fs::write(directory.join(name), content).unwrap();
+    }
+    directory
+}
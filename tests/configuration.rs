@@ -0,0 +1,1423 @@
+use std::{collections::HashMap, env, fs, pin::Pin, process::Command, sync::Arc, time::Duration};
+
+use crate::common::write_files;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Utc};
+use fototra::{
+    configuration::{
+        Configuration,
+        diff::ConfigurationDifference,
+        encryption::{ENCRYPTED_HEADER, SecretKey},
+        error::ConfigurationError,
+        layer::{ConfigurationLayer, ConfigurationOrigins},
+        loader::ConfigurationLoader,
+        merge::MergeStrategy,
+        schema::{
+            ConfigurationKey, ConfigurationSchema, ConfigurationType, ConfigurationViolation,
+        },
+        secret::Secret,
+        watch::ConfigurationChange,
+    },
+    runtime::{Runtime, error::RuntimeError},
+    traits::{
+        adapter_loader_trait::AdapterLoaderTrait,
+        configuration_subscriber_trait::ConfigurationSubscriberTrait,
+        initialize_trait::InitializeTrait,
+    },
+};
+use serde::Deserialize;
+use tokio::{
+    sync::mpsc::{UnboundedSender, unbounded_channel},
+    time::timeout,
+};
+
+fn lookup<'a>(configuration: &'a Configuration, path: &str) -> Option<&'a Configuration> {
+    path.split('.')
+        .try_fold(configuration, |configuration, key| configuration.get(key))
+}
+
+fn as_int(configuration: &Configuration, path: &str) -> Option<i64> {
+    match lookup(configuration, path) {
+        Some(Configuration::Int(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+fn as_str<'a>(configuration: &'a Configuration, path: &str) -> Option<&'a str> {
+    match lookup(configuration, path) {
+        Some(Configuration::String(value)) => Some(value),
+        _ => None,
+    }
+}
+
+pub async fn test_configuration_layers() {
+    let directory = write_files(&[
+        (
+            "config.toml",
+            "[conf1]\ndummy = 1\nname = \"base\"\n\n[conf1.array1]\nfoo = \"bar\"\n",
+        ),
+        ("config.test.toml", "[conf1]\nname = \"test\"\n"),
+        (
+            "secret.toml",
+            "[conf1]\ndummy = 2\n\n[conf1.array1]\nsecret = \"hahaha\"\n",
+        ),
+        ("secret.test.toml", "[conf1.array1]\nsecret = \"hihihi\"\n"),
+    ]);
+    let config = directory.join("config.toml");
+    let defaults = Configuration::Map(HashMap::from([(
+        "conf2".to_string(),
+        Configuration::Map(HashMap::from([(
+            "timeout".to_string(),
+            Configuration::Int(30),
+        )])),
+    )]));
+
+    // every layer, from the defaults to the environment
+    let (configuration, origins) = ConfigurationLoader::new()
+        .with_defaults(defaults.clone())
+        .with_path(&config)
+        .with_profile("test")
+        .with_env_vars([
+            ("FOTOTRA__CONF1__DUMMY".to_string(), "3".to_string()),
+            ("FOTOTRA__CONF2__MODE".to_string(), "fast".to_string()),
+            ("FOTOTRA____IGNORED".to_string(), "1".to_string()),
+            ("OTHER__CONF1__DUMMY".to_string(), "4".to_string()),
+        ])
+        .load()
+        .unwrap();
+    // the variable overrides a secret, so it stays one
+    assert_eq!(
+        configuration.get_secret("conf1.dummy").unwrap().expose(),
+        "3"
+    );
+    assert_eq!(as_str(&configuration, "conf1.name"), Some("test"));
+    assert_eq!(as_str(&configuration, "conf1.array1.foo"), Some("bar"));
+    assert_eq!(
+        configuration
+            .get_secret("conf1.array1.secret")
+            .unwrap()
+            .expose(),
+        "hihihi"
+    );
+    assert_eq!(as_int(&configuration, "conf2.timeout"), Some(30));
+    assert_eq!(as_str(&configuration, "conf2.mode"), Some("fast"));
+
+    assert_eq!(
+        origins.get("conf1.dummy"),
+        Some(&ConfigurationLayer::Environment {
+            variable: "FOTOTRA__CONF1__DUMMY".to_string()
+        })
+    );
+    assert_eq!(
+        origins.get("conf1.name"),
+        Some(&ConfigurationLayer::Profile {
+            profile: "test".to_string(),
+            path: directory.join("config.test.toml")
+        })
+    );
+    assert_eq!(
+        origins.get("conf1.array1.foo"),
+        Some(&ConfigurationLayer::File {
+            path: config.clone()
+        })
+    );
+    assert_eq!(
+        origins.get("conf1.array1.secret"),
+        Some(&ConfigurationLayer::Secret {
+            path: directory.join("secret.test.toml")
+        })
+    );
+    assert_eq!(
+        origins.get("conf2.timeout"),
+        Some(&ConfigurationLayer::Defaults)
+    );
+    assert_eq!(origins.get("conf3"), None);
+
+    // the path and the profile can come from the environment, without a profile
+    // only the base files are read
+    let (configuration, origins) = ConfigurationLoader::new()
+        .with_env_vars([("FOTOTRA_CONFIG".to_string(), config.display().to_string())])
+        .load()
+        .unwrap();
+    assert_eq!(
+        configuration.get_secret("conf1.dummy").unwrap().expose(),
+        "2"
+    );
+    assert_eq!(as_str(&configuration, "conf1.name"), Some("base"));
+    assert_eq!(
+        configuration
+            .get_secret("conf1.array1.secret")
+            .unwrap()
+            .expose(),
+        "hahaha"
+    );
+    assert_eq!(
+        origins.get("conf1.dummy"),
+        Some(&ConfigurationLayer::Secret {
+            path: directory.join("secret.toml")
+        })
+    );
+
+    let (configuration, _) = ConfigurationLoader::new()
+        .with_env_prefix("APP")
+        .with_env_vars([
+            ("APP_CONFIG".to_string(), config.display().to_string()),
+            ("APP_PROFILE".to_string(), "test".to_string()),
+            ("APP__CONF1__ENABLED".to_string(), "true".to_string()),
+            ("FOTOTRA__CONF1__DUMMY".to_string(), "3".to_string()),
+        ])
+        .load()
+        .unwrap();
+    assert_eq!(
+        configuration.get_secret("conf1.dummy").unwrap().expose(),
+        "2"
+    );
+    assert_eq!(as_str(&configuration, "conf1.name"), Some("test"));
+    assert!(matches!(
+        lookup(&configuration, "conf1.enabled"),
+        Some(Configuration::Bool(true))
+    ));
+
+    // the runtime registers the configuration with its origins
+    let runtime = Runtime::new();
+    runtime
+        .init_with(
+            &ConfigurationLoader::new()
+                .with_path(&config)
+                .with_env_vars([]),
+        )
+        .await
+        .unwrap();
+    let configuration = runtime.get::<Configuration>().await.unwrap();
+    assert_eq!(
+        configuration.get_secret("conf1.dummy").unwrap().expose(),
+        "2"
+    );
+    let origins = runtime.get::<ConfigurationOrigins>().await.unwrap();
+    assert_eq!(
+        origins.get("conf1.array1.foo"),
+        Some(&ConfigurationLayer::File { path: config })
+    );
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+pub async fn test_configuration_errors() {
+    let directory = write_files(&[
+        ("config.toml", "[conf1]\ndummy = 1\n"),
+        (
+            "config.broken.toml",
+            "[conf1]\ndummy = 1\nname = \"unterminated\n",
+        ),
+        ("config.local.toml", "[conf1]\nwhen = 2024-01-01T10:00:00\n"),
+    ]);
+    let config = directory.join("config.toml");
+    let missing = directory.join("missing.toml");
+
+    // a missing base file is an error unless it is optional
+    let error = ConfigurationLoader::new()
+        .with_path(&missing)
+        .with_env_vars([])
+        .load()
+        .unwrap_err();
+    assert!(matches!(&error, ConfigurationError::MissingFile { path, .. } if *path == missing));
+    let (_, origins) = ConfigurationLoader::new()
+        .with_path(&missing)
+        .with_required(false)
+        .with_profile("absent")
+        .with_env_vars([("FOTOTRA__CONF1__DUMMY".to_string(), "5".to_string())])
+        .load()
+        .unwrap();
+    let files: Vec<_> = origins
+        .get_files()
+        .iter()
+        .map(|file| {
+            (
+                file.get_path().to_path_buf(),
+                file.is_required(),
+                file.is_found(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        files,
+        vec![
+            (missing.clone(), false, false),
+            (directory.join("config.absent.toml"), false, false),
+            (directory.join("secret.toml"), false, false),
+            (directory.join("secret.absent.toml"), false, false),
+        ]
+    );
+
+    // parse errors give the file, line and column
+    let error = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_profile("broken")
+        .with_env_vars([])
+        .load()
+        .unwrap_err();
+    match error {
+        ConfigurationError::Parse {
+            path, line, column, ..
+        } => {
+            assert_eq!(path, directory.join("config.broken.toml"));
+            assert_eq!((line, column), (3, 21));
+        }
+        e => panic!("unexpected error {e}"),
+    }
+
+    // a datetime without offset is kept as a local datetime
+    let (configuration, _) = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_profile("local")
+        .with_env_vars([])
+        .load()
+        .unwrap();
+    assert!(matches!(
+        configuration.get_path("conf1.when"),
+        Some(Configuration::LocalDateTime(_))
+    ));
+
+    // the runtime stays uninitialized after a failure, so init can be retried
+    let runtime = Runtime::new();
+    let error = runtime
+        .init_with(
+            &ConfigurationLoader::new()
+                .with_path(&missing)
+                .with_env_vars([]),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        RuntimeError::Configuration(ConfigurationError::MissingFile { .. })
+    ));
+    assert!(runtime.get::<Configuration>().await.is_none());
+    runtime
+        .init_with(
+            &ConfigurationLoader::new()
+                .with_path(&config)
+                .with_env_vars([]),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        as_int(
+            &runtime.get::<Configuration>().await.unwrap(),
+            "conf1.dummy"
+        ),
+        Some(1)
+    );
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct ServerConfiguration {
+    host: String,
+    port: u16,
+    ratio: f32,
+    secure: bool,
+    started: DateTime<Utc>,
+    tags: Vec<String>,
+    mode: Mode,
+    limits: Option<Limits>,
+    proxy: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+    Fast,
+    Slow { delay: u32 },
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Limits {
+    connections: u32,
+}
+
+pub async fn test_configuration_typed() {
+    let directory = write_files(&[(
+        "config.toml",
+        r#"
+[conf1.array1]
+foo = "bar"
+
+[server]
+host = "localhost"
+port = 8080
+ratio = 1
+secure = true
+started = 2024-01-01T10:00:00Z
+tags = ["a", "b"]
+mode = "fast"
+
+[server.limits]
+connections = 10
+
+[broken.limits]
+connections = -1
+
+[broken.numbers]
+values = [1, "two"]
+
+[broken.mode]
+mode = { slow = { delay = "soon" } }
+"#,
+    )]);
+    let (configuration, _) = ConfigurationLoader::new()
+        .with_path(&directory.join("config.toml"))
+        .with_env_vars([])
+        .load()
+        .unwrap();
+
+    // dotted paths reach nested tables and array items
+    assert_eq!(configuration.get_str("conf1.array1.foo").unwrap(), "bar");
+    assert_eq!(configuration.get_str("server.tags.1").unwrap(), "b");
+    assert!(configuration.get_path("server.tags.2").is_none());
+    assert!(configuration.get_path("conf1.array1.foo.bar").is_none());
+    assert_eq!(configuration.get_int("server.port").unwrap(), 8080);
+    assert_eq!(configuration.get_float("server.ratio").unwrap(), 1.0);
+    assert!(configuration.get_bool("server.secure").unwrap());
+    assert_eq!(
+        configuration.get_datetime("server.started").unwrap(),
+        "2024-01-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap()
+    );
+    assert!(matches!(
+        configuration.get_int("server.host"),
+        Err(ConfigurationError::InvalidType { key, expected: "integer", found: "string" })
+            if key == "server.host"
+    ));
+    assert!(matches!(
+        configuration.get_str("server.missing"),
+        Err(ConfigurationError::MissingKey { key }) if key == "server.missing"
+    ));
+
+    // subtrees deserialize into the structures of their owner
+    assert_eq!(
+        configuration
+            .get_as::<ServerConfiguration>("server")
+            .unwrap(),
+        ServerConfiguration {
+            host: "localhost".to_string(),
+            port: 8080,
+            ratio: 1.0,
+            secure: true,
+            started: "2024-01-01T10:00:00Z".parse().unwrap(),
+            tags: vec!["a".to_string(), "b".to_string()],
+            mode: Mode::Fast,
+            limits: Some(Limits { connections: 10 }),
+            proxy: None,
+        }
+    );
+    assert_eq!(
+        configuration.get_as::<Limits>("server.limits").unwrap(),
+        Limits { connections: 10 }
+    );
+    assert_eq!(
+        configuration.get_as::<Vec<String>>("server.tags").unwrap(),
+        vec!["a".to_string(), "b".to_string()]
+    );
+
+    // errors name the offending value
+    let key_of = |error| match error {
+        ConfigurationError::Deserialize { key, .. } => key,
+        e => panic!("unexpected error {e}"),
+    };
+    assert_eq!(
+        key_of(configuration.get_as::<Limits>("broken.limits").unwrap_err()),
+        "broken.limits.connections"
+    );
+    assert_eq!(
+        key_of(
+            configuration
+                .get_as::<Vec<u32>>("broken.numbers.values")
+                .unwrap_err()
+        ),
+        "broken.numbers.values.1"
+    );
+    assert_eq!(
+        key_of(
+            configuration
+                .get_as::<Mode>("broken.mode.mode")
+                .unwrap_err()
+        ),
+        "broken.mode.mode.slow.delay"
+    );
+    assert!(matches!(
+        configuration.get_as::<Limits>("conf1.array1"),
+        Err(ConfigurationError::Deserialize { key, message })
+            if key == "conf1.array1" && message.contains("connections")
+    ));
+    assert!(matches!(
+        configuration.get_as::<Limits>("nowhere"),
+        Err(ConfigurationError::MissingKey { key }) if key == "nowhere"
+    ));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+fn server_schema() -> ConfigurationSchema {
+    ConfigurationSchema::new("server")
+        .with_key(ConfigurationKey::new("host", ConfigurationType::String).required())
+        .with_key(
+            ConfigurationKey::new("port", ConfigurationType::Int)
+                .with_default(Configuration::Int(8080))
+                .with_min(1.0)
+                .with_max(65535.0),
+        )
+        .with_key(ConfigurationKey::new("ratio", ConfigurationType::Float).with_max(1.0))
+        .with_key(ConfigurationKey::new(
+            "tls.enabled",
+            ConfigurationType::Bool,
+        ))
+        .with_key(ConfigurationKey::new("labels", ConfigurationType::Table))
+        .with_key(
+            ConfigurationKey::new("password", ConfigurationType::String)
+                .required()
+                .secret(),
+        )
+}
+
+/// Adapter expecting a secret token, checked by `start`
+#[derive(Debug)]
+struct ApiAdapter;
+
+impl InitializeTrait for ApiAdapter {}
+
+impl AdapterLoaderTrait for ApiAdapter {
+    fn name(&self) -> &str {
+        "Api"
+    }
+
+    fn configuration_schema(&self) -> Option<ConfigurationSchema> {
+        Some(
+            ConfigurationSchema::new("api")
+                .with_key(
+                    ConfigurationKey::new("token", ConfigurationType::String)
+                        .required()
+                        .secret(),
+                )
+                .with_key(
+                    ConfigurationKey::new("timeout", ConfigurationType::Int)
+                        .with_default(Configuration::Int(30)),
+                ),
+        )
+    }
+
+    fn load<'a>(&'a self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+pub async fn test_configuration_schema() {
+    let directory = write_files(&[
+        (
+            "config.toml",
+            "[server]\nhost = \"localhost\"\nratio = 1\n\n[server.labels]\nteam = \"core\"\n\n[other]\nfree = true\n",
+        ),
+        (
+            "secret.toml",
+            "[server]\npassword = \"hunter2\"\n\n[api]\ntoken = \"t0k3n\"\n",
+        ),
+        ("config.leaky.toml", "[api]\ntoken = \"t0k3n\"\n"),
+        (
+            "config.broken.toml",
+            "[server]\nhost = 1\nport = 0\nratio = 1.5\nprot = 80\npassword = \"hunter2\"\n\n[server.tls]\nenabled = true\ncert = \"x\"\n",
+        ),
+    ]);
+    let config = directory.join("config.toml");
+
+    // defaults fill the keys no layer sets
+    let (configuration, origins) = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_schema(server_schema())
+        .with_env_vars([])
+        .load()
+        .unwrap();
+    assert_eq!(configuration.get_int("server.port").unwrap(), 8080);
+    assert_eq!(
+        origins.get("server.port"),
+        Some(&ConfigurationLayer::Defaults)
+    );
+    assert_eq!(configuration.get_float("server.ratio").unwrap(), 1.0);
+
+    // every violation is reported at once
+    let error = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_profile("broken")
+        .with_schema(server_schema())
+        .with_env_vars([])
+        .load()
+        .unwrap_err();
+    let ConfigurationError::Invalid { violations } = error else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!(
+        violations,
+        vec![
+            ConfigurationViolation::InvalidType {
+                key: "server.host".to_string(),
+                expected: ConfigurationType::String,
+                found: "integer",
+            },
+            ConfigurationViolation::OutOfRange {
+                key: "server.port".to_string(),
+                min: Some(1.0),
+                max: Some(65535.0),
+            },
+            ConfigurationViolation::OutOfRange {
+                key: "server.ratio".to_string(),
+                min: None,
+                max: Some(1.0),
+            },
+            ConfigurationViolation::SecretInPlainFile {
+                key: "server.password".to_string(),
+                path: directory.join("config.broken.toml"),
+            },
+            ConfigurationViolation::UnknownKey {
+                key: "server.prot".to_string(),
+            },
+            ConfigurationViolation::UnknownKey {
+                key: "server.tls.cert".to_string(),
+            },
+        ]
+    );
+
+    // the runtime validates against the schemas declared before init
+    let runtime = Runtime::new();
+    runtime
+        .add_configuration_schema(server_schema())
+        .await
+        .unwrap();
+    runtime
+        .add_configuration_schema(
+            ConfigurationSchema::new("cache")
+                .with_key(ConfigurationKey::new("size", ConfigurationType::Int).required()),
+        )
+        .await
+        .unwrap();
+    let error = runtime
+        .init_with(
+            &ConfigurationLoader::new()
+                .with_path(&config)
+                .with_env_vars([]),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        RuntimeError::Configuration(ConfigurationError::Invalid { violations })
+            if violations == vec![ConfigurationViolation::Missing { key: "cache.size".to_string() }]
+    ));
+    runtime
+        .init_with(
+            &ConfigurationLoader::new()
+                .with_path(&config)
+                .with_env_vars([("FOTOTRA__CACHE__SIZE".to_string(), "10".to_string())]),
+        )
+        .await
+        .unwrap();
+    let configuration = runtime.get::<Configuration>().await.unwrap();
+    assert_eq!(configuration.get_int("server.port").unwrap(), 8080);
+    assert!(matches!(
+        runtime
+            .add_configuration_schema(ConfigurationSchema::new("late"))
+            .await,
+        Err(RuntimeError::AlreadyInitialized)
+    ));
+
+    // the schema of an adapter is checked at start against every layer, as a reload does
+    let runtime = Runtime::new();
+    runtime
+        .init_with(
+            &ConfigurationLoader::new()
+                .with_path(&config)
+                .with_env_vars([]),
+        )
+        .await
+        .unwrap();
+    runtime.add_adapter(Arc::new(ApiAdapter)).await.unwrap();
+    runtime.start().await.unwrap();
+    let configuration = runtime.get::<Configuration>().await.unwrap();
+    assert_eq!(configuration.get_int("api.timeout").unwrap(), 30);
+    assert_eq!(
+        configuration.get_secret("api.token").unwrap().expose(),
+        "t0k3n"
+    );
+    assert!(runtime.reload_configuration().await.unwrap().is_empty());
+    let runtime = Runtime::new();
+    runtime
+        .init_with(
+            &ConfigurationLoader::new()
+                .with_path(&config)
+                .with_profile("leaky")
+                .with_env_vars([]),
+        )
+        .await
+        .unwrap();
+    runtime.add_adapter(Arc::new(ApiAdapter)).await.unwrap();
+    let error = runtime.start().await.unwrap_err();
+    assert!(matches!(
+        error,
+        RuntimeError::Configuration(ConfigurationError::Invalid { violations })
+            if violations == vec![ConfigurationViolation::SecretInPlainFile {
+                key: "api.token".to_string(),
+                path: directory.join("config.leaky.toml"),
+            }]
+    ));
+    assert!(runtime.health().await.is_empty());
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+struct ChangeRecorder {
+    changes: UnboundedSender<ConfigurationChange>,
+}
+
+impl ConfigurationSubscriberTrait for ChangeRecorder {
+    fn name(&self) -> &str {
+        "change_recorder"
+    }
+
+    fn handle<'a>(
+        &'a self,
+        change: &'a ConfigurationChange,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            self.changes.send(change.clone())?;
+            Ok(())
+        })
+    }
+}
+
+fn level(change: &ConfigurationChange) -> (Option<&str>, Option<&str>) {
+    fn as_str(value: Option<&Configuration>) -> Option<&str> {
+        match value {
+            Some(Configuration::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+    (as_str(change.get_old()), as_str(change.get_new()))
+}
+
+pub async fn test_configuration_reload() {
+    let directory = write_files(&[
+        (
+            "config.toml",
+            "[log]\nlevel = \"info\"\n\n[server]\nport = 80\n",
+        ),
+        ("secret.toml", "[server]\npassword = \"hunter2\"\n"),
+    ]);
+    let config = directory.join("config.toml");
+    let runtime = Runtime::new();
+    runtime
+        .init_with(
+            &ConfigurationLoader::new()
+                .with_path(&config)
+                .with_env_vars([("FOTOTRA__SERVER__RATIO".to_string(), "nan".to_string())]),
+        )
+        .await
+        .unwrap();
+    let (sender, mut changes) = unbounded_channel();
+    for path in ["log.level", "server"] {
+        runtime
+            .subscribe_configuration(
+                path,
+                Arc::new(ChangeRecorder {
+                    changes: sender.clone(),
+                }),
+            )
+            .await;
+    }
+
+    // only the subscribers of a changed path are notified, a NaN being the same after
+    // each reload
+    assert!(runtime.reload_configuration().await.unwrap().is_empty());
+    assert!(changes.try_recv().is_err());
+    fs::write(&config, "[log]\nlevel = \"debug\"\n\n[server]\nport = 80\n").unwrap();
+    let differences = runtime.reload_configuration().await.unwrap();
+    assert_eq!(
+        differences,
+        vec![ConfigurationDifference::Changed {
+            path: "log.level".to_string(),
+            old: Configuration::String("info".to_string()),
+            new: Configuration::String("debug".to_string()),
+        }]
+    );
+    let change = changes.try_recv().unwrap();
+    assert_eq!(change.get_path(), "log.level");
+    assert_eq!(level(&change), (Some("info"), Some("debug")));
+    assert!(changes.try_recv().is_err());
+    let configuration = runtime.get::<Configuration>().await.unwrap();
+    assert_eq!(configuration.get_str("log.level").unwrap(), "debug");
+
+    // an invalid file is rejected and the previous configuration kept
+    fs::write(&config, "[log]\nlevel = \"warn\n").unwrap();
+    assert!(matches!(
+        runtime.reload_configuration().await,
+        Err(RuntimeError::Configuration(
+            ConfigurationError::Parse { .. }
+        ))
+    ));
+    let configuration = runtime.get::<Configuration>().await.unwrap();
+    assert_eq!(configuration.get_str("log.level").unwrap(), "debug");
+    assert!(changes.try_recv().is_err());
+
+    // the watcher reloads when a file changes
+    runtime
+        .watch_configuration(Duration::from_millis(10))
+        .await
+        .unwrap();
+    fs::write(&config, "[log]\nlevel = \"trace\"\n\n[server]\nport = 80\n").unwrap();
+    let change = timeout(Duration::from_secs(5), changes.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(level(&change), (Some("debug"), Some("trace")));
+    fs::write(
+        directory.join("secret.toml"),
+        "[server]\npassword = \"correct horse\"\n",
+    )
+    .unwrap();
+    let change = timeout(Duration::from_secs(5), changes.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(change.get_path(), "server");
+    assert!(matches!(
+        change.get_new().and_then(|server| server.get_path("password")),
+        Some(Configuration::Secret(password)) if password.expose() == "correct horse"
+    ));
+    let origins = runtime.get::<ConfigurationOrigins>().await.unwrap();
+    assert_eq!(
+        origins.get("server.password"),
+        Some(&ConfigurationLayer::Secret {
+            path: directory.join("secret.toml")
+        })
+    );
+
+    runtime.shutdown().await.unwrap();
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[derive(Debug, Deserialize)]
+struct DatabaseConfiguration {
+    port: u16,
+    tls: bool,
+    password: Secret,
+}
+
+pub async fn test_configuration_secrets() {
+    let directory = write_files(&[
+        ("config.toml", "[db]\nhost = \"localhost\"\n"),
+        (
+            "secret.toml",
+            "[db]\nport = 5432\ntls = true\npassword = \"from-file\"\ntoken = { file = \"token.txt\" }\napi = { env = \"API_KEY\" }\n",
+        ),
+        ("token.txt", "from-token-file\n"),
+        (
+            "secret.missing.toml",
+            "[db]\ncertificate = { file = \"absent.pem\" }\n",
+        ),
+        ("secret.unset.toml", "[db]\napi = { env = \"UNSET_KEY\" }\n"),
+        (
+            "secret.pool.toml",
+            "[pool]\nport = 5432\nsize = 80\nenabled = true\nsince = 2024-01-02\nname = 12\n",
+        ),
+        ("secret.text.toml", "[pool]\nport = \"five\"\n"),
+    ]);
+    let config = directory.join("config.toml");
+    let vars = || {
+        [
+            ("API_KEY".to_string(), "from-variable".to_string()),
+            (
+                "FOTOTRA__DB__PASSWORD".to_string(),
+                "from-environment".to_string(),
+            ),
+            ("FOTOTRA__SERVICE__KEY".to_string(), "declared".to_string()),
+        ]
+    };
+    let (configuration, origins) = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_schema(
+            ConfigurationSchema::new("service").with_key(
+                ConfigurationKey::new("key", ConfigurationType::String)
+                    .required()
+                    .secret(),
+            ),
+        )
+        .with_env_vars(vars())
+        .load()
+        .unwrap();
+
+    // secret files, indirections and declared secrets give secrets
+    assert_eq!(
+        configuration.get_secret("db.token").unwrap().expose(),
+        "from-token-file"
+    );
+    assert_eq!(
+        configuration.get_secret("db.api").unwrap().expose(),
+        "from-variable"
+    );
+    assert_eq!(
+        configuration.get_secret("service.key").unwrap().expose(),
+        "declared"
+    );
+    // so do the numbers and booleans of a secret file, holding their text
+    assert_eq!(
+        configuration.get_secret("db.port").unwrap().expose(),
+        "5432"
+    );
+    assert_eq!(configuration.get_secret("db.tls").unwrap().expose(), "true");
+    assert_eq!(configuration.get_str("db.host").unwrap(), "localhost");
+    assert!(
+        origins
+            .get_files()
+            .iter()
+            .any(|file| file.get_path() == directory.join("token.txt") && file.is_found())
+    );
+
+    // a plain value overriding a secret stays a secret
+    assert_eq!(
+        configuration.get_secret("db.password").unwrap().expose(),
+        "from-environment"
+    );
+    assert_eq!(
+        origins.get("db.password"),
+        Some(&ConfigurationLayer::Environment {
+            variable: "FOTOTRA__DB__PASSWORD".to_string()
+        })
+    );
+
+    // secrets never show up when formatted
+    let secret = configuration.get_secret("db.token").unwrap();
+    assert_eq!(secret.to_string(), "[REDACTED]");
+    assert_eq!(format!("{secret:?}"), "Secret([REDACTED])");
+    let debug = format!("{configuration:?}");
+    for value in [
+        "from-token-file",
+        "from-variable",
+        "from-environment",
+        "declared",
+        "5432",
+        "true",
+    ] {
+        assert!(!debug.contains(value));
+    }
+    let database: DatabaseConfiguration = configuration.get_as("db").unwrap();
+    assert_eq!(database.port, 5432);
+    assert!(database.tls);
+    assert_eq!(database.password.expose(), "from-environment");
+    assert!(!format!("{database:?}").contains("from-environment"));
+
+    // an indirection that cannot be resolved fails the load
+    let error = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_profile("missing")
+        .with_env_vars(vars())
+        .load()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ConfigurationError::SecretFile { key, path, .. }
+            if key == "db.certificate" && path == directory.join("absent.pem")
+    ));
+    let error = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_profile("unset")
+        .with_env_vars(vars())
+        .load()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ConfigurationError::SecretVariable { key, variable }
+            if key == "db.api" && variable == "UNSET_KEY"
+    ));
+
+    // a schema checks the type and range of a secret against the value it holds
+    let pool_schema = || {
+        ConfigurationSchema::new("pool")
+            .with_key(
+                ConfigurationKey::new("port", ConfigurationType::Int)
+                    .required()
+                    .secret()
+                    .with_min(1.0)
+                    .with_max(65535.0),
+            )
+            .with_key(
+                ConfigurationKey::new("size", ConfigurationType::Int)
+                    .secret()
+                    .with_max(50.0),
+            )
+            .with_key(ConfigurationKey::new("enabled", ConfigurationType::Bool).secret())
+            .with_key(ConfigurationKey::new("since", ConfigurationType::LocalDate).secret())
+            .with_key(ConfigurationKey::new("name", ConfigurationType::String).secret())
+    };
+    let error = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_profile("pool")
+        .with_schema(pool_schema())
+        .with_env_vars(vars())
+        .load()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ConfigurationError::Invalid { violations }
+            if violations == vec![ConfigurationViolation::OutOfRange {
+                key: "pool.size".to_string(),
+                min: None,
+                max: Some(50.0),
+            }]
+    ));
+    let (configuration, _) = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_profile("pool")
+        .with_schema(pool_schema())
+        .with_env_vars(
+            vars()
+                .into_iter()
+                .chain([("FOTOTRA__POOL__SIZE".to_string(), "20".to_string())]),
+        )
+        .load()
+        .unwrap();
+    assert_eq!(configuration.get_as::<u16>("pool.size").unwrap(), 20);
+    assert!(configuration.get_as::<bool>("pool.enabled").unwrap());
+    assert_eq!(
+        configuration.get_secret("pool.name").unwrap().expose(),
+        "12"
+    );
+    let error = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_profile("text")
+        .with_schema(pool_schema())
+        .with_env_vars(vars())
+        .load()
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ConfigurationError::Invalid { violations }
+            if violations == vec![ConfigurationViolation::InvalidType {
+                key: "pool.port".to_string(),
+                expected: ConfigurationType::Int,
+                found: "secret",
+            }]
+    ));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+/// Run the secret CLI with the key in the environment
+fn secret_cli(key: &SecretKey, args: &[&str], editor: Option<&str>) -> (bool, String) {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_fototra-secret"));
+    command
+        .args(args)
+        .env("FOTOTRA_SECRET_KEY", key.to_base64().as_str())
+        .env_remove("FOTOTRA_SECRET_KEY_FILE")
+        .env_remove("VISUAL");
+    if let Some(editor) = editor {
+        command.env("EDITOR", editor);
+    }
+    let output = command.output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+pub async fn test_configuration_encryption() {
+    let key = SecretKey::generate();
+    let plain = "[db]\npassword = \"hunter2\"\n";
+    let directory = write_files(&[
+        ("config.toml", "[db]\nhost = \"localhost\"\n"),
+        ("secret.toml", plain),
+        ("secret.key", key.to_base64().as_str()),
+    ]);
+    let config = directory.join("config.toml");
+    let secret = directory.join("secret.toml");
+    let secret_path = secret.display().to_string();
+    let load = |vars: Vec<(String, String)>| {
+        ConfigurationLoader::new()
+            .with_path(&config)
+            .with_env_vars(vars)
+            .load()
+    };
+    let with_key = || {
+        vec![(
+            "FOTOTRA_SECRET_KEY".to_string(),
+            key.to_base64().to_string(),
+        )]
+    };
+
+    // the CLI encrypts the file in place, without the values in clear
+    assert!(secret_cli(&key, &["encrypt", &secret_path], None).0);
+    let encrypted = fs::read_to_string(&secret).unwrap();
+    assert!(encrypted.starts_with(ENCRYPTED_HEADER));
+    assert!(!encrypted.contains("hunter2"));
+    assert!(!secret_cli(&key, &["encrypt", &secret_path], None).0);
+    assert_eq!(
+        secret_cli(&key, &["decrypt", &secret_path], None),
+        (true, plain.to_string())
+    );
+
+    // the loader decrypts it with the key from a variable or a file
+    let (configuration, _) = load(with_key()).unwrap();
+    assert_eq!(
+        configuration.get_secret("db.password").unwrap().expose(),
+        "hunter2"
+    );
+    assert_eq!(configuration.get_str("db.host").unwrap(), "localhost");
+    let (configuration, _) = load(vec![(
+        "FOTOTRA_SECRET_KEY_FILE".to_string(),
+        directory.join("secret.key").display().to_string(),
+    )])
+    .unwrap();
+    assert_eq!(
+        configuration.get_secret("db.password").unwrap().expose(),
+        "hunter2"
+    );
+    let (configuration, _) = ConfigurationLoader::new()
+        .with_path(&config)
+        .with_secret_key(key.clone())
+        .with_env_vars([])
+        .load()
+        .unwrap();
+    assert!(configuration.get_secret("db.password").is_ok());
+
+    // without the right key, or once modified, the file is rejected
+    assert!(matches!(
+        load(Vec::new()),
+        Err(ConfigurationError::MissingSecretKey { path }) if path == secret
+    ));
+    assert!(matches!(
+        load(vec![(
+            "FOTOTRA_SECRET_KEY".to_string(),
+            SecretKey::generate().to_base64().to_string()
+        )]),
+        Err(ConfigurationError::Decrypt { path }) if path == secret
+    ));
+    assert!(matches!(
+        load(vec![(
+            "FOTOTRA_SECRET_KEY".to_string(),
+            "short".to_string()
+        )]),
+        Err(ConfigurationError::InvalidSecretKey { .. })
+    ));
+    let mut tampered = encrypted.clone().into_bytes();
+    let last = tampered.len() - 3;
+    tampered[last] = if tampered[last] == b'A' { b'B' } else { b'A' };
+    fs::write(&secret, &tampered).unwrap();
+    assert!(matches!(
+        load(with_key()),
+        Err(ConfigurationError::Decrypt { .. })
+    ));
+    fs::write(&secret, &encrypted).unwrap();
+
+    // the CLI edits the decrypted content and encrypts it again, if still valid
+    if cfg!(unix) {
+        assert!(
+            secret_cli(
+                &key,
+                &["edit", &secret_path],
+                Some("sed -i s/hunter2/hunter3/")
+            )
+            .0
+        );
+        let (configuration, _) = load(with_key()).unwrap();
+        assert_eq!(
+            configuration.get_secret("db.password").unwrap().expose(),
+            "hunter3"
+        );
+        let before = fs::read_to_string(&secret).unwrap();
+        assert!(!secret_cli(&key, &["edit", &secret_path], Some("sed -i s/=/:/")).0);
+        assert_eq!(fs::read_to_string(&secret).unwrap(), before);
+    }
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+pub async fn test_configuration_export() {
+    let directory = write_files(&[
+        (
+            "config.toml",
+            "started = 2024-05-01T10:00:00Z\n\n[server]\nport = 8080\nhosts = [\"a\", \"b\"]\n\n[db]\nhost = \"localhost\"\n",
+        ),
+        ("secret.toml", "[db]\npassword = \"hunter2\"\n"),
+    ]);
+    let (configuration, _) = ConfigurationLoader::new()
+        .with_path(&directory.join("config.toml"))
+        .with_env_vars(Vec::<(String, String)>::new())
+        .load()
+        .unwrap();
+
+    // the secrets are redacted, the keys sorted and the datetimes kept as datetimes
+    let exported = configuration.to_toml().unwrap();
+    assert!(!exported.contains("hunter2"));
+    assert!(exported.contains("password = \"[REDACTED]\""));
+    assert!(exported.contains("started = 2024-05-01T10:00:00Z"));
+    assert!(exported.find("[db]").unwrap() < exported.find("[server]").unwrap());
+    let reloaded =
+        Configuration::try_from(toml::from_str::<toml::Value>(&exported).unwrap()).unwrap();
+    assert_eq!(reloaded.get_int("server.port").unwrap(), 8080);
+    assert_eq!(
+        reloaded.get_datetime("started").unwrap(),
+        configuration.get_datetime("started").unwrap()
+    );
+
+    let json: serde_json::Value = serde_json::from_str(&configuration.to_json().unwrap()).unwrap();
+    assert_eq!(json["db"]["password"], "[REDACTED]");
+    assert_eq!(json["server"]["hosts"][1], "b");
+    assert_eq!(json["started"], "2024-05-01T10:00:00Z");
+    assert!(matches!(
+        Configuration::Int(1).to_toml(),
+        Err(ConfigurationError::Export { format: "toml", .. })
+    ));
+
+    // the diff walks tables and arrays, sorted by path, secrets still redacted
+    let updated = configuration.merge(&Configuration::try_from(
+        toml::from_str::<toml::Value>(
+            "[server]\nport = 9090\nhosts = [\"a\"]\ntls = true\n\n[db]\npassword = \"hunter3\"\n",
+        )
+        .unwrap(),
+    )
+    .unwrap());
+    let differences = configuration.diff(&updated);
+    assert_eq!(
+        differences
+            .iter()
+            .map(|difference| difference.get_path())
+            .collect::<Vec<_>>(),
+        vec!["db.password", "server.hosts.1", "server.port", "server.tls"]
+    );
+    assert_eq!(
+        differences
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec![
+            "~ db.password = \"[REDACTED]\" -> \"[REDACTED]\"",
+            "- server.hosts.1 = \"b\"",
+            "~ server.port = 8080 -> 9090",
+            "+ server.tls = true",
+        ]
+    );
+    assert!(configuration.diff(&configuration).is_empty());
+    let nan = Configuration::Map(HashMap::from([(
+        "ratio".to_string(),
+        Configuration::Float(f64::NAN),
+    )]));
+    assert!(nan.diff(&nan.clone()).is_empty());
+    assert!(nan.same_as(&nan));
+    let json = serde_json::to_value(&differences).unwrap();
+    assert_eq!(json[3]["difference"], "added");
+    assert!(!json.to_string().contains("hunter"));
+
+    fs::remove_dir_all(directory).unwrap();
+}
+
+pub async fn test_configuration_merge() {
+    let directory = write_files(&[
+        (
+            "config.toml",
+            r#"[schedule]
+day = 2024-05-01
+at = 07:30:00
+since = 2024-05-01T07:30:00.5
+leap = 23:59:60
+
+[plugins]
+paths = ["a.so"]
+features = ["audit"]
+
+[cache]
+size = 10
+ttl = 60
+
+[[servers]]
+name = "primary"
+port = 80
+tags = ["eu"]
+
+[[servers]]
+name = "replica"
+port = 81
+"#,
+        ),
+        (
+            "config.prod.toml",
+            r#"[plugins]
+paths = ["b.so"]
+features = ["metrics"]
+
+[cache]
+size = 20
+
+[[servers]]
+name = "primary"
+port = 443
+tags = ["prod"]
+
+[[servers]]
+name = "backup"
+port = 82
+"#,
+        ),
+    ]);
+    let loader = ConfigurationLoader::new()
+        .with_path(&directory.join("config.toml"))
+        .with_profile("prod")
+        .with_env_vars([]);
+
+    // local dates, times and datetimes load as such, a leap second included
+    let (configuration, _) = loader.clone().load().unwrap();
+    assert_eq!(
+        configuration.get_local_date("schedule.day").unwrap(),
+        NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+    );
+    assert_eq!(
+        configuration.get_local_time("schedule.at").unwrap(),
+        NaiveTime::from_hms_opt(7, 30, 0).unwrap()
+    );
+    assert_eq!(
+        configuration.get_local_datetime("schedule.since").unwrap(),
+        NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_milli_opt(7, 30, 0, 500)
+            .unwrap()
+    );
+    assert_eq!(
+        configuration
+            .get_local_time("schedule.leap")
+            .unwrap()
+            .second(),
+        59
+    );
+    assert!(matches!(
+        configuration.get_datetime("schedule.since"),
+        Err(ConfigurationError::InvalidType {
+            found: "local datetime",
+            ..
+        })
+    ));
+    assert!(ConfigurationType::LocalDate.matches(configuration.get_path("schedule.day").unwrap()));
+
+    #[derive(Deserialize)]
+    struct Schedule {
+        day: NaiveDate,
+        at: NaiveTime,
+        since: NaiveDateTime,
+    }
+    let schedule: Schedule = configuration.get_as("schedule").unwrap();
+    assert_eq!(
+        schedule.day,
+        configuration.get_local_date("schedule.day").unwrap()
+    );
+    assert_eq!(
+        schedule.at,
+        configuration.get_local_time("schedule.at").unwrap()
+    );
+    assert_eq!(
+        schedule.since,
+        configuration.get_local_datetime("schedule.since").unwrap()
+    );
+
+    // they are written back as TOML local values
+    let exported = configuration.to_toml().unwrap();
+    assert!(exported.contains("day = 2024-05-01\n"));
+    assert!(exported.contains("since = 2024-05-01T07:30:00.5\n"));
+    assert!(exported.contains("leap = 23:59:60\n"));
+    let reloaded =
+        Configuration::try_from(toml::from_str::<toml::Value>(&exported).unwrap()).unwrap();
+    assert_eq!(
+        reloaded.get_path("schedule"),
+        configuration.get_path("schedule")
+    );
+
+    // without strategies, the arrays of the profile replace the ones of the base file
+    assert_eq!(
+        configuration
+            .get_as::<Vec<String>>("plugins.paths")
+            .unwrap(),
+        vec!["b.so"]
+    );
+    assert_eq!(configuration.get_int("cache.ttl").unwrap(), 60);
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Server {
+        name: String,
+        port: i64,
+        #[serde(default)]
+        tags: Vec<String>,
+    }
+    let (configuration, _) = loader
+        .clone()
+        .with_merge_strategy("plugins.paths", MergeStrategy::Append)
+        .with_merge_strategy("servers", MergeStrategy::MergeByKey("name".to_string()))
+        .with_merge_strategy("servers.tags", MergeStrategy::Append)
+        .with_merge_strategy("cache", MergeStrategy::Replace)
+        .load()
+        .unwrap();
+    assert_eq!(
+        configuration
+            .get_as::<Vec<String>>("plugins.paths")
+            .unwrap(),
+        vec!["a.so", "b.so"]
+    );
+    assert_eq!(
+        configuration
+            .get_as::<Vec<String>>("plugins.features")
+            .unwrap(),
+        vec!["metrics"]
+    );
+    assert!(matches!(
+        configuration.get_int("cache.ttl"),
+        Err(ConfigurationError::MissingKey { .. })
+    ));
+    let server = |name: &str, port, tags: &[&str]| Server {
+        name: name.to_string(),
+        port,
+        tags: tags.iter().map(ToString::to_string).collect(),
+    };
+    assert_eq!(
+        configuration.get_as::<Vec<Server>>("servers").unwrap(),
+        vec![
+            server("primary", 443, &["eu", "prod"]),
+            server("replica", 81, &[]),
+            server("backup", 82, &[]),
+        ]
+    );
+
+    // a strategy that does not fit the values fails instead of replacing them
+    for strategy in [
+        MergeStrategy::Append,
+        MergeStrategy::MergeByKey("name".to_string()),
+    ] {
+        let error = loader
+            .clone()
+            .with_merge_strategy("cache", strategy)
+            .load()
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ConfigurationError::MergeStrategy {
+                key,
+                lower: "table",
+                upper: "table",
+                ..
+            } if key == "cache"
+        ));
+    }
+
+    // a secret overridden by a number stays a secret
+    let merged = Configuration::Map(HashMap::from([(
+        "pin".to_string(),
+        Configuration::Secret(Secret::new("1234")),
+    )]))
+    .merge(&Configuration::Map(HashMap::from([(
+        "pin".to_string(),
+        Configuration::Int(4321),
+    )])));
+    assert_eq!(merged.get_secret("pin").unwrap().expose(), "4321");
+
+    fs::remove_dir_all(directory).unwrap();
+}
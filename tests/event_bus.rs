@@ -0,0 +1,256 @@
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+use anyhow::ensure;
+use fototra::{
+    adapters::repository::in_memory::{
+        database::InMemoryDatabase, permission_repository::InMemoryPermissionRepository,
+        user_permission_repository::InMemoryUserPermissionRepository,
+        user_repository::InMemoryUserRepository,
+    },
+    dtos::user::{user_add_request::UserAddRequest, user_delete_request::UserDeleteRequest},
+    model::{
+        adapter_health::AdapterHealth,
+        domain_event::{DomainEvent, DomainEventEnvelope},
+        user::{DEFAULT_ADMIN_USER, name::Name},
+    },
+    repository::{
+        outbox_repository::OutboxRepository, permission_repository::PermissionRepository,
+        user_permission_repository::UserPermissionRepository, user_repository::UserRepository,
+    },
+    runtime::Runtime,
+    service::user::UserService,
+    traits::{
+        adapter_loader_trait::AdapterLoaderTrait, event_subscriber_trait::EventSubscriberTrait,
+        initialize_trait::InitializeTrait,
+    },
+};
+use tokio::sync::{Notify, mpsc};
+
+use crate::common::UserToken;
+
+struct Collector {
+    sender: mpsc::UnboundedSender<DomainEventEnvelope>,
+}
+
+impl EventSubscriberTrait<DomainEventEnvelope> for Collector {
+    fn name(&self) -> &str {
+        "collector"
+    }
+
+    fn handle<'a>(
+        &'a self,
+        event: &'a DomainEventEnvelope,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            self.sender.send(event.clone())?;
+            Ok(())
+        })
+    }
+}
+
+/// Hold every event until released
+struct SlowSubscriber {
+    release: Arc<Notify>,
+    sender: mpsc::UnboundedSender<DomainEventEnvelope>,
+}
+
+impl EventSubscriberTrait<DomainEventEnvelope> for SlowSubscriber {
+    fn name(&self) -> &str {
+        "slow"
+    }
+
+    fn handle<'a>(
+        &'a self,
+        event: &'a DomainEventEnvelope,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            self.release.notified().await;
+            self.sender.send(event.clone())?;
+            Ok(())
+        })
+    }
+}
+
+#[derive(Clone)]
+struct Ping {
+    welcome: bool,
+}
+
+/// Refuse the pings that are not welcome
+struct Picky;
+
+impl EventSubscriberTrait<Ping> for Picky {
+    fn name(&self) -> &str {
+        "picky"
+    }
+
+    fn handle<'a>(
+        &'a self,
+        event: &'a Ping,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            ensure!(event.welcome, "unwelcome ping");
+            Ok(())
+        })
+    }
+}
+
+async fn failure_of(task: &str) -> Option<String> {
+    Runtime::get_instance()
+        .health()
+        .await
+        .into_iter()
+        .find_map(|(name, health)| match health {
+            AdapterHealth::Degraded { reason } if name == task => Some(reason),
+            _ => None,
+        })
+}
+
+/// Repositories of the user service without any outbox relay, with or without the outbox
+#[derive(Debug)]
+struct RelaylessRepositories {
+    database: InMemoryDatabase,
+    with_outbox: bool,
+}
+
+impl InitializeTrait for RelaylessRepositories {}
+
+impl AdapterLoaderTrait for RelaylessRepositories {
+    fn name(&self) -> &str {
+        "RelaylessRepositories"
+    }
+
+    fn load<'a>(&'a self) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            let runtime = Runtime::get_instance();
+            if self.with_outbox {
+                runtime
+                    .register(OutboxRepository::new(Arc::new(
+                        self.database.get_outbox().clone(),
+                    )))
+                    .await?;
+            }
+            let permissions = InMemoryPermissionRepository::new(&self.database);
+            let users = InMemoryUserRepository::new(&self.database);
+            let user_permissions = InMemoryUserPermissionRepository::new(&self.database);
+            permissions.initialize().await?;
+            users.initialize().await?;
+            user_permissions.initialize().await?;
+            runtime
+                .register(PermissionRepository::new(Arc::new(permissions)))
+                .await?;
+            runtime
+                .register(UserRepository::new(Arc::new(users)))
+                .await?;
+            runtime
+                .register(UserPermissionRepository::new(Arc::new(user_permissions)))
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+/// Subscribers get the events of a runtime whose adapter did not start the relay
+async fn relayless_events(with_outbox: bool) {
+    let runtime = Runtime::new();
+    runtime.init().await.unwrap();
+    runtime
+        .add_adapter(Arc::new(RelaylessRepositories {
+            database: InMemoryDatabase::new(),
+            with_outbox,
+        }))
+        .await
+        .unwrap();
+    runtime.start().await.unwrap();
+    runtime
+        .scope(async {
+            let (sender, mut collected) = mpsc::unbounded_channel();
+            Runtime::get_instance()
+                .subscribe::<DomainEventEnvelope>(Arc::new(Collector { sender }))
+                .await;
+            let user = UserService::create(
+                &UserToken {
+                    user_id: *DEFAULT_ADMIN_USER.get_id(),
+                },
+                &UserAddRequest::new(&Name::new("Relayless").unwrap(), None),
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                collected.recv().await.unwrap().get_event(),
+                &DomainEvent::UserCreated { user }
+            );
+        })
+        .await;
+}
+
+pub async fn test_event_bus() {
+    let admin = UserToken {
+        user_id: *DEFAULT_ADMIN_USER.get_id(),
+    };
+    let (sender, mut collected) = mpsc::unbounded_channel();
+    Runtime::get_instance()
+        .subscribe::<DomainEventEnvelope>(Arc::new(Collector { sender }))
+        .await;
+    let release = Arc::new(Notify::new());
+    let (sender, mut slowly_collected) = mpsc::unbounded_channel();
+    Runtime::get_instance()
+        .subscribe::<DomainEventEnvelope>(Arc::new(SlowSubscriber {
+            release: release.clone(),
+            sender,
+        }))
+        .await;
+
+    // the mutation returns while the slow subscriber still waits
+    let user = UserService::create(
+        &admin,
+        &UserAddRequest::new(&Name::new("Hery").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    let created = DomainEvent::UserCreated { user: user.clone() };
+    assert_eq!(collected.recv().await.unwrap().get_event(), &created);
+    assert!(slowly_collected.try_recv().is_err());
+    release.notify_one();
+    assert_eq!(slowly_collected.recv().await.unwrap().get_event(), &created);
+
+    // failed mutations publish nothing
+    UserService::delete(&admin, &UserDeleteRequest::new(&uuid::Uuid::new_v4()))
+        .await
+        .unwrap_err();
+    UserService::delete(&admin, &UserDeleteRequest::new(user.get_id()))
+        .await
+        .unwrap();
+    assert_eq!(
+        collected.recv().await.unwrap().get_event(),
+        &DomainEvent::UserDeleted {
+            user_id: *user.get_id()
+        }
+    );
+
+    // a failed subscriber degrades the health until it handles an event again
+    Runtime::get_instance()
+        .subscribe::<Ping>(Arc::new(Picky))
+        .await;
+    Runtime::get_instance()
+        .publish(Ping { welcome: false })
+        .await;
+    let reason = loop {
+        if let Some(reason) = failure_of("subscriber picky").await {
+            break reason;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
+    assert_eq!(reason, "unwelcome ping");
+    Runtime::get_instance()
+        .publish(Ping { welcome: true })
+        .await;
+    while failure_of("subscriber picky").await.is_some() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // without an outbox the events are published, with one but no relay they are
+    // relayed by each mutation
+    relayless_events(false).await;
+    relayless_events(true).await;
+}
@@ -0,0 +1,190 @@
+use std::{
+    ffi::{CStr, CString, c_char},
+    fs, ptr,
+};
+
+use fototra::{
+    ffi::{
+        FototraStatus, fototra_add_in_memory_adapter, fototra_init, fototra_last_error,
+        fototra_start, fototra_string_free,
+        user::{
+            fototra_user_create, fototra_user_delete, fototra_user_find, fototra_user_find_one,
+        },
+        user_internet::{fototra_user_internet_create, fototra_user_internet_find},
+        user_password::{fototra_user_password_create, fototra_user_password_match},
+        user_permission::{
+            fototra_user_permission_create, fototra_user_permission_find,
+            fototra_user_permission_find_effective,
+        },
+    },
+    model::user::DEFAULT_ADMIN_USER,
+};
+use serde_json::{Value, json};
+
+/// Take the JSON string returned by the library
+fn take(string: *mut c_char) -> Value {
+    assert!(!string.is_null());
+    let value = serde_json::from_str(unsafe { CStr::from_ptr(string) }.to_str().unwrap()).unwrap();
+    unsafe { fototra_string_free(string) };
+    value
+}
+
+fn c_string(value: &str) -> CString {
+    CString::new(value).unwrap()
+}
+
+/// Blocks on the runtime of the C API, so it runs outside of any tokio runtime
+pub fn test_ffi() {
+    let admin = c_string(&DEFAULT_ADMIN_USER.get_id().to_string());
+    assert_eq!(fototra_init(), FototraStatus::Ok);
+    assert_eq!(fototra_add_in_memory_adapter(), FototraStatus::Ok);
+    assert_eq!(fototra_start(), FototraStatus::Ok);
+    assert!(fototra_last_error().is_null());
+
+    unsafe {
+        let mut response = ptr::null_mut();
+        let request = c_string(r#"{"firstname": "Ffi", "lastname": null}"#);
+        assert_eq!(
+            fototra_user_create(admin.as_ptr(), request.as_ptr(), &mut response),
+            FototraStatus::Ok
+        );
+        let user = take(response);
+        assert_eq!(user["firstname"], json!("Ffi"));
+        let user_id = c_string(user["id"].as_str().unwrap());
+
+        assert_eq!(
+            fototra_user_find_one(admin.as_ptr(), user_id.as_ptr(), &mut response),
+            FototraStatus::Ok
+        );
+        assert_eq!(take(response), user);
+
+        let request =
+            c_string(&json!({"user_id": user["id"], "email": "ffi@fototra.mg"}).to_string());
+        assert_eq!(
+            fototra_user_internet_create(admin.as_ptr(), request.as_ptr(), &mut response),
+            FototraStatus::Ok
+        );
+        take(response);
+
+        let request =
+            c_string(&json!({"user_id": user["id"], "permission": "user:find"}).to_string());
+        assert_eq!(
+            fototra_user_permission_create(admin.as_ptr(), request.as_ptr(), &mut response),
+            FototraStatus::Ok
+        );
+        take(response);
+        assert_eq!(
+            fototra_user_permission_find_effective(admin.as_ptr(), user_id.as_ptr(), &mut response),
+            FototraStatus::Ok
+        );
+        assert!(
+            take(response)
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|effective| { effective["permission"] == json!("user:find") })
+        );
+
+        let request =
+            c_string(&json!({"user_id": user["id"], "password": "Secret#2024x"}).to_string());
+        assert_eq!(
+            fototra_user_password_create(admin.as_ptr(), request.as_ptr()),
+            FototraStatus::Ok
+        );
+        assert_eq!(
+            fototra_user_password_match(
+                admin.as_ptr(),
+                user_id.as_ptr(),
+                c_string("Secret#2024x").as_ptr()
+            ),
+            FototraStatus::Ok
+        );
+
+        // errors are reported by status and by the last error of the thread
+        let actor = c_string(user["id"].as_str().unwrap());
+        assert_eq!(
+            fototra_user_delete(actor.as_ptr(), user_id.as_ptr(), &mut response),
+            FototraStatus::Auth
+        );
+        assert!(response.is_null());
+        let error = take(fototra_last_error());
+        assert_eq!(error["code"], json!("security.not_authorized.no_grant"));
+        assert_eq!(
+            error["details"],
+            json!({
+                "permission": "user:delete",
+                "principal_id": user["id"],
+                "reason": "no_grant",
+            })
+        );
+        let request = c_string("{\"firstname\": ");
+        assert_eq!(
+            fototra_user_create(admin.as_ptr(), request.as_ptr(), &mut response),
+            FototraStatus::Validation
+        );
+        assert_eq!(
+            take(fototra_last_error())["code"],
+            json!("ffi.invalid_argument")
+        );
+        assert_eq!(
+            fototra_user_find_one(admin.as_ptr(), ptr::null(), &mut response),
+            FototraStatus::Validation
+        );
+
+        // a search is validated like one built by FindRequest::new
+        for find in [
+            fototra_user_find,
+            fototra_user_internet_find,
+            fototra_user_permission_find,
+        ] {
+            for request in [r#"{"per_page": 0}"#, r#"{"offset": 0}"#] {
+                let request = c_string(request);
+                assert_eq!(
+                    find(admin.as_ptr(), request.as_ptr(), &mut response),
+                    FototraStatus::Validation
+                );
+                assert_eq!(
+                    take(fototra_last_error())["code"],
+                    json!("ffi.invalid_argument")
+                );
+            }
+        }
+        let request = c_string(r#"{"per_page": 5}"#);
+        assert_eq!(
+            fototra_user_find(admin.as_ptr(), request.as_ptr(), &mut response),
+            FototraStatus::Ok
+        );
+        take(response);
+
+        assert_eq!(
+            fototra_user_delete(admin.as_ptr(), user_id.as_ptr(), &mut response),
+            FototraStatus::Ok
+        );
+        assert_eq!(take(response)["emails"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            fototra_user_find_one(admin.as_ptr(), user_id.as_ptr(), &mut response),
+            FototraStatus::NotFound
+        );
+        assert_eq!(take(fototra_last_error())["code"], json!("user.not_exists"));
+    }
+
+    // the shipped header is the one cbindgen generates from the sources
+    let mut generated = Vec::new();
+    cbindgen::generate_with_config(
+        env!("CARGO_MANIFEST_DIR"),
+        cbindgen::Config::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/cbindgen.toml")).unwrap(),
+    )
+    .unwrap()
+    .write(&mut generated);
+    fs::write(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/include/fototra.h"),
+        &generated,
+    )
+    .unwrap();
+    assert!(
+        String::from_utf8(generated).unwrap()
+            == fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/include/fototra.h"))
+                .unwrap(),
+        "include/fototra.h is outdated, run cbindgen --config cbindgen.toml --output include/fototra.h"
+    );
+}
@@ -1,13 +1,59 @@
+mod adapter_lifecycle;
+mod adapter_plugin;
+mod audit;
+mod common;
+mod configuration;
+mod event_bus;
+mod ffi;
+mod onboarding;
+mod outbox;
+mod registry;
+mod runtime;
+mod transaction;
 mod user;
+mod user_deletion;
+mod user_permission;
+mod user_status;
 
 use std::{path::PathBuf, sync::Arc};
 
-use fototra::{adapters::repository::in_memory::InMemoryRepository, runtime::Runtime};
+use adapter_lifecycle::test_adapter_lifecycle;
+use adapter_plugin::test_adapter_plugin;
+use audit::test_audit;
+use configuration::{
+    test_configuration_encryption, test_configuration_errors, test_configuration_export,
+    test_configuration_layers, test_configuration_merge, test_configuration_reload,
+    test_configuration_schema, test_configuration_secrets, test_configuration_typed,
+};
+use event_bus::test_event_bus;
+use ffi::test_ffi;
+use fototra::{
+    adapters::repository::in_memory::InMemoryRepository,
+    repository::user_repository::UserRepository, runtime::Runtime,
+};
 use libloading::{Library, Symbol};
+use onboarding::test_onboarding;
+use outbox::test_outbox;
+use registry::test_registry;
+use runtime::test_runtime_isolation;
+use transaction::test_transactions;
 use user::test_users;
+use user_deletion::{test_user_deletion, test_user_deletion_without_unit_of_work};
+use user_permission::test_user_permissions;
+use user_status::test_user_status;
+
+/// Runtime started with its own in-memory database, so each test gets a clean world
+async fn start_runtime() -> (Runtime, Arc<InMemoryRepository>) {
+    let runtime = Runtime::new();
+    let adapter = Arc::new(InMemoryRepository::new());
+    runtime.init().await.unwrap();
+    runtime.add_adapter(adapter.clone()).await.unwrap();
+    runtime.start().await.unwrap();
+    (runtime, adapter)
+}
 
 #[tokio::test]
-async fn tests() {
+async fn global_runtime_from_library() {
     let mut lib_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     lib_path.push("target");
     lib_path.push(if cfg!(debug_assertions) {
@@ -26,18 +72,157 @@ async fn tests() {
     lib_path.push("libfototra.so");
 
     let lib = unsafe { Library::new(lib_path).expect("Failed to load library") };
-    let mut get_runtime: Option<Symbol<unsafe extern "C" fn() -> Runtime>> = None;
-    unsafe {
-        get_runtime = Some(
-            lib.get(b"get_runtime")
-                .expect("Failed to load 'get_adapter' function"),
-        );
-
-        get_runtime.as_ref().unwrap()().init().await.unwrap();
-        get_runtime.as_ref().unwrap()()
-            .add_adapter(Arc::new(InMemoryRepository))
-            .await;
-    }
-
-    test_users().await;
+    let runtime = unsafe {
+        let get_runtime: Symbol<unsafe extern "C" fn() -> *const Runtime> = lib
+            .get(b"get_runtime")
+            .expect("Failed to load 'get_runtime' function");
+        (*get_runtime()).clone()
+    };
+
+    runtime.init().await.unwrap();
+    runtime
+        .add_adapter(Arc::new(InMemoryRepository::new()))
+        .await
+        .unwrap();
+    runtime.start().await.unwrap();
+    assert!(runtime.get::<UserRepository>().await.is_some());
+    runtime.shutdown().await.unwrap();
+}
+
+#[test]
+fn c_api() {
+    test_ffi();
+}
+
+#[tokio::test]
+async fn users() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_users()).await;
+}
+
+#[tokio::test]
+async fn user_permissions() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_user_permissions()).await;
+}
+
+#[tokio::test]
+async fn audit() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_audit()).await;
+}
+
+#[tokio::test]
+async fn event_bus() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_event_bus()).await;
+}
+
+#[tokio::test]
+async fn outbox() {
+    let (runtime, adapter) = start_runtime().await;
+    runtime
+        .scope(test_outbox(adapter.get_database().get_outbox()))
+        .await;
+}
+
+#[tokio::test]
+async fn registry() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_registry()).await;
+}
+
+#[tokio::test]
+async fn transactions() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_transactions()).await;
+}
+
+#[tokio::test]
+async fn onboarding() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_onboarding()).await;
+}
+
+#[tokio::test]
+async fn user_deletion() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_user_deletion()).await;
+}
+
+#[tokio::test]
+async fn user_deletion_without_unit_of_work() {
+    let runtime = Runtime::new();
+    runtime.init().await.unwrap();
+    runtime
+        .scope(test_user_deletion_without_unit_of_work())
+        .await;
+}
+
+#[tokio::test]
+async fn user_status() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_user_status()).await;
+}
+
+#[tokio::test]
+async fn adapter_lifecycle() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_adapter_lifecycle()).await;
+}
+
+#[tokio::test]
+async fn adapter_plugin() {
+    let (runtime, _) = start_runtime().await;
+    runtime.scope(test_adapter_plugin()).await;
+}
+
+#[tokio::test]
+async fn configuration_layers() {
+    test_configuration_layers().await;
+}
+
+#[tokio::test]
+async fn configuration_encryption() {
+    test_configuration_encryption().await;
+}
+
+#[tokio::test]
+async fn configuration_export() {
+    test_configuration_export().await;
+}
+
+#[tokio::test]
+async fn configuration_merge() {
+    test_configuration_merge().await;
+}
+
+#[tokio::test]
+async fn configuration_errors() {
+    test_configuration_errors().await;
+}
+
+#[tokio::test]
+async fn configuration_reload() {
+    test_configuration_reload().await;
+}
+
+#[tokio::test]
+async fn configuration_schema() {
+    test_configuration_schema().await;
+}
+
+#[tokio::test]
+async fn configuration_secrets() {
+    test_configuration_secrets().await;
+}
+
+#[tokio::test]
+async fn configuration_typed() {
+    test_configuration_typed().await;
+}
+
+#[tokio::test]
+async fn runtime_isolation() {
+    test_runtime_isolation().await;
 }
@@ -0,0 +1,156 @@
+use fototra::{
+    dtos::{
+        find_request::FindRequest, user::user_onboard_request::UserOnboardRequest,
+        user_internet::user_internet_find_request_filter::UserInternetFindRequestFilter,
+    },
+    model::user::{DEFAULT_ADMIN_USER, error::UserError},
+    repository::{
+        user_internet_repository::UserInternetRepository, user_repository::UserRepository,
+    },
+    runtime::Runtime,
+    service::{error::ErrorCategory, user::UserService, user_password::UserPasswordService},
+    traits::find_result_trait::FindResultTrait,
+};
+
+use crate::common::UserToken;
+
+async fn email_count(email: &str) -> usize {
+    let filter = UserInternetFindRequestFilter {
+        email: Some(email.to_string()),
+        ..Default::default()
+    };
+    Runtime::get_instance()
+        .get::<UserInternetRepository>()
+        .await
+        .unwrap()
+        .find_all(&FindRequest::new(&filter, "", &25, &1).unwrap())
+        .await
+        .unwrap()
+        .get_result()
+        .count()
+}
+
+pub async fn test_onboarding() {
+    let admin = UserToken {
+        user_id: *DEFAULT_ADMIN_USER.get_id(),
+    };
+
+    let onboarded = UserService::onboard(
+        &admin,
+        &UserOnboardRequest::new(
+            "Rova",
+            Some("Rakoto"),
+            &["rova@fototra.mg", "rova.rakoto@fototra.mg"],
+            "secret123",
+            &["user:find", "user_internet:*"],
+        ),
+    )
+    .await
+    .unwrap();
+    let user = onboarded.get_user();
+    assert_eq!(onboarded.get_emails().len(), 2);
+    assert_eq!(onboarded.get_permissions().len(), 2);
+    assert_eq!(
+        Runtime::get_instance()
+            .get::<UserRepository>()
+            .await
+            .unwrap()
+            .find_by_id(user.get_id())
+            .await
+            .unwrap(),
+        *user
+    );
+    UserPasswordService::match_user_password(&admin, user.get_id(), "secret123")
+        .await
+        .unwrap();
+
+    // every problem is reported and nothing is written
+    let e = UserService::onboard(
+        &admin,
+        &UserOnboardRequest::new(
+            "rova",
+            None,
+            &[
+                "fresh@fototra.mg",
+                "not-an-email",
+                "fresh@fototra.mg",
+                "rova@fototra.mg",
+            ],
+            "",
+            &["user:find", "nope:nope"],
+        ),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(e.get_category(), ErrorCategory::Validation);
+    assert_eq!(e.get_code(), "user.invalid_onboarding");
+    let UserError::InvalidOnboarding { violations } = &*e.get::<UserError>().unwrap() else {
+        panic!("{e}");
+    };
+    let fields: Vec<&str> = violations.iter().map(|v| v.get_field()).collect();
+    assert_eq!(
+        fields,
+        [
+            "firstname",
+            "emails[1]",
+            "emails[2]",
+            "emails[3]",
+            "password",
+            "permissions[1]"
+        ]
+    );
+    assert_eq!(email_count("fresh@fototra.mg").await, 0);
+
+    // granting needs the right to grant
+    let e = UserService::onboard(
+        &UserToken {
+            user_id: *user.get_id(),
+        },
+        &UserOnboardRequest::new("Other", None, &["other@fototra.mg"], "secret123", &[]),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(e.get_category(), ErrorCategory::Auth);
+    assert_eq!(email_count("other@fototra.mg").await, 0);
+
+    // onboarding writes emails and a password, which need their own permissions
+    let onboarder = UserService::onboard(
+        &admin,
+        &UserOnboardRequest::new(
+            "Onboarder",
+            None,
+            &["onboarder@fototra.mg"],
+            "secret123",
+            &["user:onboard"],
+        ),
+    )
+    .await
+    .unwrap();
+    let e = UserService::onboard(
+        &UserToken {
+            user_id: *onboarder.get_user().get_id(),
+        },
+        &UserOnboardRequest::new("Other", None, &["other@fototra.mg"], "secret123", &[]),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(e.get_category(), ErrorCategory::Auth);
+    assert_eq!(email_count("other@fototra.mg").await, 0);
+
+    // concurrent onboardings of the same email: only one of them creates the user, the
+    // other one failing the validation or, when both passed it, the commit
+    let request = UserOnboardRequest::new("Twin", None, &["twin@fototra.mg"], "secret123", &[]);
+    let (first, second) = tokio::join!(
+        UserService::onboard(&admin, &request),
+        UserService::onboard(&admin, &request)
+    );
+    let failure = match (first, second) {
+        (Ok(_), Err(e)) | (Err(e), Ok(_)) => e,
+        (first, second) => panic!("{first:?} {second:?}"),
+    };
+    assert!(matches!(
+        failure.get_category(),
+        ErrorCategory::Validation | ErrorCategory::Conflict
+    ));
+    assert_eq!(email_count("twin@fototra.mg").await, 1);
+}
@@ -0,0 +1,247 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use fototra::{
+    adapters::repository::in_memory::outbox_repository::{InMemoryOutboxRepository, OutboxStep},
+    configuration::Configuration,
+    dtos::user::{user_add_request::UserAddRequest, user_update_request::UserUpdateRequest},
+    model::{
+        adapter_health::AdapterHealth,
+        domain_event::{DomainEvent, DomainEventEnvelope},
+        user::{DEFAULT_ADMIN_USER, name::Name},
+    },
+    runtime::Runtime,
+    service::user::UserService,
+    traits::{
+        domain_event::outbox_repository_trait::OutboxRepositoryTrait,
+        event_subscriber_trait::EventSubscriberTrait,
+    },
+};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::common::UserToken;
+
+struct Recorder {
+    sender: mpsc::UnboundedSender<DomainEventEnvelope>,
+}
+
+impl EventSubscriberTrait<DomainEventEnvelope> for Recorder {
+    fn name(&self) -> &str {
+        "recorder"
+    }
+
+    fn handle<'a>(
+        &'a self,
+        event: &'a DomainEventEnvelope,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            self.sender.send(event.clone())?;
+            Ok(())
+        })
+    }
+}
+
+/// Fail the first event handed once armed, reporting it
+struct Flaky {
+    armed: AtomicBool,
+    failed: mpsc::UnboundedSender<DomainEventEnvelope>,
+}
+
+impl EventSubscriberTrait<DomainEventEnvelope> for Flaky {
+    fn name(&self) -> &str {
+        "flaky"
+    }
+
+    fn handle<'a>(
+        &'a self,
+        event: &'a DomainEventEnvelope,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            if self.armed.swap(false, Ordering::SeqCst) {
+                self.failed.send(event.clone())?;
+                anyhow::bail!("cannot handle {}", event.get_id());
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Fail the events about a user named Poison and never finish the ones about Stuck
+struct Picky;
+
+impl EventSubscriberTrait<DomainEventEnvelope> for Picky {
+    fn name(&self) -> &str {
+        "picky"
+    }
+
+    fn handle<'a>(
+        &'a self,
+        event: &'a DomainEventEnvelope,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'a>> {
+        Box::pin(async {
+            if let DomainEvent::UserUpdated { user } = event.get_event() {
+                match &**user.get_firstname() {
+                    "Poison" => anyhow::bail!("poisoned"),
+                    "Stuck" => std::future::pending().await,
+                    _ => {}
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+pub async fn test_outbox(outbox: &InMemoryOutboxRepository) {
+    let admin = UserToken {
+        user_id: *DEFAULT_ADMIN_USER.get_id(),
+    };
+    let (sender, mut recorded) = mpsc::unbounded_channel();
+    Runtime::get_instance()
+        .subscribe::<DomainEventEnvelope>(Arc::new(Recorder { sender }))
+        .await;
+
+    // the relay fails before publishing: the stored event is published by a later pass
+    outbox.fail_next(OutboxStep::FindPending);
+    let user = UserService::create(
+        &admin,
+        &UserAddRequest::new(&Name::new("Outbox").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    let created = recorded.recv().await.unwrap();
+    assert_eq!(
+        created.get_event(),
+        &DomainEvent::UserCreated { user: user.clone() }
+    );
+
+    // the relay fails after the subscribers handled the event: a later pass acknowledges
+    // it without handing it again
+    outbox.fail_next(OutboxStep::Acknowledge);
+    let user = UserService::update(
+        &admin,
+        user.get_id(),
+        &UserUpdateRequest::new(user.get_id(), &Name::new("Relayed").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    let relayed = recorded.recv().await.unwrap();
+    assert_eq!(
+        relayed.get_event(),
+        &DomainEvent::UserUpdated { user: user.clone() }
+    );
+    assert_ne!(relayed.get_id(), created.get_id());
+    wait_until_relayed(outbox).await;
+    assert!(recorded.try_recv().is_err());
+
+    // a failed subscriber leaves the event pending and only gets it again
+    let (failed, mut failures) = mpsc::unbounded_channel();
+    let flaky = Arc::new(Flaky {
+        armed: AtomicBool::new(true),
+        failed,
+    });
+    Runtime::get_instance()
+        .subscribe::<DomainEventEnvelope>(flaky)
+        .await;
+    let user = UserService::update(
+        &admin,
+        user.get_id(),
+        &UserUpdateRequest::new(user.get_id(), &Name::new("Retried").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    let failed = failures.recv().await.unwrap();
+    assert_eq!(
+        failed.get_event(),
+        &DomainEvent::UserUpdated { user: user.clone() }
+    );
+    assert!(
+        outbox
+            .find_pending(100)
+            .await
+            .unwrap()
+            .iter()
+            .any(|envelope| envelope.get_id() == failed.get_id())
+    );
+    assert_eq!(recorded.recv().await.unwrap(), failed);
+    wait_until_relayed(outbox).await;
+    assert!(recorded.try_recv().is_err());
+
+    // an event a subscriber keeps failing or never finishes is parked after the last
+    // attempt, and the following events are relayed
+    let runtime = Runtime::get_instance();
+    let configuration = runtime.get::<Configuration>().await.unwrap();
+    runtime
+        .register(configuration.merge(&Configuration::Map(HashMap::from([(
+            "outbox".to_string(),
+            Configuration::Map(HashMap::from([
+                ("max_attempts".to_string(), Configuration::Int(2)),
+                ("delivery_timeout_ms".to_string(), Configuration::Int(50)),
+            ])),
+        )]))))
+        .await
+        .unwrap();
+    runtime
+        .subscribe::<DomainEventEnvelope>(Arc::new(Picky))
+        .await;
+    let mut parked = Vec::new();
+    for name in ["Poison", "Stuck", "Fine"] {
+        let user = UserService::update(
+            &admin,
+            user.get_id(),
+            &UserUpdateRequest::new(user.get_id(), &Name::new(name).unwrap(), None),
+        )
+        .await
+        .unwrap();
+        let handled = recorded.recv().await.unwrap();
+        assert_eq!(
+            handled.get_event(),
+            &DomainEvent::UserUpdated { user: user.clone() }
+        );
+        if name != "Fine" {
+            parked.push(*handled.get_id());
+        }
+    }
+    wait_until_relayed(outbox).await;
+    assert!(recorded.try_recv().is_err());
+    let reasons: Vec<(Uuid, String)> = outbox
+        .find_parked(100)
+        .await
+        .unwrap()
+        .iter()
+        .map(|event| {
+            (
+                *event.get_envelope().get_id(),
+                event.get_reason().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        reasons,
+        vec![
+            (parked[0], "subscribers failed: picky: poisoned".to_string()),
+            (
+                parked[1],
+                "subscribers failed: picky: not handled within 50ms".to_string()
+            ),
+        ]
+    );
+    assert!(runtime.health().await.iter().any(|(task, health)| {
+        task == "outbox parked events"
+            && matches!(health, AdapterHealth::Degraded { reason } if reason.contains(&parked[1].to_string()))
+    }));
+}
+
+/// Wait until the relay emptied the outbox
+async fn wait_until_relayed(outbox: &InMemoryOutboxRepository) {
+    while !outbox.find_pending(100).await.unwrap().is_empty() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
@@ -0,0 +1,55 @@
+use anyhow::Context;
+use fototra::{
+    configuration::{
+        Configuration,
+        schema::{ConfigurationKey, ConfigurationSchema, ConfigurationType},
+    },
+    declare_adapter_plugin,
+    model::adapter_health::AdapterHealth,
+    runtime::plugin::export::PluginHost,
+    traits::plugin_adapter_trait::PluginAdapterTrait,
+};
+
+/// Adapter registering the configured greeting, loaded by the integration tests
+#[derive(Debug, Default)]
+pub struct GreetingAdapter;
+
+impl PluginAdapterTrait for GreetingAdapter {
+    fn name(&self) -> &str {
+        "GreetingPlugin"
+    }
+
+    fn dependencies(&self) -> &[&str] {
+        &["InMemoryRepository"]
+    }
+
+    fn configuration_schema(&self) -> Option<ConfigurationSchema> {
+        Some(
+            ConfigurationSchema::new("greeting").with_key(
+                ConfigurationKey::new("message", ConfigurationType::String)
+                    .with_default(Configuration::String("Hello from a plugin".to_string())),
+            ),
+        )
+    }
+
+    fn load(&self, host: &PluginHost<'_>) -> anyhow::Result<()> {
+        // the plugin drives its own tokio runtime, apart from the one of the host
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+        runtime.block_on(async { tokio::time::sleep(std::time::Duration::from_millis(1)).await });
+        let message = host
+            .configuration("greeting.message")?
+            .and_then(|message| message.as_str().map(ToString::to_string))
+            .context("no greeting configured")?;
+        host.register_string("greeting", &message)
+    }
+
+    fn health(&self) -> AdapterHealth {
+        AdapterHealth::Degraded {
+            reason: "loaded from a plugin".to_string(),
+        }
+    }
+}
+
+declare_adapter_plugin!(GreetingAdapter::default);
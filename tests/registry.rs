@@ -0,0 +1,130 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use fototra::{
+    adapters::repository::in_memory::{
+        InMemoryRepository, user_repository::InMemoryUserRepository,
+    },
+    model::user::DEFAULT_ADMIN_USER,
+    repository::user_repository::UserRepository,
+    runtime::{Runtime, error::RuntimeError},
+};
+
+#[derive(Debug)]
+struct RequestContext {
+    number: usize,
+}
+
+pub async fn test_registry() {
+    let runtime = Runtime::get_instance();
+
+    // a read replica registered next to the primary repository
+    let replica = InMemoryRepository::new();
+    runtime
+        .register_named(
+            "replica",
+            UserRepository::new(Arc::new(InMemoryUserRepository::new(
+                replica.get_database(),
+            ))),
+        )
+        .await
+        .unwrap();
+    let primary = runtime.get::<UserRepository>().await.unwrap();
+    let replica = runtime
+        .get_named::<UserRepository>("replica")
+        .await
+        .unwrap();
+    primary
+        .find_by_id(DEFAULT_ADMIN_USER.get_id())
+        .await
+        .unwrap();
+    replica
+        .find_by_id(DEFAULT_ADMIN_USER.get_id())
+        .await
+        .unwrap_err();
+    assert!(
+        runtime
+            .get_named::<UserRepository>("tenant")
+            .await
+            .is_none()
+    );
+
+    // registering again can be refused
+    let Err(RuntimeError::AlreadyRegistered { service, name }) = runtime
+        .try_register(UserRepository::new(Arc::new(InMemoryUserRepository::new(
+            InMemoryRepository::new().get_database(),
+        ))))
+        .await
+    else {
+        panic!("the user repository is already registered");
+    };
+    assert!(service.ends_with("UserRepository"));
+    assert_eq!(name, None);
+    assert!(matches!(
+        runtime.try_register_named("replica", 0_u8).await,
+        Ok(())
+    ));
+    assert!(matches!(
+        runtime.try_register_named("replica", 1_u8).await,
+        Err(RuntimeError::AlreadyRegistered { name: Some(name), .. }) if name == "replica"
+    ));
+    let held = runtime.get_named::<u8>("replica").await.unwrap();
+    assert_eq!(*held, 0);
+    // register replaces it for the next get only
+    runtime.register_named("replica", 2_u8).await.unwrap();
+    assert_eq!(*runtime.get_named::<u8>("replica").await.unwrap(), 2);
+    assert_eq!(*held, 0);
+
+    // a factory runs on the first get only
+    let created = Arc::new(AtomicUsize::new(0));
+    let counter = created.clone();
+    runtime
+        .register_factory(move || {
+            let number = counter.fetch_add(1, Ordering::SeqCst);
+            async move { RequestContext { number } }
+        })
+        .await
+        .unwrap();
+    assert_eq!(created.load(Ordering::SeqCst), 0);
+    let first = runtime.get::<RequestContext>().await.unwrap();
+    let second = runtime.get::<RequestContext>().await.unwrap();
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!(created.load(Ordering::SeqCst), 1);
+
+    // a scoped service is shared inside a request only
+    let counter = created.clone();
+    runtime
+        .register_scoped_named("request", move || {
+            let number = counter.fetch_add(1, Ordering::SeqCst);
+            async move { RequestContext { number } }
+        })
+        .await
+        .unwrap();
+    let mut numbers = Vec::new();
+    for _ in 0..2 {
+        let (first, second) = runtime
+            .request_scope(async {
+                (
+                    Runtime::get_instance()
+                        .get_named::<RequestContext>("request")
+                        .await
+                        .unwrap(),
+                    Runtime::get_instance()
+                        .get_named::<RequestContext>("request")
+                        .await
+                        .unwrap(),
+                )
+            })
+            .await;
+        assert!(Arc::ptr_eq(&first, &second));
+        numbers.push(first.number);
+    }
+    assert_eq!(numbers, [1, 2]);
+    let outside = runtime
+        .get_named::<RequestContext>("request")
+        .await
+        .unwrap();
+    assert_eq!(outside.number, 3);
+}
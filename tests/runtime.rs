@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use fototra::{
+    adapters::repository::in_memory::InMemoryRepository,
+    dtos::{
+        find_request::FindRequest,
+        user::{user_add_request::UserAddRequest, user_find_request_filter::UserFindRequestFilter},
+    },
+    model::user::{DEFAULT_ADMIN_USER, name::Name},
+    runtime::Runtime,
+    service::user::UserService,
+    traits::find_result_trait::FindResultTrait,
+};
+
+use crate::common::UserToken;
+
+pub async fn test_runtime_isolation() {
+    let admin = UserToken {
+        user_id: *DEFAULT_ADMIN_USER.get_id(),
+    };
+    let mut runtimes = Vec::new();
+    for _ in 0..2 {
+        let runtime = Runtime::new();
+        runtime.init().await.unwrap();
+        runtime
+            .add_adapter(Arc::new(InMemoryRepository::new()))
+            .await
+            .unwrap();
+        runtime.start().await.unwrap();
+        runtimes.push(runtime);
+    }
+
+    // a user created in a runtime is unknown to the other one
+    let user = runtimes[0]
+        .scope(UserService::create(
+            &admin,
+            &UserAddRequest::new(&Name::new("Isolated").unwrap(), None),
+        ))
+        .await
+        .unwrap();
+    runtimes[1]
+        .scope(UserService::find_one(&admin, user.get_id()))
+        .await
+        .unwrap_err();
+    runtimes[0]
+        .scope(UserService::find_one(&admin, user.get_id()))
+        .await
+        .unwrap();
+
+    // each runtime holds its own default admin
+    let find_request = FindRequest::<UserFindRequestFilter>::default();
+    for (runtime, count) in runtimes.iter().zip([2, 1]) {
+        let users = runtime
+            .scope(UserService::find(&admin, &find_request))
+            .await
+            .unwrap();
+        assert_eq!(users.get_result().count(), count);
+    }
+
+    // outside of any scope the global runtime is used
+    runtimes[0].register_named("isolation", 0_u8).await.unwrap();
+    assert!(
+        Runtime::get_instance()
+            .get_named::<u8>("isolation")
+            .await
+            .is_none()
+    );
+    assert!(
+        runtimes[0]
+            .scope(async { Runtime::get_instance().get_named::<u8>("isolation").await })
+            .await
+            .is_some()
+    );
+}
@@ -0,0 +1,178 @@
+use fototra::{
+    dtos::{
+        find_request::FindRequest,
+        user_internet::user_internet_find_request_filter::UserInternetFindRequestFilter,
+    },
+    model::{
+        email_address::EmailAddress,
+        user::{User, error::UserError, name::Name},
+        user_internet::UserInternet,
+        user_permission::UserPermission,
+    },
+    repository::{
+        transaction::error::TransactionError, unit_of_work::UnitOfWork,
+        user_internet_repository::UserInternetRepository, user_repository::UserRepository,
+    },
+    runtime::Runtime,
+    traits::find_result_trait::FindResultTrait,
+};
+use uuid::Uuid;
+
+pub async fn test_transactions() {
+    let unit_of_work = Runtime::get_instance().get::<UnitOfWork>().await.unwrap();
+    let users = Runtime::get_instance()
+        .get::<UserRepository>()
+        .await
+        .unwrap();
+    let user_internets = Runtime::get_instance()
+        .get::<UserInternetRepository>()
+        .await
+        .unwrap();
+    let email = EmailAddress::new("transaction@fototra.mg").unwrap();
+
+    // writes stay inside the transaction until committed
+    let transaction = unit_of_work.begin().await.unwrap();
+    let user = transaction
+        .get_user_repository()
+        .save(&User::new(
+            &Uuid::nil(),
+            &Name::new("Committed").unwrap(),
+            None,
+        ))
+        .await
+        .unwrap();
+    transaction
+        .get_user_internet_repository()
+        .save(&UserInternet::new(user.get_id(), &email))
+        .await
+        .unwrap();
+    transaction
+        .get_user_permission_repository()
+        .save(&UserPermission::new(user.get_id(), "user:find"))
+        .await
+        .unwrap();
+    assert!(matches!(
+        users.find_by_id(user.get_id()).await,
+        Err(UserError::UserNotExists { .. })
+    ));
+    transaction.commit().await.unwrap();
+    assert_eq!(users.find_by_id(user.get_id()).await.unwrap(), user);
+    assert!(matches!(
+        transaction.commit().await,
+        Err(TransactionError::AlreadyFinished)
+    ));
+
+    // a failing step is undone with everything before it
+    let transaction = unit_of_work.begin().await.unwrap();
+    let rolled_back = transaction
+        .get_user_repository()
+        .save(&User::new(
+            &Uuid::nil(),
+            &Name::new("RolledBack").unwrap(),
+            None,
+        ))
+        .await
+        .unwrap();
+    transaction
+        .get_user_internet_repository()
+        .delete(&(*user.get_id(), email.clone()))
+        .await
+        .unwrap();
+    transaction
+        .get_user_permission_repository()
+        .save(&UserPermission::new(
+            rolled_back.get_id(),
+            "unknown:permission",
+        ))
+        .await
+        .unwrap_err();
+    transaction.rollback().await.unwrap();
+    assert!(users.find_by_id(rolled_back.get_id()).await.is_err());
+    user_internets
+        .delete(&(*user.get_id(), email.clone()))
+        .await
+        .unwrap();
+
+    // dropping an unfinished transaction discards it as well
+    let transaction = unit_of_work.begin().await.unwrap();
+    transaction
+        .get_user_repository()
+        .delete(user.get_id())
+        .await
+        .unwrap();
+    drop(transaction);
+    assert!(users.find_by_id(user.get_id()).await.is_ok());
+
+    // two transactions taking the same email: the second commit fails with nothing written
+    let email = EmailAddress::new("race@fototra.mg").unwrap();
+    let first = unit_of_work.begin().await.unwrap();
+    let second = unit_of_work.begin().await.unwrap();
+    let mut racers = Vec::new();
+    for transaction in [&first, &second] {
+        let racer = transaction
+            .get_user_repository()
+            .save(&User::new(&Uuid::nil(), &Name::new("Racer").unwrap(), None))
+            .await
+            .unwrap();
+        transaction
+            .get_user_internet_repository()
+            .save(&UserInternet::new(racer.get_id(), &email))
+            .await
+            .unwrap();
+        racers.push(racer);
+    }
+    first.commit().await.unwrap();
+    assert!(matches!(
+        second.commit().await,
+        Err(TransactionError::Conflict { .. })
+    ));
+    assert!(users.find_by_id(racers[1].get_id()).await.is_err());
+
+    // a row changed by another writer since the transaction began is a conflict too
+    let transaction = unit_of_work.begin().await.unwrap();
+    transaction
+        .get_user_internet_repository()
+        .delete(&(*racers[0].get_id(), email.clone()))
+        .await
+        .unwrap();
+    user_internets
+        .delete(&(*racers[0].get_id(), email.clone()))
+        .await
+        .unwrap();
+    assert!(matches!(
+        transaction.commit().await,
+        Err(TransactionError::Conflict { .. })
+    ));
+
+    // rows staged for a user deleted meanwhile would be orphans: the commit fails
+    let orphan = EmailAddress::new("orphan@fototra.mg").unwrap();
+    let transaction = unit_of_work.begin().await.unwrap();
+    transaction
+        .get_user_internet_repository()
+        .save(&UserInternet::new(racers[0].get_id(), &orphan))
+        .await
+        .unwrap();
+    transaction
+        .get_user_permission_repository()
+        .save(&UserPermission::new(racers[0].get_id(), "user:find"))
+        .await
+        .unwrap();
+    users.delete(racers[0].get_id()).await.unwrap();
+    assert!(matches!(
+        transaction.commit().await,
+        Err(TransactionError::Conflict { .. })
+    ));
+    let filter = UserInternetFindRequestFilter {
+        email: Some(orphan.to_string()),
+        ..Default::default()
+    };
+    assert_eq!(
+        user_internets
+            .find_all(&FindRequest::new(&filter, "", &1, &1).unwrap())
+            .await
+            .unwrap()
+            .get_result()
+            .count(),
+        0
+    );
+}
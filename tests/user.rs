@@ -9,9 +9,15 @@ use fototra::{
             user_update_request::UserUpdateRequest,
         },
     },
-    model::user::{error::UserError, name::Name},
+    model::{
+        permission_check::DenialReason,
+        user::{error::UserError, name::Name},
+    },
     security::error::SecurityError,
-    service::{error::ServiceError, user::UserService},
+    service::{
+        error::{ErrorCategory, ServiceError},
+        user::UserService,
+    },
     traits::{
         authentication_trait::AuthenticationTrait, authorization_trait::AuthorizationTrait,
         find_result_trait::FindResultTrait,
@@ -36,7 +42,7 @@ impl AuthorizationTrait for Entity {
             if self.authorized {
                 return Ok(());
             } else {
-                return Err(SecurityError::NotAuthorized);
+                return Err(SecurityError::not_authorized(DenialReason::NoGrant));
             }
         })
     }
@@ -76,14 +82,8 @@ pub async fn test_users() {
     let created_user = UserService::create(&token, &user_add_request)
         .await
         .map_err(|e| {
-            let mut msg = String::new();
-            if let Some(error) = e.get::<SecurityError>() {
-                msg = error.to_string();
-            } else if let Some(error) = e.get::<UserError>() {
-                msg = error.to_string();
-            }
-            println!("error message: {}", msg);
-            msg
+            println!("error message: {}", e);
+            e.to_string()
         })
         .unwrap();
 
@@ -120,6 +120,21 @@ pub async fn test_users() {
         )
     );
 
+    assert_eq!(
+        user_mismatch_id_err.get_category(),
+        ErrorCategory::Validation
+    );
+    assert_eq!(user_mismatch_id_err.get_code(), "user.mismatch_user_id");
+    assert_eq!(
+        serde_json::to_value(&user_mismatch_id_err).unwrap(),
+        serde_json::json!({
+            "category": "validation",
+            "code": "user.mismatch_user_id",
+            "message": user_mismatch_id_err.to_string(),
+            "sources": [],
+        })
+    );
+
     // find user
     let find_request = FindRequest::<UserFindRequestFilter>::default();
     let user_list = UserService::find(&token, &find_request).await.unwrap();
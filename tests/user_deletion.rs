@@ -0,0 +1,259 @@
+use std::{collections::HashMap, sync::Arc};
+
+use fototra::{
+    adapters::repository::in_memory::{
+        InMemoryRepository, permission_repository::InMemoryPermissionRepository,
+        user_internet_repository::InMemoryUserInternetRepository,
+        user_password_repository::InMemoryUserPasswordRepository,
+        user_permission_repository::InMemoryUserPermissionRepository,
+        user_repository::InMemoryUserRepository,
+    },
+    configuration::Configuration,
+    dtos::{
+        find_request::FindRequest,
+        user::{
+            user_add_request::UserAddRequest, user_delete_request::UserDeleteRequest,
+            user_onboard_request::UserOnboardRequest,
+        },
+        user_internet::{
+            user_internet_add_request::UserInternetAddRequest,
+            user_internet_find_request_filter::UserInternetFindRequestFilter,
+        },
+    },
+    model::{
+        email_address::EmailAddress,
+        user::{DEFAULT_ADMIN_USER, error::UserError, name::Name},
+    },
+    repository::{
+        permission_repository::PermissionRepository,
+        user_internet_repository::UserInternetRepository,
+        user_password_repository::UserPasswordRepository,
+        user_permission_repository::UserPermissionRepository, user_repository::UserRepository,
+    },
+    runtime::Runtime,
+    service::{error::ErrorCategory, user::UserService, user_internet::UserInternetService},
+    traits::find_result_trait::FindResultTrait,
+};
+
+use crate::common::UserToken;
+
+fn with_delete_policy(configuration: &Configuration, policy: &str) -> Configuration {
+    configuration.merge(&Configuration::Map(HashMap::from([(
+        "user".to_string(),
+        Configuration::Map(HashMap::from([(
+            "delete_policy".to_string(),
+            Configuration::String(policy.to_string()),
+        )])),
+    )])))
+}
+
+pub async fn test_user_deletion() {
+    let admin = UserToken {
+        user_id: *DEFAULT_ADMIN_USER.get_id(),
+    };
+    let configuration = Runtime::get_instance()
+        .get::<Configuration>()
+        .await
+        .unwrap();
+
+    // cascade removes and reports every dependent
+    let onboarded = UserService::onboard(
+        &admin,
+        &UserOnboardRequest::new(
+            "Cascade",
+            None,
+            &["cascade@fototra.mg", "cascade2@fototra.mg"],
+            "secret123",
+            &["user:find"],
+        ),
+    )
+    .await
+    .unwrap();
+    let user_id = onboarded.get_user().get_id();
+    let deleted = UserService::delete(&admin, &UserDeleteRequest::new(user_id))
+        .await
+        .unwrap();
+    assert_eq!(deleted.get_user_id(), user_id);
+    let mut emails = onboarded.get_emails().to_vec();
+    emails.sort();
+    assert_eq!(deleted.get_emails(), emails);
+    assert_eq!(deleted.get_permissions(), onboarded.get_permissions());
+    assert!(deleted.has_password());
+    UserService::find_one(&admin, user_id).await.unwrap_err();
+    UserService::onboard(
+        &admin,
+        &UserOnboardRequest::new("Again", None, &["cascade@fototra.mg"], "secret123", &[]),
+    )
+    .await
+    .unwrap();
+
+    // restrict refuses while dependents exist
+    Runtime::get_instance()
+        .register(with_delete_policy(&configuration, "restrict"))
+        .await
+        .unwrap();
+    let onboarded = UserService::onboard(
+        &admin,
+        &UserOnboardRequest::new("Restrict", None, &["restrict@fototra.mg"], "secret123", &[]),
+    )
+    .await
+    .unwrap();
+    let user_id = onboarded.get_user().get_id();
+    let e = UserService::delete(&admin, &UserDeleteRequest::new(user_id))
+        .await
+        .unwrap_err();
+    assert_eq!(e.get_category(), ErrorCategory::Conflict);
+    assert!(matches!(
+        e.get::<UserError>().as_deref(),
+        Some(UserError::HasDependents {
+            emails: 1,
+            permissions: 0,
+            password: true,
+            ..
+        })
+    ));
+    UserService::find_one(&admin, user_id).await.unwrap();
+    let lonely = UserService::create(
+        &admin,
+        &UserAddRequest::new(&Name::new("Lonely").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    let deleted = UserService::delete(&admin, &UserDeleteRequest::new(lonely.get_id()))
+        .await
+        .unwrap();
+    assert!(deleted.get_emails().is_empty() && !deleted.has_password());
+
+    // an unknown policy is a configuration error, not a fallback
+    Runtime::get_instance()
+        .register(with_delete_policy(&configuration, "restrcit"))
+        .await
+        .unwrap();
+    let typo = UserService::create(
+        &admin,
+        &UserAddRequest::new(&Name::new("Typo").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    let e = UserService::delete(&admin, &UserDeleteRequest::new(typo.get_id()))
+        .await
+        .unwrap_err();
+    assert_eq!(e.get_category(), ErrorCategory::Validation);
+    assert_eq!(e.get_code(), "user.invalid_delete_policy");
+    assert!(e.to_string().contains("user.delete_policy"), "{e}");
+    assert_eq!(e.get_sources().len(), 1, "{:?}", e.get_sources());
+    assert!(!e.get_message().contains(&e.get_sources()[0]), "{e}");
+    UserService::find_one(&admin, typo.get_id()).await.unwrap();
+
+    Runtime::get_instance()
+        .register((*configuration).clone())
+        .await
+        .unwrap();
+    UserService::delete(&admin, &UserDeleteRequest::new(typo.get_id()))
+        .await
+        .unwrap();
+}
+
+/// Without a unit of work the cascade is refused rather than left half done
+pub async fn test_user_deletion_without_unit_of_work() {
+    let admin = UserToken {
+        user_id: *DEFAULT_ADMIN_USER.get_id(),
+    };
+    let runtime = Runtime::get_instance();
+    let adapter = InMemoryRepository::new();
+    let database = adapter.get_database();
+    runtime
+        .register(PermissionRepository::new(Arc::new(
+            InMemoryPermissionRepository::new(database),
+        )))
+        .await
+        .unwrap();
+    runtime
+        .register(UserRepository::new(Arc::new(InMemoryUserRepository::new(
+            database,
+        ))))
+        .await
+        .unwrap();
+    runtime
+        .register(UserInternetRepository::new(Arc::new(
+            InMemoryUserInternetRepository::new(database),
+        )))
+        .await
+        .unwrap();
+    runtime
+        .register(UserPermissionRepository::new(Arc::new(
+            InMemoryUserPermissionRepository::new(database),
+        )))
+        .await
+        .unwrap();
+    runtime
+        .register(UserPasswordRepository::new(Arc::new(
+            InMemoryUserPasswordRepository::new(database),
+        )))
+        .await
+        .unwrap();
+    runtime
+        .get::<PermissionRepository>()
+        .await
+        .unwrap()
+        .initialize()
+        .await
+        .unwrap();
+    runtime
+        .get::<UserRepository>()
+        .await
+        .unwrap()
+        .initialize()
+        .await
+        .unwrap();
+    runtime
+        .get::<UserPermissionRepository>()
+        .await
+        .unwrap()
+        .initialize()
+        .await
+        .unwrap();
+
+    let user = UserService::create(
+        &admin,
+        &UserAddRequest::new(&Name::new("Dependent").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    let email = EmailAddress::new("dependent@fototra.mg").unwrap();
+    let user_internet =
+        UserInternetService::create(&admin, &UserInternetAddRequest::new(user.get_id(), &email))
+            .await
+            .unwrap();
+    let e = UserService::delete(&admin, &UserDeleteRequest::new(user.get_id()))
+        .await
+        .unwrap_err();
+    assert_eq!(e.get_code(), "transaction.not_supported");
+    UserService::find_one(&admin, user.get_id()).await.unwrap();
+    let filter = UserInternetFindRequestFilter {
+        user_id: Some(*user.get_id()),
+        ..Default::default()
+    };
+    let emails = runtime
+        .get::<UserInternetRepository>()
+        .await
+        .unwrap()
+        .find_all(&FindRequest::new(&filter, "", &25, &1).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(emails.get_result().collect::<Vec<_>>(), vec![user_internet]);
+
+    // a user without dependents is a single write, safe without a transaction
+    let lonely = UserService::create(
+        &admin,
+        &UserAddRequest::new(&Name::new("Lonely").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    UserService::delete(&admin, &UserDeleteRequest::new(lonely.get_id()))
+        .await
+        .unwrap();
+    UserService::find_one(&admin, lonely.get_id())
+        .await
+        .unwrap_err();
+}
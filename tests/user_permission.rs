@@ -0,0 +1,326 @@
+use chrono::{Duration, Utc};
+use fototra::{
+    dtos::{
+        find_request::FindRequest,
+        user::{user_add_request::UserAddRequest, user_delete_request::UserDeleteRequest},
+        user_permission::{
+            user_permission_add_request::UserPermissionAddRequest,
+            user_permission_delete_request::UserPermissionDeleteRequest,
+            user_permission_find_request_filter::UserPermissionFindRequestFilter,
+        },
+    },
+    model::{
+        effective_permission::PermissionSource,
+        permission_check::{DenialReason, PermissionDecision},
+        user::{DEFAULT_ADMIN_USER, name::Name},
+        user_permission::error::UserPermissionError,
+    },
+    security::error::SecurityError,
+    service::{user::UserService, user_permission::UserPermissionService},
+    traits::{
+        authentication_trait::AuthenticationTrait, find_result_trait::FindResultTrait,
+        service_error_trait::ServiceErrorTrait,
+    },
+};
+use uuid::Uuid;
+
+use crate::common::UserToken;
+
+pub async fn test_user_permissions() {
+    let admin = UserToken {
+        user_id: *DEFAULT_ADMIN_USER.get_id(),
+    };
+    let user = UserService::create(
+        &admin,
+        &UserAddRequest::new(&Name::new("Soa").unwrap(), None),
+    )
+    .await
+    .unwrap();
+
+    UserPermissionService::create(
+        &admin,
+        &UserPermissionAddRequest::new(user.get_id(), "user:*"),
+    )
+    .await
+    .unwrap();
+    UserPermissionService::create(
+        &admin,
+        &UserPermissionAddRequest::new(user.get_id(), "user:find"),
+    )
+    .await
+    .unwrap();
+
+    // a wildcard must cover at least one known permission
+    let unknown_wildcard_err = UserPermissionService::create(
+        &admin,
+        &UserPermissionAddRequest::new(user.get_id(), "unknown:*"),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(
+        unknown_wildcard_err.get::<UserPermissionError>().as_deref(),
+        Some(UserPermissionError::PermissionNotExists { .. })
+    ));
+
+    // effective permissions with their provenance
+    let effective = UserPermissionService::find_effective(&admin, user.get_id())
+        .await
+        .unwrap();
+    assert_eq!(
+        effective
+            .iter()
+            .map(|p| p.get_permission())
+            .collect::<Vec<_>>(),
+        vec![
+            "user:create",
+            "user:deactivate",
+            "user:delete",
+            "user:find",
+            "user:find_one",
+            "user:onboard",
+            "user:reactivate",
+            "user:restore",
+            "user:soft_delete",
+            "user:suspend",
+            "user:update"
+        ]
+    );
+    let find = effective
+        .iter()
+        .find(|p| p.get_permission() == "user:find")
+        .unwrap();
+    assert_eq!(
+        find.get_sources(),
+        &[
+            PermissionSource::Direct,
+            PermissionSource::Wildcard {
+                pattern: "user:*".to_string()
+            }
+        ]
+    );
+
+    // dry-run checks
+    let check = UserPermissionService::check(&admin, user.get_id(), "user:delete")
+        .await
+        .unwrap();
+    assert!(check.is_allowed());
+    let check = UserPermissionService::check(&admin, user.get_id(), "user_permission:delete")
+        .await
+        .unwrap();
+    assert_eq!(
+        check.get_decision(),
+        &PermissionDecision::Denied {
+            reason: DenialReason::NoGrant
+        }
+    );
+    let check = UserPermissionService::check(&admin, user.get_id(), "unknown:permission")
+        .await
+        .unwrap();
+    assert_eq!(
+        check.get_decision(),
+        &PermissionDecision::Denied {
+            reason: DenialReason::UnknownPermission
+        }
+    );
+
+    // the authorizer agrees with the dry-run
+    let user_token = UserToken {
+        user_id: *user.get_id(),
+    };
+    let authorizable = user_token.authenticate().await.unwrap();
+    assert!(authorizable.authorize("user:delete").await.is_ok());
+    assert!(matches!(
+        authorizable.authorize("user_permission:delete").await,
+        Err(SecurityError::NotAuthorized {
+            reason: DenialReason::NoGrant,
+            ..
+        })
+    ));
+
+    // services report the denied permission and principal
+    let denied_err = UserPermissionService::delete(
+        &user_token,
+        &UserPermissionDeleteRequest::new(user.get_id(), "user:find"),
+    )
+    .await
+    .unwrap_err();
+    let denied = denied_err.get::<SecurityError>().unwrap();
+    assert_eq!(denied.get_permission(), Some("user_permission:delete"));
+    assert_eq!(denied.get_principal_id(), Some(user.get_id()));
+    assert_eq!(denied.get_reason(), Some(&DenialReason::NoGrant));
+    assert_eq!(denied.code(), "security.not_authorized.no_grant");
+
+    // a deny grant wins over the wildcard covering the permission
+    UserPermissionService::create(
+        &admin,
+        &UserPermissionAddRequest::new_deny(user.get_id(), "user:delete"),
+    )
+    .await
+    .unwrap();
+    let check = UserPermissionService::check(&admin, user.get_id(), "user:delete")
+        .await
+        .unwrap();
+    assert_eq!(
+        check.get_decision(),
+        &PermissionDecision::Denied {
+            reason: DenialReason::ExplicitDeny
+        }
+    );
+    let explicit_deny = authorizable.authorize("user:delete").await.unwrap_err();
+    assert_eq!(
+        explicit_deny.code(),
+        "security.not_authorized.explicit_deny"
+    );
+    assert!(
+        !UserPermissionService::find_effective(&admin, user.get_id())
+            .await
+            .unwrap()
+            .iter()
+            .any(|p| p.get_permission() == "user:delete")
+    );
+    UserPermissionService::delete(
+        &admin,
+        &UserPermissionDeleteRequest::new(user.get_id(), "user:delete"),
+    )
+    .await
+    .unwrap();
+    assert!(authorizable.authorize("user:delete").await.is_ok());
+
+    // time-bound grants
+    let now = Utc::now();
+    UserPermissionService::create(
+        &admin,
+        &UserPermissionAddRequest::new_time_bound(
+            user.get_id(),
+            "user_internet:find",
+            Some(&(now - Duration::hours(2))),
+            Some(&(now - Duration::hours(1))),
+        ),
+    )
+    .await
+    .unwrap();
+    UserPermissionService::create(
+        &admin,
+        &UserPermissionAddRequest::new_time_bound(
+            user.get_id(),
+            "user_internet:delete",
+            Some(&(now + Duration::hours(1))),
+            None,
+        ),
+    )
+    .await
+    .unwrap();
+    let invalid_period_err = UserPermissionService::create(
+        &admin,
+        &UserPermissionAddRequest::new_time_bound(
+            user.get_id(),
+            "user_internet:create",
+            Some(&now),
+            Some(&(now - Duration::hours(1))),
+        ),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(
+        invalid_period_err.get::<UserPermissionError>().as_deref(),
+        Some(UserPermissionError::InvalidValidityPeriod { .. })
+    ));
+
+    let check = UserPermissionService::check(&admin, user.get_id(), "user_internet:find")
+        .await
+        .unwrap();
+    assert_eq!(
+        check.get_decision(),
+        &PermissionDecision::Denied {
+            reason: DenialReason::Expired
+        }
+    );
+    let check = UserPermissionService::check(&admin, user.get_id(), "user_internet:delete")
+        .await
+        .unwrap();
+    assert_eq!(
+        check.get_decision(),
+        &PermissionDecision::Denied {
+            reason: DenialReason::NotYetValid
+        }
+    );
+    assert!(authorizable.authorize("user_internet:find").await.is_err());
+
+    // expired grants are filtered out unless asked for
+    let mut filter = UserPermissionFindRequestFilter {
+        user_id: Some(*user.get_id()),
+        ..Default::default()
+    };
+    let grants = UserPermissionService::find(
+        &admin,
+        &FindRequest::new(&filter, "permission", &25, &1).unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(grants.get_result().count(), 3);
+    filter.include_expired = true;
+    let grants = UserPermissionService::find(
+        &admin,
+        &FindRequest::new(&filter, "permission", &25, &1).unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(grants.get_result().count(), 4);
+
+    // an expired grant is not replaced silently by granting the permission again
+    let regranted = UserPermissionService::create(
+        &admin,
+        &UserPermissionAddRequest::new(user.get_id(), "user_internet:find"),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(
+        regranted.get::<UserPermissionError>().as_deref(),
+        Some(UserPermissionError::PermissionAlreadyAssigned { .. })
+    ));
+
+    // the sweep removes expired grants only
+    let purged = UserPermissionService::purge_expired(&admin).await.unwrap();
+    assert_eq!(purged.len(), 1);
+    assert_eq!(purged[0].get_permission(), "user_internet:find");
+    let grants = UserPermissionService::find(
+        &admin,
+        &FindRequest::new(&filter, "permission", &25, &1).unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(grants.get_result().count(), 3);
+    UserPermissionService::create(
+        &admin,
+        &UserPermissionAddRequest::new(user.get_id(), "user_internet:find"),
+    )
+    .await
+    .unwrap();
+
+    // unknown user
+    let unknown_user_err = UserPermissionService::check(&admin, &Uuid::new_v4(), "user:find")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        unknown_user_err.get::<UserPermissionError>().as_deref(),
+        Some(UserPermissionError::UserNotExists { .. })
+    ));
+
+    // clean up
+    for permission in [
+        "user:*",
+        "user:find",
+        "user_internet:find",
+        "user_internet:delete",
+    ] {
+        UserPermissionService::delete(
+            &admin,
+            &UserPermissionDeleteRequest::new(user.get_id(), permission),
+        )
+        .await
+        .unwrap();
+    }
+    UserService::delete(&admin, &UserDeleteRequest::new(user.get_id()))
+        .await
+        .unwrap();
+}
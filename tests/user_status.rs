@@ -0,0 +1,154 @@
+use fototra::{
+    dtos::{
+        find_request::FindRequest,
+        user::{
+            user_add_request::UserAddRequest, user_find_request_filter::UserFindRequestFilter,
+            user_status_change_request::UserStatusChangeRequest,
+            user_update_request::UserUpdateRequest,
+        },
+        user_password::user_password_add_request::UserPasswordAddRequest,
+        user_permission::user_permission_add_request::UserPermissionAddRequest,
+    },
+    model::user::{DEFAULT_ADMIN_USER, UserID, name::Name, user_status::UserStatus},
+    runtime::Runtime,
+    service::{
+        error::ErrorCategory, user::UserService, user_password::UserPasswordService,
+        user_permission::UserPermissionService,
+    },
+    traits::find_result_trait::FindResultTrait,
+};
+
+use uuid::Uuid;
+
+use crate::common::UserToken;
+
+fn find_request(user_id: &UserID, include_deleted: bool) -> FindRequest<UserFindRequestFilter> {
+    let filter = UserFindRequestFilter {
+        id: Some(*user_id),
+        include_deleted,
+        ..Default::default()
+    };
+    FindRequest::new(&filter, "", &25, &1).unwrap()
+}
+
+pub async fn test_user_status() {
+    let admin = UserToken {
+        user_id: *DEFAULT_ADMIN_USER.get_id(),
+    };
+    let user = UserService::create(
+        &admin,
+        &UserAddRequest::new(&Name::new("Status").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    assert_eq!(user.get_status(), UserStatus::Active);
+    UserPermissionService::create(
+        &admin,
+        &UserPermissionAddRequest::new(user.get_id(), "user:find"),
+    )
+    .await
+    .unwrap();
+    UserPasswordService::create(
+        &admin,
+        &UserPasswordAddRequest::new(user.get_id(), "secret123"),
+    )
+    .await
+    .unwrap();
+    let token = UserToken {
+        user_id: *user.get_id(),
+    };
+    let status_request = UserStatusChangeRequest::new(user.get_id(), Some("Unpaid invoice"));
+    UserService::find(&token, &find_request(user.get_id(), false))
+        .await
+        .unwrap();
+
+    // a suspended user is refused, even if granted
+    let suspended = UserService::suspend(&admin, &status_request).await.unwrap();
+    assert_eq!(suspended.get_status(), UserStatus::Suspended);
+    assert_eq!(suspended.get_status_reason(), Some("Unpaid invoice"));
+    assert!(suspended.get_status_changed_at().is_some());
+    let e = UserService::find(&token, &find_request(user.get_id(), false))
+        .await
+        .unwrap_err();
+    assert_eq!(e.get_category(), ErrorCategory::Auth);
+    assert_eq!(e.get_code(), "security.not_authorized.locked");
+    let e = UserPasswordService::match_user_password(&admin, user.get_id(), "secret123")
+        .await
+        .unwrap_err();
+    assert_eq!(e.get_category(), ErrorCategory::Auth);
+    assert_eq!(e.get_code(), "user_password.user_not_active");
+    let e = UserService::suspend(&admin, &status_request)
+        .await
+        .unwrap_err();
+    assert_eq!(e.get_code(), "user.invalid_status_transition");
+
+    // renaming keeps the status
+    let renamed = UserService::update(
+        &admin,
+        user.get_id(),
+        &UserUpdateRequest::new(user.get_id(), &Name::new("Renamed").unwrap(), None),
+    )
+    .await
+    .unwrap();
+    assert_eq!(renamed.get_status(), UserStatus::Suspended);
+
+    let reactivated =
+        UserService::reactivate(&admin, &UserStatusChangeRequest::new(user.get_id(), None))
+            .await
+            .unwrap();
+    assert!(reactivated.is_active());
+    assert_eq!(reactivated.get_status_reason(), None);
+    UserService::find(&token, &find_request(user.get_id(), false))
+        .await
+        .unwrap();
+    UserPasswordService::match_user_password(&admin, user.get_id(), "secret123")
+        .await
+        .unwrap();
+
+    // a principal that is not a known user is refused
+    let e = UserService::find(
+        &UserToken {
+            user_id: Uuid::new_v4(),
+        },
+        &find_request(user.get_id(), false),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(e.get_code(), "security.not_authenticated");
+
+    // without a user repository to check the principal, nothing is authorized
+    let e = Runtime::new()
+        .scope(UserService::find(
+            &admin,
+            &find_request(user.get_id(), false),
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(e.get_code(), "security.not_authorized.unavailable");
+
+    // soft-deleted users are kept but hidden from searches
+    UserService::soft_delete(&admin, &status_request)
+        .await
+        .unwrap();
+    let found = UserService::find(&admin, &find_request(user.get_id(), false))
+        .await
+        .unwrap();
+    assert_eq!(found.get_result().count(), 0);
+    let found = UserService::find(&admin, &find_request(user.get_id(), true))
+        .await
+        .unwrap();
+    assert_eq!(
+        found.get_result().next().unwrap().get_status(),
+        UserStatus::Deleted
+    );
+    let e = UserService::reactivate(&admin, &status_request)
+        .await
+        .unwrap_err();
+    assert_eq!(e.get_category(), ErrorCategory::Conflict);
+    let e = UserPasswordService::match_user_password(&admin, user.get_id(), "secret123")
+        .await
+        .unwrap_err();
+    assert_eq!(e.get_code(), "user_password.user_not_active");
+    let restored = UserService::restore(&admin, &status_request).await.unwrap();
+    assert!(restored.is_active());
+}